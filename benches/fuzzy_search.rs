@@ -0,0 +1,66 @@
+//! Benchmarks [crow::bench::fuzzy_search_commands] over synthetic databases of different sizes.
+//! Run with `cargo bench`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use crow::bench::{fuzzy_search_commands, CrowCommand, SearchWeights};
+
+const DB_SIZES: &[usize] = &[100, 1_000, 10_000];
+
+const QUERIES: &[&str] = &["", "git", "docker ps", "list running containers", "xyzxyz"];
+
+const COMMANDS: &[(&str, &str)] = &[
+    ("git cherry-pick", "apply the changes of an existing commit"),
+    ("docker ps -a", "list all running and stopped containers"),
+    ("kubectl get pods", "show pods of the current namespace"),
+    ("find . -name '*.rs'", "find all rust files"),
+    ("tar -xzf archive.tar.gz", "extract a gzipped tarball"),
+    ("rsync -avz src/ dest/", "sync directories"),
+    ("ssh -L 8080:localhost:80 host", "forward a local port"),
+    ("date '+%Y%m%d'", "print the current date"),
+];
+
+/// Creates a synthetic database with `size` commands, which are variations of [COMMANDS]
+fn synthetic_commands(size: usize) -> Vec<CrowCommand> {
+    (0..size)
+        .map(|i| {
+            let (command, description) = COMMANDS[i % COMMANDS.len()];
+
+            CrowCommand {
+                id: format!("command_{}", i),
+                command: format!("{} # {}", command, i),
+                description: format!("{} (variant {})", description, i),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+fn bench_fuzzy_search(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fuzzy_search_commands");
+
+    for size in DB_SIZES {
+        let commands = synthetic_commands(*size);
+
+        for query in QUERIES {
+            group.bench_with_input(
+                BenchmarkId::new(format!("{} commands", size), format!("'{}'", query)),
+                query,
+                |b, query| {
+                    b.iter(|| {
+                        fuzzy_search_commands(
+                            black_box(commands.clone()),
+                            query,
+                            &SearchWeights::default(),
+                        )
+                    })
+                },
+            );
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_fuzzy_search);
+criterion_main!(benches);
@@ -0,0 +1,304 @@
+//! Abstraction of the system clipboard so that copying commands can be tested and other clipboard
+//! backends can be plugged in.
+
+use copypasta::{ClipboardContext, ClipboardProvider};
+use std::fmt::{self, Debug};
+
+use crate::crow_commands::CrowCommand;
+
+/// The format in which a command is copied to the clipboard
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CopyFormat {
+    /// Only the command itself
+    Command,
+    /// The command wrapped inside a fenced markdown code block, optionally with the description
+    /// as a comment line above the command
+    CodeBlock { with_description: bool },
+    /// The id of the command (e.g. for `crow tag <id>`)
+    Id,
+    /// The command below its description as comment lines (`# <description>`), e.g. to paste it
+    /// into a script
+    WithDescription,
+}
+
+impl CopyFormat {
+    /// Formats the given command for copying
+    pub fn format(&self, command: &CrowCommand) -> String {
+        match self {
+            Self::Command => command.command.clone(),
+            Self::Id => command.id.clone(),
+            Self::WithDescription => {
+                let comment: String = command
+                    .description
+                    .trim()
+                    .lines()
+                    .map(|line| match line.trim_end() {
+                        "" => "#\n".to_string(),
+                        line => format!("# {}\n", line),
+                    })
+                    .collect();
+
+                format!("{}{}", comment, command.command)
+            }
+            Self::CodeBlock { with_description } => {
+                if *with_description && !command.description.trim().is_empty() {
+                    format!(
+                        "```\n# {}\n{}\n```",
+                        command.description.trim(),
+                        command.command
+                    )
+                } else {
+                    format!("```\n{}\n```", command.command)
+                }
+            }
+        }
+    }
+}
+
+/// Fills the placeholders `{command}`, `{description}`, `{id}` and `{tags}` (comma separated) of
+/// the given template (the `copy_template` config) with the values of the command. Other text,
+/// including unknown placeholders, is kept as it is. Placeholders inside the inserted values
+/// are not filled.
+pub fn apply_copy_template(template: &str, command: &CrowCommand) -> String {
+    let mut text = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let end = match rest.find('}') {
+            Some(end) => end,
+            None => break,
+        };
+
+        match &rest[1..end] {
+            "command" => text.push_str(&command.command),
+            "description" => text.push_str(command.description.trim()),
+            "id" => text.push_str(&command.id),
+            "tags" => text.push_str(&command.tags.join(", ")),
+            _ => {
+                // Keep the brace and continue behind it, so that e.g. `{{command}` still works
+                text.push('{');
+                rest = &rest[1..];
+                continue;
+            }
+        }
+
+        rest = &rest[end + 1..];
+    }
+
+    text.push_str(rest);
+    text
+}
+
+/// Removes shell comments (everything after an unquoted `#` which starts a word) from every line
+/// of the given command, so that annotations like `ls -la # show hidden files` are not pasted.
+pub fn strip_comments(command: &str) -> String {
+    command
+        .lines()
+        .map(strip_line_comment)
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+fn strip_line_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    let mut word_start = true;
+
+    for (i, c) in line.char_indices() {
+        let is_escaped = escaped;
+        escaped = false;
+
+        match quote {
+            _ if is_escaped => {}
+            // Backslashes don't escape anything inside single quotes
+            Some('\'') if c == '\'' => quote = None,
+            Some('\'') => {}
+            _ if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '\'' || c == '"' => quote = Some(c),
+            None if c == '#' && word_start => return line[..i].trim_end(),
+            None => {}
+        }
+
+        word_start = !is_escaped && quote.is_none() && c.is_whitespace();
+    }
+
+    line
+}
+
+/// A clipboard which commands can be copied to
+pub trait Clipboard: Debug {
+    /// Replaces the clipboard contents
+    fn set_contents(&mut self, contents: String) -> Result<(), String>;
+
+    /// Returns the current clipboard contents
+    fn get_contents(&mut self) -> Result<String, String>;
+}
+
+impl Default for Box<dyn Clipboard> {
+    fn default() -> Self {
+        Box::new(SystemClipboard::default())
+    }
+}
+
+/// The clipboard of the operating system.
+/// The clipboard context is only created on first use, because creating it might fail on systems
+/// without a clipboard (e.g. without a display server).
+#[derive(Default)]
+pub struct SystemClipboard {
+    context: Option<ClipboardContext>,
+}
+
+impl SystemClipboard {
+    fn context(&mut self) -> Result<&mut ClipboardContext, String> {
+        if self.context.is_none() {
+            let context = ClipboardContext::new()
+                .map_err(|e| format!("Could not create clipboard context. {}", e))?;
+            self.context = Some(context);
+        }
+
+        Ok(self
+            .context
+            .as_mut()
+            .expect("Clipboard context was just created"))
+    }
+}
+
+impl Clipboard for SystemClipboard {
+    fn set_contents(&mut self, contents: String) -> Result<(), String> {
+        self.context()?
+            .set_contents(contents)
+            .map_err(|e| e.to_string())
+    }
+
+    fn get_contents(&mut self) -> Result<String, String> {
+        self.context()?.get_contents().map_err(|e| e.to_string())
+    }
+}
+
+impl Debug for SystemClipboard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SystemClipboard")
+            .field("initialized", &self.context.is_some())
+            .finish()
+    }
+}
+
+/// In-memory clipboard which is used as a test double for the system clipboard
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct MemoryClipboard {
+    contents: String,
+}
+
+#[cfg(test)]
+impl Clipboard for MemoryClipboard {
+    fn set_contents(&mut self, contents: String) -> Result<(), String> {
+        self.contents = contents;
+        Ok(())
+    }
+
+    fn get_contents(&mut self) -> Result<String, String> {
+        Ok(self.contents.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crow_commands::CrowCommand;
+
+    use super::{apply_copy_template, strip_comments, CopyFormat};
+
+    #[test]
+    fn formats_command_as_code_block() {
+        let command = CrowCommand {
+            id: "1".to_string(),
+            command: "git status".to_string(),
+            description: "show repo status".to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(CopyFormat::Command.format(&command), "git status");
+        assert_eq!(CopyFormat::Id.format(&command), "1");
+        assert_eq!(
+            CopyFormat::CodeBlock {
+                with_description: false
+            }
+            .format(&command),
+            "```\ngit status\n```"
+        );
+        assert_eq!(
+            CopyFormat::CodeBlock {
+                with_description: true
+            }
+            .format(&command),
+            "```\n# show repo status\ngit status\n```"
+        );
+    }
+
+    #[test]
+    fn formats_command_with_description_as_comment() {
+        let mut command = CrowCommand {
+            id: "1".to_string(),
+            command: "git status".to_string(),
+            description: "show repo status\n\nincluding untracked files\n".to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            CopyFormat::WithDescription.format(&command),
+            "# show repo status\n#\n# including untracked files\ngit status"
+        );
+
+        command.description = " ".to_string();
+        assert_eq!(CopyFormat::WithDescription.format(&command), "git status");
+    }
+
+    #[test]
+    fn fills_copy_template() {
+        let command = CrowCommand {
+            id: "1".to_string(),
+            command: "echo {id}".to_string(),
+            description: " print the id\n".to_string(),
+            tags: vec!["shell".to_string(), "demo".to_string()],
+            ..Default::default()
+        };
+
+        assert_eq!(apply_copy_template("{command}", &command), "echo {id}");
+        assert_eq!(
+            apply_copy_template("{command} # {description} ({id}: {tags})", &command),
+            "echo {id} # print the id (1: shell, demo)"
+        );
+        assert_eq!(
+            apply_copy_template("{unknown} {{command} {command", &command),
+            "{unknown} {echo {id} {command"
+        );
+    }
+
+    #[test]
+    fn strips_unquoted_comments() {
+        assert_eq!(strip_comments("ls -la # show hidden files"), "ls -la");
+        assert_eq!(strip_comments("# only a comment"), "");
+        assert_eq!(strip_comments("ls\t#tab"), "ls");
+        assert_eq!(strip_comments("cd /tmp # go\nls # list"), "cd /tmp\nls");
+    }
+
+    #[test]
+    fn keeps_quoted_and_inline_hashes() {
+        assert_eq!(
+            strip_comments("echo '# not a comment'"),
+            "echo '# not a comment'"
+        );
+        assert_eq!(
+            strip_comments("echo \"a \\\" # b\" # note"),
+            "echo \"a \\\" # b\""
+        );
+        assert_eq!(strip_comments("echo \\# hi"), "echo \\# hi");
+        assert_eq!(strip_comments("echo a#b"), "echo a#b");
+        assert_eq!(strip_comments("echo ${#PATH}"), "echo ${#PATH}");
+    }
+}
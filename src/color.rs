@@ -0,0 +1,29 @@
+//! Coloring of the non-TUI output (e.g. of `crow add`), which can be disabled with `--no-color`,
+//! the `NO_COLOR` environment variable (see <https://no-color.org>) or the `color` config option.
+
+use crossterm::style::{Color, Stylize};
+
+use std::fmt::Display;
+
+/// Returns the given text in the given color or the plain text if colors are disabled
+pub fn paint(text: impl Display, color: Color, enabled: bool) -> String {
+    if enabled {
+        text.to_string().with(color).to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::style::Color;
+
+    use super::paint;
+
+    #[test]
+    fn paints_text_only_if_enabled() {
+        assert_eq!(paint("ls", Color::Cyan, false), "ls");
+        assert_ne!(paint("ls", Color::Cyan, true), "ls");
+        assert!(paint("ls", Color::Cyan, true).contains("ls"));
+    }
+}
@@ -1,6 +1,7 @@
-//! Models which result from [fuzzy::fuzzy_search_commands] over [CrowCommands].
-//! [ScoredCommands] contain an actual [CrowCommand], a fuzzy-score as well as the matching indices
-//! of the search result.
+//! Models which result from [crate::fuzzy::fuzzy_search_commands] over [CrowCommand]s.
+//! A [CommandScore] contains the id of a command, a fuzzy-score as well as the matching indices of
+//! the search result. It can also contain the [CrowCommand] itself (see [CommandScore::command]).
+//! [CommandScores] keep the scores of a search result in order and by id.
 
 use std::{
     fmt::Debug,
@@ -9,26 +10,56 @@ use std::{
 
 use indexmap::IndexMap;
 
-use crate::crow_commands::Id;
+use crate::crow_commands::{CrowCommand, Id};
 
-/// A [ScoredCommand] contains a [CrowCommand] alongside scoring metadata and
+/// A [CommandScore] contains the id of a [CrowCommand] alongside scoring metadata and
 /// a list of matching indices.
+/// The command itself is resolved by the search (see [crate::fuzzy::fuzzy_search_commands]). The
+/// interactive mode drops it and looks commands up by id instead, so that it always shows their
+/// latest version.
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub struct CommandScore {
     score: i64,
     indices: Vec<usize>,
     command_id: Id,
+    command: Option<CrowCommand>,
 }
 
 impl CommandScore {
+    /// Creates the score of the command with the given id
     pub fn new(score: i64, indices: Vec<usize>, command_id: Id) -> Self {
         Self {
             score,
             indices,
             command_id,
+            command: None,
         }
     }
 
+    /// Resolves the scored command, which has to be the command with the scored id
+    pub fn with_command(mut self, command: CrowCommand) -> Self {
+        debug_assert_eq!(command.id, self.command_id);
+
+        self.command = Some(command);
+        self
+    }
+
+    /// Drops the resolved command, so that only the id is kept
+    pub fn without_command(mut self) -> Self {
+        self.command = None;
+        self
+    }
+
+    /// Get a reference to the scored command if it has been resolved.
+    pub fn command(&self) -> Option<&CrowCommand> {
+        self.command.as_ref()
+    }
+
+    /// Returns the scored command if it has been resolved
+    pub fn into_command(self) -> Option<CrowCommand> {
+        self.command
+    }
+
     /// Get a reference to the scored command's score.
     pub fn score(&self) -> i64 {
         self.score
@@ -90,7 +121,7 @@ impl CommandScores {
 
 #[cfg(test)]
 mod tests {
-    use crate::command_scores::CommandScores;
+    use crate::{command_scores::CommandScores, crow_commands::CrowCommand};
 
     use super::CommandScore;
 
@@ -105,4 +136,21 @@ mod tests {
         let denormalized: Vec<CommandScore> = scores.denormalize().cloned().collect();
         assert_eq!(denormalized, vec![score]);
     }
+
+    #[test]
+    fn resolves_and_drops_command() {
+        let command = CrowCommand {
+            id: "sc_1".to_string(),
+            command: "ls".to_string(),
+            ..Default::default()
+        };
+        let score = CommandScore::new(1, vec![0], "sc_1".to_string());
+
+        assert_eq!(score.command(), None);
+
+        let resolved = score.clone().with_command(command.clone());
+        assert_eq!(resolved.command(), Some(&command));
+        assert_eq!(resolved.clone().without_command(), score);
+        assert_eq!(resolved.into_command(), Some(command));
+    }
 }
@@ -2,4 +2,16 @@
 
 pub mod add;
 pub mod add_last;
+pub mod clear;
 pub mod default;
+pub mod doctor;
+pub mod export;
+pub mod first;
+pub mod import;
+pub mod list;
+pub mod pick;
+pub mod repair;
+pub mod self_test_history;
+pub mod stale;
+pub mod tag;
+pub mod which;
@@ -1,57 +1,293 @@
 use clap::ArgMatches;
-use crossterm::style::Stylize;
-use dialoguer::{Confirm, Editor};
-use nanoid::nanoid;
+use crossterm::style::Color;
+use dialoguer::{Confirm, Input};
 
 use crate::{
-    crow_commands::CrowCommand,
+    clipboard::Clipboard,
+    color::paint,
+    config::{Config, DuplicateKey},
+    crow_commands::{parse_keywords, truncate_description, unix_timestamp, CrowCommand},
     crow_db::{CrowDBConnection, FilePath},
+    editor::edit,
+    eject,
 };
 
-use std::io::Error;
+use std::{
+    env::current_dir,
+    io::{Error, ErrorKind},
+};
 
 /// Uses the command given by the user as CLI argument and prompts to save it.
 /// Upon save the user is asked to provided a description.
-/// When the command is saved, it is written to the crow_db json file.
-pub fn run(arg_matches: &ArgMatches) -> Result<(), Error> {
-    let command = arg_matches.value_of("command").expect("Has command");
+/// When the command is saved, it is written to the crow_db json file and a short summary of the
+/// saved command is printed.
+/// For scripting the save confirmation can be skipped with `--yes` and the description (which
+/// also skips the keywords prompt) can be given with `--description`.
+/// With `--from-clipboard` the command is read from the clipboard instead.
+/// With `--capture-cwd` the current working directory is saved along with the command.
+/// Tags can be given with (multiple) `--tag`s.
+/// The source of the command (e.g. a URL) is prompted for as well, unless it is given with
+/// `--source` or the prompts are skipped with `--description`.
+pub fn run(arg_matches: &ArgMatches, config: Config) -> Result<(), Error> {
+    let command = match arg_matches.value_of("command") {
+        Some(command) => command.to_string(),
+        None => command_from_clipboard(&mut Box::<dyn Clipboard>::default())
+            .unwrap_or_else(|e| eject(&format!("Could not read command from clipboard. {}", e))),
+    };
+    let command = command.as_str();
+    validate_command(command)?;
 
-    let save_prompt = format!("Do you want to save command: {}?", command.cyan());
-    let should_save = Confirm::new()
-        .with_prompt(save_prompt)
-        .default(false)
-        .interact()?;
+    let mut connection = CrowDBConnection::new(FilePath::from_args(Some(arg_matches), &config))
+        .set_sort_order(config.sort_db);
 
-    if !should_save {
+    // Duplicates are reported before the prompts, unless the description is needed to detect them
+    let check_duplicate_early = !config.duplicate_key.depends_on_description();
+    if check_duplicate_early
+        && !confirm_duplicate(
+            &CrowCommand {
+                command: command.to_string(),
+                ..Default::default()
+            },
+            &connection,
+            arg_matches.is_present("yes"),
+            &config,
+        )?
+    {
+        return Ok(());
+    }
+
+    if !arg_matches.is_present("yes") {
+        let save_prompt = format!(
+            "Do you want to save command: {}?",
+            paint(command, Color::Cyan, config.color)
+        );
+        let should_save = Confirm::new()
+            .with_prompt(save_prompt)
+            .default(false)
+            .interact()?;
+
+        if !should_save {
+            return Ok(());
+        };
+    }
+
+    let (description, keywords, usage_hint) = match arg_matches.value_of("description") {
+        Some(description) => (description.to_string(), "".to_string(), "".to_string()),
+        None => prompt_description_and_keywords()?,
+    };
+    let description = limit_description(description, arg_matches.is_present("yes"), &config)?;
+
+    let source = match arg_matches.value_of("source") {
+        Some(source) => source.to_string(),
+        None if arg_matches.is_present("description") => "".to_string(),
+        None => Input::new()
+            .with_prompt("Where did you find this command, e.g. a URL (optional)")
+            .allow_empty(true)
+            .interact_text()?,
+    };
+
+    let mut new_command = CrowCommand {
+        command: command.to_string(),
+        description,
+        keywords: parse_keywords(&keywords),
+        usage_hint: Some(usage_hint.trim().to_string()).filter(|hint| !hint.is_empty()),
+        cwd: if arg_matches.is_present("capture_cwd") {
+            Some(current_dir()?.to_string_lossy().to_string())
+        } else {
+            None
+        },
+        source: Some(source.trim().to_string()).filter(|source| !source.is_empty()),
+        created_at: Some(unix_timestamp()),
+        ..Default::default()
+    };
+    if !check_duplicate_early
+        && !confirm_duplicate(
+            &new_command,
+            &connection,
+            arg_matches.is_present("yes"),
+            &config,
+        )?
+    {
         return Ok(());
+    }
+
+    let tags: Vec<String> = arg_matches
+        .values_of("tag")
+        .into_iter()
+        .flatten()
+        .map(str::to_string)
+        .collect();
+    new_command.add_tags(&tags);
+
+    new_command.id = config
+        .id_scheme
+        .generate(&new_command, connection.commands());
+
+    let new_command = connection.append_command(new_command);
+
+    print_summary(&new_command, connection.path());
+    Ok(())
+}
+
+/// Rejects empty (or whitespace only) commands, because they can't be used for anything.
+/// Descriptions may still be empty.
+pub fn validate_command(command: &str) -> Result<(), Error> {
+    if command.trim().is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "The command must not be empty",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Warns if the description is longer than [Config::max_description_len] and asks whether it
+/// should be truncated. The description is kept as it is if the prompts are skipped with `--yes`.
+fn limit_description(
+    description: String,
+    skip_prompt: bool,
+    config: &Config,
+) -> Result<String, Error> {
+    let (max_len, truncated) = match config.max_description_len.and_then(|max_len| {
+        truncate_description(&description, max_len).map(|truncated| (max_len, truncated))
+    }) {
+        Some(limit) => limit,
+        None => return Ok(description),
+    };
+
+    let warning = format!(
+        "The description is longer than {} characters, which makes it harder to find the command.",
+        max_len
+    );
+    println!("{}", paint(&warning, Color::Yellow, config.color));
+
+    if skip_prompt {
+        return Ok(description);
+    }
+
+    let should_truncate = Confirm::new()
+        .with_prompt(format!("Do you want to truncate it to: {}?", truncated))
+        .default(true)
+        .interact()?;
+
+    Ok(if should_truncate {
+        truncated
+    } else {
+        description
+    })
+}
+
+/// Warns if the new command is a duplicate of an already saved command (see
+/// [Config::duplicate_key]) and asks whether it should be saved anyway.
+/// Returns whether the command should be saved. Duplicates are saved without asking if the prompts
+/// are skipped with `--yes`.
+pub fn confirm_duplicate(
+    new_command: &CrowCommand,
+    connection: &CrowDBConnection,
+    skip_prompt: bool,
+    config: &Config,
+) -> Result<bool, Error> {
+    let duplicate = match config
+        .duplicate_key
+        .find_duplicate(connection.commands(), new_command)
+    {
+        Some(duplicate) => duplicate,
+        None => return Ok(true),
     };
 
+    let warning = match config.duplicate_key {
+        DuplicateKey::Command => "This command has already been saved",
+        DuplicateKey::CommandDescription => {
+            "This command has already been saved with the same description"
+        }
+    };
+    println!(
+        "{}:\n  {}",
+        paint(warning, Color::Yellow, config.color),
+        duplicate
+    );
+
+    if skip_prompt {
+        return Ok(true);
+    }
+
+    Confirm::new()
+        .with_prompt("Do you want to save it anyway?")
+        .default(false)
+        .interact()
+}
+
+/// Returns the clipboard contents without surrounding whitespace (e.g. the trailing line break
+/// of a command which was copied from a web page)
+fn command_from_clipboard(clipboard: &mut Box<dyn Clipboard>) -> Result<String, String> {
+    let contents = clipboard.get_contents()?;
+
+    match contents.trim() {
+        "" => Err("The clipboard is empty".to_string()),
+        command => Ok(command.to_string()),
+    }
+}
+
+/// Interactively asks the user for an (optional) description, keywords and usage hint of a new
+/// command
+fn prompt_description_and_keywords() -> Result<(String, String, String), Error> {
     let description = Confirm::new()
         .with_prompt("Do you want to add a description")
         .default(true)
         .interact()?;
 
     let description = if description {
-        Editor::new().edit("")?.unwrap()
+        edit("")
+            .unwrap_or_else(|e| eject(&format!("Could not edit description. {}", e)))
+            .unwrap()
     } else {
         "".to_string()
     };
 
-    let new_command = CrowCommand {
-        id: nanoid!(),
-        command: command.to_string(),
-        description,
-    };
+    let keywords: String = Input::new()
+        .with_prompt("Keywords to find this command by (comma separated, optional)")
+        .allow_empty(true)
+        .interact_text()?;
+
+    let usage_hint: String = Input::new()
+        .with_prompt("When to use this command (optional)")
+        .allow_empty(true)
+        .interact_text()?;
+
+    Ok((description, keywords, usage_hint))
+}
 
-    if let Some(p) = arg_matches.value_of("db_path") {
-        println!("{}", p);
+/// Prints a short summary of a saved command and the database file it was written to.
+pub fn print_summary(command: &CrowCommand, path: &FilePath) {
+    println!("\nSaved command to {}:\n  {}", path, command);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clipboard::{Clipboard, MemoryClipboard};
+
+    use super::{command_from_clipboard, validate_command};
+
+    #[test]
+    fn rejects_empty_commands() {
+        assert!(validate_command("").is_err());
+        assert!(validate_command(" \t\n ").is_err());
+        assert!(validate_command(" ls ").is_ok());
     }
 
-    CrowDBConnection::new(FilePath::new(
-        arg_matches.value_of("db_path"),
-        arg_matches.value_of("db_name"),
-    ))
-    .add_command(new_command)
-    .write();
-    Ok(())
+    #[test]
+    fn reads_trimmed_command_from_clipboard() {
+        let mut clipboard: Box<dyn Clipboard> = Box::new(MemoryClipboard::default());
+
+        assert!(command_from_clipboard(&mut clipboard).is_err());
+
+        clipboard
+            .set_contents("  git log --oneline\n".to_string())
+            .unwrap();
+        assert_eq!(
+            command_from_clipboard(&mut clipboard),
+            Ok("git log --oneline".to_string())
+        );
+    }
 }
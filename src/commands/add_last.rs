@@ -1,40 +1,70 @@
 use clap::ArgMatches;
-use crossterm::style::Stylize;
-use dialoguer::{Confirm, Editor};
+use crossterm::style::Color;
+use dialoguer::{Confirm, Input};
 use dirs::home_dir;
-use nanoid::nanoid;
+use log::debug;
 
 use crate::{
-    crow_commands::CrowCommand,
+    color::paint,
+    commands::add,
+    config::Config,
+    crow_commands::{parse_keywords, unix_timestamp, CrowCommand},
     crow_db::{CrowDBConnection, FilePath},
+    editor::edit,
     eject,
-    history::Shell,
+    history::{read_last_command_from_file, Shell},
 };
 
-use std::{env, io::Error};
+use std::{
+    env,
+    io::{Error, ErrorKind},
+    path::Path,
+};
 
-/// Tries to read the last command from the history of the users configured default shell and asks
-/// the user if it should be saved.
+/// Tries to read the last command from the history of the users configured default shell (or from
+/// the history file given with `--history-file`) and asks the user if it should be saved.
 /// If the command should be saved, the user is prompted for a description.
 /// Upon saving the command will be written to the crow_db json file.
-pub fn run(arg_matches: &ArgMatches) -> Result<(), Error> {
-    let shell_path = env::var("SHELL").expect("Could access $SHELL environment variable");
-    let shell = if let Some(shell) = Shell::from_path(shell_path) {
-        shell
-    } else {
-        eject("Did not find a proper shell!");
+pub fn run(arg_matches: &ArgMatches, config: Config) -> Result<(), Error> {
+    let last_history_command = match arg_matches.value_of("history_file") {
+        Some(path) => read_last_command_from_file(Path::new(path), arg_matches.is_present("plain")),
+        None => read_last_shell_history_command()?,
+    };
+
+    let last_history_command = match last_history_command {
+        Some(command) => command,
+        None => {
+            println!("No prior command to save.");
+            return Ok(());
+        }
     };
 
-    let base_dir = home_dir().unwrap_or_else(|| {
-        eject("Unable to determine home path");
-    });
-    let last_history_command = shell.read_last_history_command(base_dir);
+    add::validate_command(&last_history_command)?;
 
     println!(
         "\nThe last command was: {}",
-        last_history_command.clone().cyan()
+        paint(&last_history_command, Color::Cyan, config.color)
     );
 
+    let mut connection = CrowDBConnection::new(FilePath::from_args(Some(arg_matches), &config))
+        .set_sort_order(config.sort_db);
+
+    // Duplicates are reported before the prompts, unless the description is needed to detect them
+    let check_duplicate_early = !config.duplicate_key.depends_on_description();
+    if check_duplicate_early
+        && !add::confirm_duplicate(
+            &CrowCommand {
+                command: last_history_command.clone(),
+                ..Default::default()
+            },
+            &connection,
+            false,
+            &config,
+        )?
+    {
+        return Ok(());
+    }
+
     let should_save = Confirm::new()
         .with_prompt("Do you want to save that command?")
         .default(false)
@@ -50,22 +80,57 @@ pub fn run(arg_matches: &ArgMatches) -> Result<(), Error> {
         .interact()?;
 
     let description = if description {
-        Editor::new().edit("")?.unwrap()
+        edit("")
+            .unwrap_or_else(|e| eject(&format!("Could not edit description. {}", e)))
+            .unwrap()
     } else {
         "".to_string()
     };
 
-    let new_command = CrowCommand {
-        id: nanoid!(),
+    let keywords: String = Input::new()
+        .with_prompt("Keywords to find this command by (comma separated, optional)")
+        .allow_empty(true)
+        .interact_text()?;
+
+    let mut new_command = CrowCommand {
         command: last_history_command,
         description,
+        keywords: parse_keywords(&keywords),
+        created_at: Some(unix_timestamp()),
+        ..Default::default()
     };
+    if !check_duplicate_early && !add::confirm_duplicate(&new_command, &connection, false, &config)?
+    {
+        return Ok(());
+    }
 
-    CrowDBConnection::new(FilePath::new(
-        arg_matches.value_of("db_path"),
-        arg_matches.value_of("db_name"),
-    ))
-    .add_command(new_command)
-    .write();
+    new_command.id = config
+        .id_scheme
+        .generate(&new_command, connection.commands());
+
+    let new_command = connection.append_command(new_command);
+
+    add::print_summary(&new_command, connection.path());
     Ok(())
 }
+
+/// Reads the last command from the history file of the shell in `$SHELL`.
+/// Fails if the history file can't be found, because there is no home directory.
+fn read_last_shell_history_command() -> Result<Option<String>, Error> {
+    let shell_path = env::var("SHELL").expect("Could access $SHELL environment variable");
+    debug!("Detecting shell from $SHELL: {}", shell_path);
+    let shell = if let Some(shell) = Shell::from_path(shell_path) {
+        shell
+    } else {
+        eject("Did not find a proper shell! Use --history-file to read another history file.");
+    };
+
+    let base_dir = home_dir().ok_or_else(|| {
+        Error::new(
+            ErrorKind::NotFound,
+            "Could not determine your home directory to find the shell history. Use --history-file to read the history file directly.",
+        )
+    })?;
+
+    Ok(shell.read_last_history_command(&shell.user_history_path(&base_dir)))
+}
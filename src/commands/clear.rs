@@ -0,0 +1,47 @@
+use clap::ArgMatches;
+use crossterm::style::Color;
+use dialoguer::Confirm;
+
+use crate::{
+    color::paint,
+    config::Config,
+    crow_db::{CrowDBConnection, FilePath},
+};
+
+use std::io::Error;
+
+/// Removes all commands from the crow_db file after asking for confirmation (which can be skipped
+/// with `--yes`). The current database file is backed up first (e.g. to `crow_db.json.bak`).
+pub fn run(arg_matches: &ArgMatches, config: Config) -> Result<(), Error> {
+    let connection = CrowDBConnection::new(FilePath::from_args(Some(arg_matches), &config));
+    let command_count = connection.commands().len();
+
+    if !arg_matches.is_present("yes") {
+        let clear_prompt = format!(
+            "Do you really want to remove all {} commands from {}?",
+            paint(command_count, Color::Cyan, config.color),
+            connection.path()
+        );
+        let should_clear = Confirm::new()
+            .with_prompt(clear_prompt)
+            .default(false)
+            .interact()?;
+
+        if !should_clear {
+            return Ok(());
+        }
+    }
+
+    let backup_path = connection.backup()?;
+    let mut connection = connection.set_commands(vec![]);
+    connection.write();
+
+    println!(
+        "\nRemoved {} commands from {}\nA backup has been saved to {}",
+        command_count,
+        connection.path(),
+        backup_path.display()
+    );
+
+    Ok(())
+}
@@ -1,15 +1,18 @@
+use crate::config::Config;
 use crate::crow_commands::CrowCommand;
 use crate::crow_db::FilePath;
 use crate::events::{CliEvent, InputEvent};
+use crate::filter::parse_filter_args;
+use crate::palette::search_actions;
 use crate::state::{MenuItem, State};
-use crate::{eject, input};
+use crate::{eject, exit_code, input};
 use clap::ArgMatches;
 use crossterm::event::EnableMouseCapture;
 use crossterm::execute;
 
 use std::sync::mpsc::TryRecvError;
 use std::{
-    io::{self, Error, Stdout},
+    io::{self, Error, IsTerminal, Stdout},
     sync::mpsc::{self, Receiver, Sender},
     thread,
     time::{Duration, Instant},
@@ -79,33 +82,54 @@ fn render(
     terminal.draw(|frame| {
         let rect = frame.size();
         let layout = rendering::layout(rect);
+        let block_style = state.block_style();
 
-        frame.render_widget(rendering::keybindings(state.active_menu_item()), layout[0]);
+        frame.render_widget(
+            rendering::keybindings(state.active_menu_item(), block_style),
+            layout[0],
+        );
 
-        let command_scores = state.fuzz_result_or_all();
+        let command_scores = state.visible_fuzz_result();
+        let hidden_result_count = state.hidden_result_count();
 
         let inner_split_layout = rendering::inner_split_layout(layout[1]);
 
-        let filtered_crow_commands = command_scores
+        let (filtered_crow_commands, scores): (Vec<CrowCommand>, Vec<i64>) = command_scores
             .iter()
-            .map(|sc| {
+            .filter_map(|sc| {
                 state
                     .crow_commands()
                     .commands()
-                    .get(sc.command_id())
-                    .unwrap()
-                    .clone()
+                    .find_by_id(sc.command_id())
+                    .map(|c| (c.clone(), sc.score()))
             })
-            .collect::<Vec<CrowCommand>>();
+            .unzip();
 
-        if state.has_crow_commands() {
+        if !state.has_crow_commands() {
+            frame.render_widget(empty_command_list(block_style), inner_split_layout[0]);
+        } else if filtered_crow_commands.is_empty() {
+            frame.render_widget(
+                rendering::no_matches(state.input(), block_style),
+                inner_split_layout[0],
+            );
+        } else {
             frame.render_stateful_widget(
-                rendering::command_list(filtered_crow_commands, inner_split_layout[0]),
+                rendering::command_list(
+                    filtered_crow_commands,
+                    hidden_result_count,
+                    if state.show_scores() {
+                        Some(scores.as_slice())
+                    } else {
+                        None
+                    },
+                    state.inline_descriptions(),
+                    inner_split_layout[0],
+                    state.tab_width(),
+                    block_style,
+                ),
                 inner_split_layout[0],
                 state.mut_command_list(),
             );
-        } else {
-            frame.render_widget(empty_command_list(), inner_split_layout[0]);
         }
 
         if let Some(c) = state.selected_crow_command() {
@@ -116,12 +140,25 @@ fn render(
             };
 
             frame.render_widget(
-                rendering::command_detail(c, state.detail_scroll_position(), highlight_indices),
+                rendering::command_detail(
+                    c,
+                    state.detail_scroll_position(),
+                    highlight_indices,
+                    state.syntax_highlight(),
+                    state.line_numbers(),
+                    state.tab_width(),
+                    block_style,
+                ),
                 inner_split_layout[1],
             );
         };
 
-        frame.render_widget(rendering::input(state.input()), layout[2]);
+        frame.render_widget(rendering::input(state.input(), block_style), layout[2]);
+
+        frame.render_widget(
+            rendering::status_bar(state.flash(), state.search_terms(), state.tagged_only()),
+            layout[3],
+        );
 
         frame.set_cursor(
             layout[2].x + UnicodeWidthStr::width(state.input().as_str()) as u16 + 3,
@@ -131,18 +168,53 @@ fn render(
         match state.active_menu_item() {
             MenuItem::Edit => {
                 if state.selected_crow_command().is_some() {
-                    rendering::popup(frame, rendering::edit_command());
+                    rendering::popup(frame, rendering::edit_command(block_style));
+                };
+            }
+
+            MenuItem::Palette => {
+                let actions = search_actions(state.palette_input());
+                rendering::popup(
+                    frame,
+                    rendering::palette(
+                        &actions,
+                        state.palette_selection(),
+                        state.palette_input(),
+                        block_style,
+                    ),
+                );
+            }
+
+            MenuItem::Tags => {
+                if let Some(c) = state.selected_crow_command() {
+                    rendering::popup(
+                        frame,
+                        rendering::edit_tags(c, state.tag_input(), block_style),
+                    );
+                };
+            }
+
+            MenuItem::ConfirmEdit => {
+                if let Some(edit) = state.pending_edit() {
+                    rendering::popup(
+                        frame,
+                        rendering::confirm_edit(edit, state.max_description_len(), block_style),
+                    );
                 };
             }
 
             MenuItem::Delete => {
                 if let Some(c) = state.selected_crow_command() {
-                    rendering::popup(frame, rendering::delete_command(c));
+                    rendering::popup(frame, rendering::delete_command(c, block_style));
                 };
             }
 
             _ => {}
         }
+
+        if let Some(error) = state.error() {
+            rendering::popup(frame, rendering::error(error, block_style));
+        }
     })?;
 
     Ok(())
@@ -155,34 +227,88 @@ fn main_loop(
     main_tx: Sender<InputWorkerEvent>,
     input_worker_rx: Receiver<CliEvent<CEvent>>,
     arg_matches: Option<&ArgMatches>,
-) -> Result<(), Error> {
+    file_path: FilePath,
+    config: Config,
+    initial_menu_item: MenuItem,
+) -> Result<InputEvent, Error> {
     let stdout = io::stdout();
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
     terminal.clear()?;
 
-    let file_path = match arg_matches {
-        Some(matches) => FilePath::new(matches.value_of("db_path"), matches.value_of("db_name")),
-        None => FilePath::default(),
-    };
-
-    let mut state = State::new(Some(file_path));
+    let mut state = initial_state(arg_matches, file_path, config, initial_menu_item);
 
     loop {
         render(&mut terminal, &mut state).expect("Can render");
 
-        if let Ok(InputEvent::Quit) =
-            input::handle_input(&main_tx, &input_worker_rx, &mut terminal, &mut state)
-        {
-            break;
-        };
+        match input::handle_input(&main_tx, &input_worker_rx, &mut terminal, &mut state) {
+            Ok(InputEvent::Continue) | Err(_) => {}
+            Ok(InputEvent::Cancel) => {
+                // The last viewed command is expected in the clipboard with `copy_on_select`, even
+                // if crow is quit right after selecting it. The terminal is already restored, so
+                // there is no way to show an error anymore.
+                let _ = state.copy_pending_selection();
+                return Ok(InputEvent::Cancel);
+            }
+            Ok(quit) => return Ok(quit),
+        }
     }
+}
 
-    Ok(())
+/// Creates the state the interactive mode starts with: the commands of the given database file,
+/// filtered, sorted and searched according to the arguments (e.g. of `crow search`).
+fn initial_state(
+    arg_matches: Option<&ArgMatches>,
+    file_path: FilePath,
+    config: Config,
+    initial_menu_item: MenuItem,
+) -> State {
+    let mut state = State::new(file_path);
+    state.set_config(config);
+
+    if let Some(matches) = arg_matches {
+        let filters = parse_filter_args(matches.values_of("filter").into_iter().flatten())
+            .unwrap_or_else(|e| eject(&e));
+        state.set_filters(filters);
+
+        if let Some(sort) = matches.value_of("sort") {
+            state.set_browse_sort(sort.parse().unwrap_or_else(|e: String| eject(&e)));
+        }
+        state.set_fuzz_result(vec![]);
+
+        // Prefill the search input with the query of `crow search <query>`
+        if let Some(query) = matches.value_of("query") {
+            state.set_input(query.to_string());
+            state.search();
+        }
+
+        state.select_command(0);
+    }
+
+    if let MenuItem::Edit = initial_menu_item {
+        state.edit_selected_command();
+    }
+
+    state
 }
 
-/// Default command when running 'crow' without arguments
-pub fn run(arg_matches: Option<&ArgMatches>) -> Result<(), Error> {
+/// Default command when running 'crow' without arguments.
+/// The interactive mode starts in the given menu, e.g. in [MenuItem::Edit] for `crow edit-search`.
+/// Exits with [exit_code::NO_SELECTION] if crow is quit without copying a command.
+pub fn run(
+    arg_matches: Option<&ArgMatches>,
+    config: Config,
+    initial_menu_item: MenuItem,
+) -> Result<(), Error> {
+    // NOTE: we can't use `eject` here, because raw mode can't be disabled without a terminal
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        eprintln!("crow's interactive mode needs a TTY, use 'crow list [query]' or 'crow --first <query> --print' instead");
+        std::process::exit(exit_code::ERROR);
+    }
+
+    // Resolved before raw mode, so that warnings about the path are printed properly
+    let file_path = FilePath::from_args(arg_matches, &config);
+
     enable_raw_mode().expect("Can run in raw mode");
     execute!(io::stdout(), EnableMouseCapture)?;
 
@@ -190,7 +316,77 @@ pub fn run(arg_matches: Option<&ArgMatches>) -> Result<(), Error> {
     let (main_tx, main_rx) = mpsc::channel();
 
     poll_input_thread(input_worker_tx, main_rx);
-    main_loop(main_tx, input_worker_rx, arg_matches).expect("Main loop runs");
+    let quit = main_loop(
+        main_tx,
+        input_worker_rx,
+        arg_matches,
+        file_path,
+        config,
+        initial_menu_item,
+    )
+    .expect("Main loop runs");
+
+    if let InputEvent::Cancel = quit {
+        std::process::exit(exit_code::NO_SELECTION);
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use nanoid::nanoid;
+
+    use crate::{
+        config::Config,
+        crow_commands::CrowCommand,
+        crow_db::{CrowDBConnection, FilePath},
+        initialize_arg_parser,
+        state::MenuItem,
+    };
+
+    use super::initial_state;
+
+    #[test]
+    fn reads_the_database_file_given_to_search() {
+        let fn_path = &format!("./testdata/tmp/{}", nanoid!());
+        CrowDBConnection::new(FilePath::new(Some(fn_path), Some("other.json")))
+            .set_commands(vec![
+                CrowCommand::test("1", "docker ps"),
+                CrowCommand::test("2", "git status"),
+            ])
+            .write();
+
+        let matches = initialize_arg_parser().get_matches_from(vec![
+            "crow",
+            "search",
+            "--path",
+            fn_path,
+            "--file",
+            "other.json",
+            "docker",
+        ]);
+        let sub_matches = matches.subcommand_matches("search");
+        let config = Config::default();
+
+        let mut state = initial_state(
+            sub_matches,
+            FilePath::from_args(sub_matches, &config),
+            config,
+            MenuItem::Find,
+        );
+
+        assert_eq!(state.crow_commands().commands().len(), 2);
+        assert_eq!(state.input(), "docker");
+        let result: Vec<String> = state
+            .visible_fuzz_result()
+            .iter()
+            .map(|score| score.command_id().clone())
+            .collect();
+        assert_eq!(result, vec!["1"]);
+
+        fs::remove_dir_all(fn_path).unwrap();
+    }
+}
@@ -0,0 +1,144 @@
+use clap::ArgMatches;
+use dirs::home_dir;
+
+use crate::{
+    config::Config,
+    crow_db::{CrowDBConnection, FilePath},
+    exit_code,
+    history::Shell,
+};
+
+use std::{
+    env,
+    io::Error,
+    path::{Path, PathBuf},
+};
+
+/// Prints diagnostic information about the files crow resolved, which helps to debug e.g. why a
+/// config option is not applied.
+/// A config file which can't be used is reported as a failed check, the remaining checks still
+/// run with the default config. Exits with a non-zero exit code if any check failed.
+pub fn run(
+    arg_matches: &ArgMatches,
+    config_arg: Option<&str>,
+    config: Config,
+) -> Result<(), Error> {
+    let (db_path, config_path) = resolve_files(arg_matches, config_arg, &config);
+
+    let config_check = check_config(config_path.as_deref());
+    match &config_check {
+        Ok(status) | Err(status) => println!("{}", status),
+    }
+
+    println!(
+        "{}",
+        describe_file("Database file", Some(db_path.as_path()))
+    );
+    println!(
+        "Commands: {}",
+        CrowDBConnection::new(db_path).commands().len()
+    );
+
+    // The history file which is read by `crow add:last`
+    let shell = env::var("SHELL").ok().and_then(Shell::from_path);
+    match (shell, home_dir()) {
+        (Some(shell), Some(home)) => println!(
+            "{}",
+            describe_file("History file", Some(&shell.user_history_path(&home)))
+        ),
+        (Some(_), None) => println!("History file: unknown (home directory not found)"),
+        (None, _) => println!("History file: none (shell not supported)"),
+    }
+
+    if config_check.is_err() {
+        std::process::exit(exit_code::ERROR);
+    }
+
+    Ok(())
+}
+
+/// Resolves the database and config file from the arguments, environment variables and default
+/// locations, like every other command does. The config file is `None` if none has been found.
+pub fn resolve_files(
+    arg_matches: &ArgMatches,
+    config_arg: Option<&str>,
+    config: &Config,
+) -> (FilePath, Option<PathBuf>) {
+    (
+        FilePath::from_args(Some(arg_matches), config),
+        Config::path(config_arg),
+    )
+}
+
+/// Describes a resolved file and whether it exists. A missing path means that no file was found
+/// and the defaults are used.
+pub fn describe_file(name: &str, path: Option<&Path>) -> String {
+    match path {
+        Some(path) if path.exists() => format!("{}: {} (exists)", name, path.display()),
+        Some(path) => format!("{}: {} (does not exist)", name, path.display()),
+        None => format!("{}: none (using defaults)", name),
+    }
+}
+
+/// Describes the config file, which fails if the file can't be read or parsed.
+fn check_config(path: Option<&Path>) -> Result<String, String> {
+    match path {
+        Some(path) => match Config::try_from_file(path) {
+            Ok(_) => Ok(describe_file("Config file", Some(path))),
+            Err(e) => Err(format!(
+                "Config file: {} (invalid)\n  {}",
+                path.display(),
+                e
+            )),
+        },
+        None => Ok(describe_file("Config file", None)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use nanoid::nanoid;
+
+    use super::{check_config, describe_file};
+
+    #[test]
+    fn describes_resolved_files() {
+        assert_eq!(
+            describe_file("Database file", Some(Path::new("./testdata/crow.json"))),
+            "Database file: ./testdata/crow.json (exists)"
+        );
+        assert_eq!(
+            describe_file("Config file", Some(Path::new("./testdata/missing.toml"))),
+            "Config file: ./testdata/missing.toml (does not exist)"
+        );
+        assert_eq!(
+            describe_file("Config file", None),
+            "Config file: none (using defaults)"
+        );
+    }
+
+    #[test]
+    fn reports_invalid_config_files() {
+        let path = format!("./testdata/tmp/{}.toml", nanoid!());
+        fs::create_dir_all("./testdata/tmp").unwrap();
+        fs::write(&path, "unknown_option = true").unwrap();
+
+        let result = check_config(Some(Path::new(&path)));
+        fs::remove_file(&path).unwrap();
+
+        assert!(
+            matches!(result, Err(status) if status.starts_with(&format!("Config file: {} (invalid)\n  Could not parse", path)))
+        );
+        assert_eq!(
+            check_config(Some(Path::new("./testdata/xdg_config/crow/config.toml"))),
+            Ok("Config file: ./testdata/xdg_config/crow/config.toml (exists)".to_string())
+        );
+        assert!(check_config(Some(Path::new("./testdata/missing.toml"))).is_err());
+        assert_eq!(
+            check_config(None),
+            Ok("Config file: none (using defaults)".to_string())
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use clap::ArgMatches;
+
+use crate::{
+    clipboard::CopyFormat,
+    config::Config,
+    crow_commands::{normalize_tag, CrowCommand},
+    crow_db::{CrowDBConnection, FilePath},
+    eject,
+};
+
+use std::io::Error;
+
+/// Heading of commands without any tag inside the markdown export
+const UNTAGGED_HEADING: &str = "Untagged";
+
+/// Prints all saved commands to stdout, either as json (the crow_db format) or as markdown
+/// cheat sheet.
+pub fn run(arg_matches: &ArgMatches, config: Config) -> Result<(), Error> {
+    let connection = CrowDBConnection::new(FilePath::from_args(Some(arg_matches), &config));
+
+    match arg_matches.value_of("format").unwrap_or("json") {
+        "markdown" => print!("{}", markdown(connection.commands())),
+        _ => match serde_json::to_string_pretty(connection.commands()) {
+            Ok(json) => println!("{}", json),
+            Err(e) => eject(&format!("Could not export commands as json. {}", e)),
+        },
+    }
+
+    Ok(())
+}
+
+/// Creates a markdown cheat sheet of the given commands which are grouped by their tags (sorted
+/// alphabetically, untagged commands last). Commands with multiple tags are listed under every
+/// tag. Every command is rendered as heading (the first description line or the first line of the
+/// command itself), fenced code block and the remaining description.
+pub fn markdown(commands: &[CrowCommand]) -> String {
+    let mut tags: Vec<String> = commands
+        .iter()
+        .flat_map(|c| c.tags.iter().map(|t| normalize_tag(t)))
+        .collect();
+    tags.sort();
+    tags.dedup();
+
+    let mut groups: Vec<(String, Vec<&CrowCommand>)> = tags
+        .into_iter()
+        .map(|tag| {
+            let tagged = commands.iter().filter(|c| c.has_tag(&tag)).collect();
+            (tag, tagged)
+        })
+        .collect();
+
+    let untagged: Vec<&CrowCommand> = commands.iter().filter(|c| c.tags.is_empty()).collect();
+    if !untagged.is_empty() {
+        groups.push((UNTAGGED_HEADING.to_string(), untagged));
+    }
+
+    let mut document = String::from("# crow commands\n");
+
+    for (heading, commands) in groups {
+        document.push_str(&format!("\n## {}\n", escape_heading(&heading)));
+
+        for command in commands {
+            let mut description = command.description.trim().lines();
+            let title = description
+                .next()
+                .or_else(|| command.command.lines().next())
+                .unwrap_or("");
+
+            document.push_str(&format!(
+                "\n### {}\n\n{}\n",
+                escape_heading(title.trim()),
+                CopyFormat::CodeBlock {
+                    with_description: false
+                }
+                .format(command)
+            ));
+
+            let rest = description.collect::<Vec<&str>>().join("\n");
+            if !rest.trim().is_empty() {
+                document.push_str(&format!("\n{}\n", rest.trim()));
+            }
+        }
+    }
+
+    document
+}
+
+/// Escapes the characters which markdown would interpret inside a heading (e.g. `*` or a
+/// trailing `#`), so that commands like `ls *.md` are shown as they are.
+fn escape_heading(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '~' | '|'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crow_commands::CrowCommand;
+
+    use super::{escape_heading, markdown};
+
+    #[test]
+    fn groups_commands_by_tag() {
+        let command_1 = CrowCommand {
+            id: "1".to_string(),
+            command: "git status".to_string(),
+            description: "show repo status\nincluding untracked files".to_string(),
+            tags: vec!["git".to_string()],
+            ..Default::default()
+        };
+        let command_2 = CrowCommand {
+            id: "2".to_string(),
+            command: "ls -la".to_string(),
+            ..Default::default()
+        };
+        let command_3 = CrowCommand {
+            id: "3".to_string(),
+            command: "docker ps".to_string(),
+            description: "list containers".to_string(),
+            tags: vec!["docker".to_string(), "git".to_string()],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            markdown(&[command_1, command_2, command_3]),
+            "# crow commands\n\
+             \n## docker\n\
+             \n### list containers\n\n```\ndocker ps\n```\n\
+             \n## git\n\
+             \n### show repo status\n\n```\ngit status\n```\n\
+             \nincluding untracked files\n\
+             \n### list containers\n\n```\ndocker ps\n```\n\
+             \n## Untagged\n\
+             \n### ls -la\n\n```\nls -la\n```\n"
+        );
+    }
+
+    #[test]
+    fn uses_first_command_line_as_heading_without_description() {
+        let command = CrowCommand::test("1", "for f in *.md; do\n  echo $f\ndone");
+
+        assert_eq!(
+            markdown(&[command]),
+            "# crow commands\n\
+             \n## Untagged\n\
+             \n### for f in \\*.md; do\n\n```\nfor f in *.md; do\n  echo $f\ndone\n```\n"
+        );
+    }
+
+    #[test]
+    fn escapes_markdown_in_headings() {
+        assert_eq!(escape_heading("ls -la"), "ls -la");
+        assert_eq!(
+            escape_heading("grep <pattern> | wc #"),
+            "grep \\<pattern\\> \\| wc \\#"
+        );
+        assert_eq!(escape_heading("echo `a_b`"), "echo \\`a\\_b\\`");
+    }
+}
@@ -0,0 +1,54 @@
+use clap::ArgMatches;
+use crossterm::style::Color;
+
+use crate::{
+    clipboard::Clipboard,
+    color::paint,
+    commands::list::matching_commands,
+    config::Config,
+    crow_db::{CrowDBConnection, FilePath},
+    eject, exit_code,
+};
+
+use std::io::Error;
+
+/// Copies (or with `--print` prints) the best matching command for the `--first` query without
+/// starting the interactive mode. Exits with [exit_code::NO_SELECTION] if nothing matched.
+pub fn run(arg_matches: &ArgMatches, config: Config) -> Result<(), Error> {
+    let query = arg_matches.value_of("first").expect("Has query");
+
+    let connection = CrowDBConnection::new(FilePath::from_args(Some(arg_matches), &config));
+
+    let command = match matching_commands(connection.commands(), query, &config.weights)
+        .into_iter()
+        .next()
+    {
+        Some(command) => command.command,
+        None => {
+            eprintln!("No command matches '{}'", query);
+            std::process::exit(exit_code::NO_SELECTION);
+        }
+    };
+
+    copy_or_print(command, arg_matches.is_present("print"), &config);
+
+    Ok(())
+}
+
+/// Copies the command to the clipboard (or prints it if `print` is set) outside of the
+/// interactive mode. This is shared by all commands which select a command without the fuzzy mode.
+pub fn copy_or_print(command: String, print: bool, config: &Config) {
+    if print {
+        println!("{}", command);
+        return;
+    }
+
+    Box::<dyn Clipboard>::default()
+        .set_contents(command.clone())
+        .unwrap_or_else(|e| eject(&format!("Could not add command to clipboard. {}", e)));
+
+    println!(
+        "\nCommand:\n  {}\ncopied to clipboard!\n",
+        paint(&command, Color::Cyan, config.color)
+    );
+}
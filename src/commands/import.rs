@@ -0,0 +1,365 @@
+use clap::ArgMatches;
+use nanoid::nanoid;
+use serde::Deserialize;
+
+use crate::{
+    config::{Config, DuplicateKey},
+    crow_commands::CrowCommand,
+    crow_db::{CrowDBConnection, FilePath},
+};
+
+use std::{
+    fs::read_to_string,
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+/// Policy for imported commands which conflict with an existing command, meaning that they have
+/// the same id or are duplicates (see [Config::duplicate_key]).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MergeStrategy {
+    /// Keep the existing command and drop the imported one
+    Skip,
+    /// Replace the existing command with the imported one
+    Overwrite,
+    /// Keep both commands, the imported one gets a new id if its id is already taken
+    KeepBoth,
+}
+
+impl MergeStrategy {
+    /// Parses the value of the `--merge-strategy` argument
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "skip" => Ok(Self::Skip),
+            "overwrite" => Ok(Self::Overwrite),
+            "keep-both" => Ok(Self::KeepBoth),
+            _ => Err(format!(
+                "Unknown merge strategy '{}', expected one of: skip, overwrite, keep-both",
+                value
+            )),
+        }
+    }
+}
+
+/// Number of imported commands per outcome
+#[derive(Debug, Default, PartialEq)]
+pub struct MergeReport {
+    pub added: usize,
+    pub skipped: usize,
+    pub overwritten: usize,
+    pub kept_both: usize,
+}
+
+/// Files which can be imported: the output of `crow export` (a list of commands) or a crow_db
+/// json file.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ImportFile {
+    Commands(Vec<CrowCommand>),
+    Database { commands: Vec<CrowCommand> },
+}
+
+/// Imports the commands of a file (e.g. created by `crow export` on another machine) into the
+/// crow_db file and prints how many commands were added, skipped, overwritten or kept.
+pub fn run(arg_matches: &ArgMatches, config: Config) -> Result<(), Error> {
+    let import_path = Path::new(arg_matches.value_of("file").expect("Has file"));
+    let strategy = MergeStrategy::parse(arg_matches.value_of("merge_strategy").unwrap_or("skip"))
+        .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+
+    let content = read_to_string(import_path)?;
+    let incoming = parse_import(&content, import_path.extension() == Some("jsonl".as_ref()))
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Could not parse {:?}. {}", import_path, e),
+            )
+        })?;
+
+    let connection = CrowDBConnection::new(FilePath::from_args(Some(arg_matches), &config))
+        .set_sort_order(config.sort_db);
+
+    let mut commands = connection.commands().to_vec();
+    let report = merge_commands(&mut commands, incoming, strategy, config.duplicate_key);
+
+    connection.set_commands(commands).write();
+
+    println!(
+        "Imported commands: {} added, {} skipped, {} overwritten, {} kept both",
+        report.added, report.skipped, report.overwritten, report.kept_both
+    );
+
+    Ok(())
+}
+
+/// Parses the content of an import file, which is either json or json lines (one command per
+/// line).
+fn parse_import(content: &str, json_lines: bool) -> Result<Vec<CrowCommand>, serde_json::Error> {
+    if json_lines {
+        return content
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect();
+    }
+
+    Ok(match serde_json::from_str(content)? {
+        ImportFile::Commands(commands) => commands,
+        ImportFile::Database { commands } => commands,
+    })
+}
+
+/// Merges the incoming commands into the existing ones according to the given strategy.
+/// An incoming command conflicts with the existing command with the same id, or otherwise with
+/// the existing command it is a duplicate of according to the given duplicate key.
+pub fn merge_commands(
+    existing: &mut Vec<CrowCommand>,
+    incoming: Vec<CrowCommand>,
+    strategy: MergeStrategy,
+    duplicate_key: DuplicateKey,
+) -> MergeReport {
+    let mut report = MergeReport::default();
+
+    for mut command in incoming {
+        let conflict = existing
+            .iter()
+            .position(|c| c.id == command.id)
+            .or_else(|| {
+                existing
+                    .iter()
+                    .position(|c| duplicate_key.is_duplicate(c, &command))
+            });
+
+        match (conflict, strategy) {
+            (None, _) => {
+                existing.push(command);
+                report.added += 1;
+            }
+            (Some(_), MergeStrategy::Skip) => report.skipped += 1,
+            (Some(index), MergeStrategy::Overwrite) => {
+                existing[index] = command;
+                report.overwritten += 1;
+            }
+            (Some(_), MergeStrategy::KeepBoth) => {
+                while existing.iter().any(|c| c.id == command.id) {
+                    command.id = nanoid!();
+                }
+
+                existing.push(command);
+                report.kept_both += 1;
+            }
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use nanoid::nanoid;
+
+    use crate::{
+        config::{Config, DuplicateKey},
+        crow_commands::CrowCommand,
+        crow_db::{CrowDBConnection, FilePath},
+        initialize_arg_parser,
+    };
+
+    use super::{merge_commands, parse_import, run, MergeReport, MergeStrategy};
+
+    fn existing() -> Vec<CrowCommand> {
+        vec![
+            CrowCommand::test("1", "ls"),
+            CrowCommand::test("2", "git status"),
+        ]
+    }
+
+    fn incoming() -> Vec<CrowCommand> {
+        vec![
+            CrowCommand::test("1", "ls -la"),
+            CrowCommand::test("3", "git status"),
+            CrowCommand::test("4", "docker ps"),
+        ]
+    }
+
+    #[test]
+    fn skips_conflicting_commands() {
+        let mut commands = existing();
+
+        let report = merge_commands(
+            &mut commands,
+            incoming(),
+            MergeStrategy::Skip,
+            DuplicateKey::Command,
+        );
+
+        assert_eq!(
+            report,
+            MergeReport {
+                added: 1,
+                skipped: 2,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            commands,
+            vec![
+                CrowCommand::test("1", "ls"),
+                CrowCommand::test("2", "git status"),
+                CrowCommand::test("4", "docker ps")
+            ]
+        );
+    }
+
+    #[test]
+    fn overwrites_conflicting_commands() {
+        let mut commands = existing();
+
+        let report = merge_commands(
+            &mut commands,
+            incoming(),
+            MergeStrategy::Overwrite,
+            DuplicateKey::Command,
+        );
+
+        assert_eq!(
+            report,
+            MergeReport {
+                added: 1,
+                overwritten: 2,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            commands,
+            vec![
+                CrowCommand::test("1", "ls -la"),
+                CrowCommand::test("3", "git status"),
+                CrowCommand::test("4", "docker ps")
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_both_conflicting_commands_with_unique_ids() {
+        let mut commands = existing();
+
+        let report = merge_commands(
+            &mut commands,
+            incoming(),
+            MergeStrategy::KeepBoth,
+            DuplicateKey::Command,
+        );
+
+        assert_eq!(
+            report,
+            MergeReport {
+                added: 1,
+                kept_both: 2,
+                ..Default::default()
+            }
+        );
+        assert_eq!(commands.len(), 5);
+        assert_ne!(commands[2].id, "1");
+        assert_eq!(commands[2].command, "ls -la");
+        assert_eq!(commands[3], CrowCommand::test("3", "git status"));
+    }
+
+    #[test]
+    fn detects_conflicts_with_the_configured_duplicate_key() {
+        let mut commands = existing();
+        commands[1].description = "show repo status".to_string();
+        let mut incoming = incoming();
+        incoming[1].description = "show the status".to_string();
+
+        let report = merge_commands(
+            &mut commands,
+            incoming,
+            MergeStrategy::Skip,
+            DuplicateKey::CommandDescription,
+        );
+
+        assert_eq!(
+            report,
+            MergeReport {
+                added: 2,
+                skipped: 1,
+                ..Default::default()
+            }
+        );
+        assert_eq!(commands[2].id, "3");
+        assert_eq!(commands[2].command, "git status");
+    }
+
+    #[test]
+    fn imports_commands_with_taken_ids_without_replacing_existing_commands() {
+        let fn_path = &format!("./testdata/tmp/{}", nanoid!());
+        let file_path = FilePath::new(Some(fn_path), Some("crow.json"));
+        CrowDBConnection::new(file_path.clone())
+            .set_commands(existing())
+            .write();
+
+        let import_file = format!("{}/export.json", fn_path);
+        let imported = vec![CrowCommand::test("1", "ls -la")];
+        fs::write(&import_file, serde_json::to_string(&imported).unwrap()).unwrap();
+
+        let import = |strategy: &str| {
+            let matches = initialize_arg_parser().get_matches_from(vec![
+                "crow",
+                "import",
+                &import_file,
+                "--merge-strategy",
+                strategy,
+                "--path",
+                fn_path,
+                "--file",
+                "crow.json",
+            ]);
+            run(
+                matches.subcommand_matches("import").unwrap(),
+                Config::default(),
+            )
+            .unwrap();
+
+            CrowDBConnection::new(file_path.clone()).commands().to_vec()
+        };
+
+        assert_eq!(import("skip"), existing());
+
+        let commands = import("keep-both");
+        assert_eq!(commands.len(), 3);
+        assert_eq!(commands[..2], existing()[..]);
+        assert_ne!(commands[2].id, "1");
+        assert_eq!(commands[2].command, "ls -la");
+
+        fs::remove_dir_all(fn_path).unwrap();
+    }
+
+    #[test]
+    fn parses_export_and_database_files() {
+        let expected = vec![CrowCommand::test("1", "ls")];
+
+        assert_eq!(
+            parse_import(r#"[{"id":"1","command":"ls","description":""}]"#, false).unwrap(),
+            expected
+        );
+        assert_eq!(
+            parse_import(
+                r#"{"commands":[{"id":"1","command":"ls","description":""}]}"#,
+                false
+            )
+            .unwrap(),
+            expected
+        );
+        assert_eq!(
+            parse_import(
+                "{\"id\":\"1\",\"command\":\"ls\",\"description\":\"\"}\n",
+                true
+            )
+            .unwrap(),
+            expected
+        );
+        assert!(parse_import("{}", false).is_err());
+    }
+}
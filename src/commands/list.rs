@@ -0,0 +1,234 @@
+use clap::ArgMatches;
+
+use crate::{
+    command_scores::CommandScore,
+    config::Config,
+    crow_commands::CrowCommand,
+    crow_db::{CrowDBConnection, FilePath},
+    date::parse_date,
+    eject, exit_code,
+    filter::{filter_commands, parse_filter_args, split_query},
+    fuzzy::{fuzzy_search_commands, SearchWeights},
+};
+
+use std::io::{Error, ErrorKind};
+
+/// Prints all saved commands (or only the ones matching an optional fuzzy query and `--filter`s) to stdout
+/// without starting the interactive mode, one command per line and ordered by score.
+/// The ranking is the same as in the interactive mode. With `--limit` at most this number of
+/// commands is printed.
+/// Commands with the same score are ordered by the `browse_sort` config (or by `--sort`).
+/// This is also used for `crow search --print`, which exits with [exit_code::NO_SELECTION] if
+/// nothing matched and can print the commands as json with `--format json`.
+/// With `--count` only the number of matching commands is printed and crow exits with a non-zero
+/// exit code if nothing matched, so that scripts can branch on whether a command exists.
+pub fn run(arg_matches: &ArgMatches, config: Config) -> Result<(), Error> {
+    let connection = CrowDBConnection::new(FilePath::from_args(Some(arg_matches), &config));
+
+    let filters = parse_filter_args(arg_matches.values_of("filter").into_iter().flatten())
+        .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+
+    let limit = arg_matches
+        .value_of("limit")
+        .map(|limit| limit.parse::<usize>())
+        .transpose()
+        .map_err(|e| Error::new(ErrorKind::InvalidInput, format!("Invalid --limit. {}", e)))?;
+
+    let since = parse_date_arg(arg_matches, "since")?;
+    let before = parse_date_arg(arg_matches, "before")?;
+    let include_undated = arg_matches.is_present("include_undated");
+
+    let mut commands: Vec<CrowCommand> = filter_commands(connection.commands().to_vec(), &filters)
+        .into_iter()
+        .filter(|c| saved_within(c, since, before, include_undated))
+        .collect();
+
+    // Like in the interactive mode the browse order breaks ties between equal scores
+    let browse_sort = match arg_matches.value_of("sort") {
+        Some(sort) => sort
+            .parse()
+            .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?,
+        None => config.browse_sort,
+    };
+    browse_sort.sort(&mut commands);
+
+    let mut matches = matching_commands(
+        &commands,
+        arg_matches.value_of("query").unwrap_or(""),
+        &config.weights,
+    );
+
+    if let Some(limit) = limit {
+        matches.truncate(limit);
+    }
+
+    if arg_matches.is_present("count") {
+        println!("{}", matches.len());
+
+        if matches.is_empty() {
+            std::process::exit(exit_code::NO_SELECTION);
+        }
+
+        return Ok(());
+    }
+
+    let format = arg_matches.value_of("format").unwrap_or("plain");
+    for command in &matches {
+        match format_command(command, format) {
+            Ok(line) => println!("{}", line),
+            Err(e) => eject(&format!("Could not print command as json. {}", e)),
+        }
+    }
+
+    if matches.is_empty() && arg_matches.is_present("print") {
+        std::process::exit(exit_code::NO_SELECTION);
+    }
+
+    Ok(())
+}
+
+/// Formats a matching command as output line: the command text for the `plain` format or all
+/// fields of the command as single line json object for the `json` format
+pub fn format_command(command: &CrowCommand, format: &str) -> Result<String, serde_json::Error> {
+    match format {
+        "json" => serde_json::to_string(command),
+        _ => Ok(command.command.clone()),
+    }
+}
+
+/// Parses the date of the given argument (see [parse_date]), if it is present
+fn parse_date_arg(arg_matches: &ArgMatches, name: &str) -> Result<Option<u64>, Error> {
+    arg_matches
+        .value_of(name)
+        .map(parse_date)
+        .transpose()
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid --{}. {}", name, e),
+            )
+        })
+}
+
+/// Checks whether the command has been saved at or after `since` and before `before`.
+/// Commands without a saving date only match if `include_undated` is set or no date is given.
+pub fn saved_within(
+    command: &CrowCommand,
+    since: Option<u64>,
+    before: Option<u64>,
+    include_undated: bool,
+) -> bool {
+    match command.created_at {
+        _ if since.is_none() && before.is_none() => true,
+        Some(created_at) => {
+            since.into_iter().all(|since| created_at >= since)
+                && before.into_iter().all(|before| created_at < before)
+        }
+        None => include_undated,
+    }
+}
+
+/// Fuzzy searches the given commands and returns the matching commands ordered by their score.
+/// Filter predicates inside the query (e.g. `has:description`) are applied before fuzzy searching.
+/// An empty query matches all commands.
+pub fn matching_commands(
+    commands: &[CrowCommand],
+    query: &str,
+    weights: &SearchWeights,
+) -> Vec<CrowCommand> {
+    let (predicates, pattern) = split_query(query);
+
+    fuzzy_search_commands(
+        filter_commands(commands.to_vec(), &predicates),
+        &pattern,
+        weights,
+    )
+    .into_iter()
+    .filter_map(CommandScore::into_command)
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{crow_commands::CrowCommand, fuzzy::SearchWeights};
+
+    use super::{format_command, matching_commands, saved_within};
+
+    #[test]
+    fn returns_matching_commands_by_score() {
+        let command_1 = CrowCommand {
+            id: "1".to_string(),
+            command: "docker ps".to_string(),
+            description: "list containers".to_string(),
+            ..Default::default()
+        };
+        let command_2 = CrowCommand {
+            id: "2".to_string(),
+            command: "git status".to_string(),
+            description: "show repo status".to_string(),
+            ..Default::default()
+        };
+        let commands = [command_1.clone(), command_2.clone()];
+
+        assert_eq!(
+            matching_commands(&commands, "", &SearchWeights::default()),
+            vec![command_1.clone(), command_2.clone()]
+        );
+        assert_eq!(
+            matching_commands(&commands, "git status", &SearchWeights::default()),
+            vec![command_2]
+        );
+        assert_eq!(
+            matching_commands(&commands, "zzzzzz", &SearchWeights::default()),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn excludes_commands_matching_negative_terms() {
+        let commands = [
+            CrowCommand::test("1", "docker compose up"),
+            CrowCommand::test("2", "docker ps"),
+            CrowCommand::test("3", "git status"),
+        ];
+
+        assert_eq!(
+            matching_commands(&commands, "docker !compose", &SearchWeights::default()),
+            vec![commands[1].clone()]
+        );
+    }
+
+    #[test]
+    fn filters_commands_by_saving_date() {
+        let legacy_command = CrowCommand::test("1", "ls");
+        let saved_at_9 = legacy_command.clone().with_created_at(9);
+        let saved_at_10 = legacy_command.clone().with_created_at(10);
+        let saved_at_20 = legacy_command.clone().with_created_at(20);
+
+        assert!(saved_within(&saved_at_10, Some(10), Some(20), false));
+        assert!(!saved_within(&saved_at_20, Some(10), Some(20), false));
+        assert!(!saved_within(&saved_at_9, Some(10), None, false));
+        assert!(saved_within(&saved_at_9, None, Some(10), false));
+
+        // Legacy commands without a date
+        assert!(saved_within(&legacy_command, None, None, false));
+        assert!(!saved_within(&legacy_command, Some(10), None, false));
+        assert!(saved_within(&legacy_command, Some(10), None, true));
+    }
+
+    #[test]
+    fn formats_commands_as_plain_text_or_json() {
+        let command = CrowCommand {
+            id: "1".to_string(),
+            command: "echo \"hi\"".to_string(),
+            description: "greets".to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(format_command(&command, "plain").unwrap(), "echo \"hi\"");
+
+        let json = format_command(&command, "json").unwrap();
+        assert!(!json.contains('\n'));
+        assert_eq!(serde_json::from_str::<CrowCommand>(&json).unwrap(), command);
+    }
+}
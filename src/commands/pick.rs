@@ -0,0 +1,127 @@
+use clap::ArgMatches;
+
+use crate::{
+    commands::first::copy_or_print,
+    config::Config,
+    crow_commands::CrowCommand,
+    crow_db::{CrowDBConnection, FilePath},
+    eject, exit_code,
+    filter::{filter_commands, parse_filter_args},
+    sanitize::printable,
+};
+
+use std::{
+    io::{Error, ErrorKind, Write},
+    process::{Command, Stdio},
+};
+
+/// Lets the user pick a command with `fzf` instead of the interactive mode of crow and copies
+/// (or with `--print` prints) the picked command. Exits with [exit_code::NO_SELECTION] if `fzf`
+/// is cancelled or nothing matched.
+pub fn run(arg_matches: &ArgMatches, config: Config) -> Result<(), Error> {
+    let connection = CrowDBConnection::new(FilePath::from_args(Some(arg_matches), &config));
+
+    let filters = parse_filter_args(arg_matches.values_of("filter").into_iter().flatten())
+        .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+    let commands = filter_commands(connection.commands().to_vec(), &filters);
+
+    // Only the label and the description are shown, the index in front of them is used to find
+    // the picked command again
+    let mut fzf = Command::new("fzf")
+        .args(["--delimiter", "\t", "--with-nth", "2..", "--no-multi"])
+        .args(["--query", arg_matches.value_of("query").unwrap_or("")])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap_or_else(|e| eject(&format!("Could not start fzf, is it installed? {}", e)));
+
+    let written = fzf
+        .stdin
+        .take()
+        .expect("Has stdin")
+        .write_all(fzf_input(&commands).as_bytes());
+    let output = fzf.wait_with_output()?;
+
+    match written {
+        // fzf stops reading if something is picked before all commands have been written
+        Err(e) if e.kind() != ErrorKind::BrokenPipe => return Err(e),
+        _ => {}
+    }
+
+    match picked_command(&String::from_utf8_lossy(&output.stdout), &commands) {
+        Some(command) if output.status.success() => {
+            copy_or_print(
+                command.command.clone(),
+                arg_matches.is_present("print"),
+                &config,
+            );
+            Ok(())
+        }
+        _ => std::process::exit(exit_code::NO_SELECTION),
+    }
+}
+
+/// Creates the lines which are piped to `fzf`, one line per command consisting of the index of
+/// the command, the command and the first description line separated by tabs.
+/// Line breaks, tabs and other control chars are replaced (see [printable]), so that every
+/// command stays on its own line.
+pub fn fzf_input(commands: &[CrowCommand]) -> String {
+    commands
+        .iter()
+        .enumerate()
+        .map(|(index, c)| {
+            let description = c.description.lines().next().unwrap_or("");
+            format!(
+                "{}\t{}\t{}\n",
+                index,
+                printable(&c.command).replace('\n', "␊"),
+                printable(description)
+            )
+        })
+        .collect()
+}
+
+/// Returns the command of the line which has been picked in `fzf` (see [fzf_input])
+pub fn picked_command<'a>(output: &str, commands: &'a [CrowCommand]) -> Option<&'a CrowCommand> {
+    output
+        .split('\t')
+        .next()
+        .and_then(|index| index.trim().parse::<usize>().ok())
+        .and_then(|index| commands.get(index))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crow_commands::CrowCommand;
+
+    use super::{fzf_input, picked_command};
+
+    #[test]
+    fn creates_fzf_lines_and_finds_picked_command() {
+        let command_1 = CrowCommand {
+            id: "1".to_string(),
+            command: "docker ps".to_string(),
+            description: "list containers\nincluding stopped ones".to_string(),
+            ..Default::default()
+        };
+        let command_2 = CrowCommand {
+            id: "2".to_string(),
+            command: "for f in *; do\n  echo $f\ndone".to_string(),
+            ..Default::default()
+        };
+        let commands = [command_1, command_2.clone()];
+
+        let input = fzf_input(&commands);
+
+        assert_eq!(
+            input,
+            "0\tdocker ps\tlist containers\n1\tfor f in *; do␊  echo $f␊done\t\n"
+        );
+        assert_eq!(
+            picked_command(input.lines().nth(1).unwrap(), &commands),
+            Some(&command_2)
+        );
+        assert_eq!(picked_command("", &commands), None);
+        assert_eq!(picked_command("5\tls\t\n", &commands), None);
+    }
+}
@@ -0,0 +1,127 @@
+use clap::ArgMatches;
+use crossterm::style::Color;
+use nanoid::nanoid;
+
+use crate::{
+    color::paint,
+    config::Config,
+    crow_commands::{CrowCommand, Id},
+    crow_db::{CrowDBConnection, FilePath},
+};
+
+use std::{collections::HashSet, io::Error};
+
+/// Regenerates the ids of all commands whose id is empty or already used by a previous command
+/// (e.g. in older or hand-edited database files), because these commands would otherwise replace
+/// each other when they are loaded. The database file is backed up before the repaired commands
+/// are written, and every changed id is printed.
+pub fn run(arg_matches: &ArgMatches, config: Config) -> Result<(), Error> {
+    let connection = CrowDBConnection::new(FilePath::from_args(Some(arg_matches), &config))
+        .set_sort_order(config.sort_db);
+
+    let mut commands = connection.commands().to_vec();
+    let changes = repair_ids(&mut commands);
+
+    if changes.is_empty() {
+        println!("All command ids in {} are valid.", connection.path());
+        return Ok(());
+    }
+
+    let backup_path = connection.backup()?;
+
+    for (old_id, command) in changes
+        .iter()
+        .map(|(old_id, index)| (old_id, &commands[*index]))
+    {
+        println!(
+            "Changed id of '{}' from '{}' to '{}'",
+            paint(&command.command, Color::Cyan, config.color),
+            old_id,
+            command.id
+        );
+    }
+
+    let mut connection = connection.set_commands(commands);
+    connection.write();
+
+    println!(
+        "\nRepaired {} commands in {}\nA backup has been saved to {}",
+        changes.len(),
+        connection.path(),
+        backup_path.display()
+    );
+
+    Ok(())
+}
+
+/// Gives every command with an empty or duplicate id a new, unique id. The first command with a
+/// given id keeps it. Returns the previous id and the index of every changed command.
+pub fn repair_ids(commands: &mut [CrowCommand]) -> Vec<(Id, usize)> {
+    let mut used_ids: HashSet<Id> = commands.iter().map(|c| c.id.clone()).collect();
+    let mut seen_ids: HashSet<Id> = HashSet::new();
+    let mut changes = vec![];
+
+    for (index, command) in commands.iter_mut().enumerate() {
+        if !command.id.trim().is_empty() && seen_ids.insert(command.id.clone()) {
+            continue;
+        }
+
+        let mut new_id = nanoid!();
+        while used_ids.contains(&new_id) {
+            new_id = nanoid!();
+        }
+        used_ids.insert(new_id.clone());
+        seen_ids.insert(new_id.clone());
+
+        changes.push((std::mem::replace(&mut command.id, new_id), index));
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use nanoid::nanoid;
+
+    use crate::{
+        crow_commands::Commands,
+        crow_db::{CrowDBConnection, FilePath},
+    };
+
+    use super::repair_ids;
+
+    #[test]
+    fn regenerates_duplicate_and_empty_ids() {
+        let fn_path = &format!("./testdata/tmp/{}", nanoid!());
+        fs::create_dir_all(fn_path).unwrap();
+        fs::copy(
+            "./testdata/duplicate_ids/crow.json",
+            Path::new(fn_path).join("crow.json"),
+        )
+        .unwrap();
+        let file_path = FilePath::new(Some(fn_path), Some("crow.json"));
+
+        let connection = CrowDBConnection::new(file_path.clone());
+        let mut commands = connection.commands().to_vec();
+
+        // Without repair the commands with the same id replace each other
+        assert_eq!(Commands::normalize(&commands).len(), 2);
+
+        let changes = repair_ids(&mut commands);
+
+        assert_eq!(
+            changes,
+            vec![("docker-ps".to_string(), 1), ("".to_string(), 2)]
+        );
+        connection.set_commands(commands).write();
+
+        let commands = CrowDBConnection::new(file_path).commands().to_vec();
+        assert_eq!(commands[0].id, "docker-ps");
+        assert_eq!(Commands::normalize(&commands).len(), 3);
+        assert!(commands.iter().all(|c| !c.id.is_empty()));
+
+        fs::remove_dir_all(Path::new(fn_path)).unwrap();
+    }
+}
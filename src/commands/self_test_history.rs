@@ -0,0 +1,63 @@
+use clap::ArgMatches;
+use crossterm::style::Color;
+use dirs::home_dir;
+
+use crate::{color::paint, config::Config, exit_code, history::Shell};
+
+use std::{env, io::Error, path::PathBuf};
+
+/// History fixtures inside the fixtures directory (`testdata` of the repository) together with
+/// the expected last command.
+const FIXTURES: &[(Shell, &str, Option<&str>)] = &[
+    (Shell::Bash, "", Some("echo \"Hi from test history\"")),
+    (Shell::Zsh, "", Some("echo 'Hi from test zsh_history'")),
+    (Shell::Fish, "", Some("echo 'Hi from test fish_history'")),
+    (Shell::Bash, "only_crow_history", None),
+];
+
+/// Developer command which reads the last command of every bundled history fixture and compares
+/// it with the expected result, so that history parsing can be verified on other systems without
+/// running the test suite. Afterwards the last command of the users own history is printed.
+/// Exits with a non-zero exit code if any fixture fails.
+pub fn run(arg_matches: &ArgMatches, config: Config) -> Result<(), Error> {
+    let fixtures_dir = PathBuf::from(arg_matches.value_of("fixtures").unwrap_or("./testdata"));
+    let mut failures = 0;
+
+    for (shell, dir, expected) in FIXTURES {
+        let result =
+            shell.read_last_history_command(&shell.history_path(&fixtures_dir.join(dir), None));
+        let name = format!("{:?} {:?}", shell, fixtures_dir.join(dir));
+
+        if result.as_deref() == *expected {
+            println!("{} {}", paint("PASS", Color::Green, config.color), name);
+        } else {
+            failures += 1;
+            println!(
+                "{} {}\n  expected: {:?}\n  got:      {:?}",
+                paint("FAIL", Color::Red, config.color),
+                name,
+                expected,
+                result
+            );
+        }
+    }
+
+    let own_shell = env::var("SHELL").ok().and_then(Shell::from_path);
+    match (own_shell, home_dir()) {
+        (Some(shell), Some(home)) if !shell.user_history_path(&home).exists() => {
+            println!("\nYour {:?} history file does not exist", shell)
+        }
+        (Some(shell), Some(home)) => println!(
+            "\nLast command of your {:?} history: {:?}",
+            shell,
+            shell.read_last_history_command(&shell.user_history_path(&home))
+        ),
+        _ => println!("\nYour shell is not supported"),
+    }
+
+    if failures > 0 {
+        std::process::exit(exit_code::ERROR);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use clap::ArgMatches;
+
+use crate::{
+    config::Config,
+    crow_commands::{unix_timestamp, CrowCommand},
+    crow_db::{CrowDBConnection, FilePath},
+};
+
+use std::io::{Error, ErrorKind};
+
+const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
+
+/// Prints all commands which have not been used (copied from the fuzzy mode) within the last
+/// `--days` days, so that they can be cleaned up. Commands which have never been used are listed
+/// first.
+pub fn run(arg_matches: &ArgMatches, config: Config) -> Result<(), Error> {
+    let days = arg_matches
+        .value_of("days")
+        .unwrap_or("30")
+        .parse::<u64>()
+        .map_err(|e| Error::new(ErrorKind::InvalidInput, format!("Invalid --days. {}", e)))?;
+
+    let connection = CrowDBConnection::new(FilePath::from_args(Some(arg_matches), &config));
+    let now = unix_timestamp();
+
+    for command in stale_commands(connection.commands(), days, now) {
+        let last_used = match command.last_used_at {
+            Some(timestamp) => format!(
+                "last used {} days ago",
+                now.saturating_sub(timestamp) / SECONDS_PER_DAY
+            ),
+            None => "never used".to_string(),
+        };
+
+        println!("{} ({})", command.command, last_used);
+    }
+
+    Ok(())
+}
+
+/// Returns all commands which have not been used within `days` days before `now`, ordered from
+/// the longest unused (or never used) command to the most recently used one.
+pub fn stale_commands(commands: &[CrowCommand], days: u64, now: u64) -> Vec<CrowCommand> {
+    let threshold = now.saturating_sub(days * SECONDS_PER_DAY);
+
+    let mut stale: Vec<CrowCommand> = commands
+        .iter()
+        .filter(|c| !matches!(c.last_used_at, Some(t) if t >= threshold))
+        .cloned()
+        .collect();
+
+    // The reverse of the most recently used first order, so never used commands come first
+    stale.sort_by(|a, b| b.cmp_by_recent_use(a));
+
+    stale
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crow_commands::CrowCommand;
+
+    use super::{stale_commands, SECONDS_PER_DAY};
+
+    #[test]
+    fn returns_commands_not_used_within_days() {
+        let now = 100 * SECONDS_PER_DAY;
+
+        let never_used = CrowCommand {
+            id: "1".to_string(),
+            command: "ls".to_string(),
+            ..Default::default()
+        };
+        let used_recently = CrowCommand {
+            id: "2".to_string(),
+            command: "git status".to_string(),
+            last_used_at: Some(now - 2 * SECONDS_PER_DAY),
+            ..Default::default()
+        };
+        let used_long_ago = CrowCommand {
+            id: "3".to_string(),
+            command: "docker ps".to_string(),
+            last_used_at: Some(now - 40 * SECONDS_PER_DAY),
+            ..Default::default()
+        };
+        let commands = [used_long_ago.clone(), used_recently, never_used.clone()];
+
+        assert_eq!(
+            stale_commands(&commands, 30, now),
+            vec![never_used.clone(), used_long_ago]
+        );
+        assert_eq!(stale_commands(&commands, 50, now), vec![never_used]);
+    }
+}
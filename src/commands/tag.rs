@@ -0,0 +1,94 @@
+use clap::ArgMatches;
+
+use crate::{
+    config::Config,
+    crow_db::{CrowDBConnection, FilePath},
+    exit_code,
+};
+
+use std::io::Error;
+
+/// Adds the given tags to (or with `--remove` removes them from) the command with the given id
+/// and writes the result to the crow_db file. This allows to manage tags from scripts.
+pub fn run(arg_matches: &ArgMatches, config: Config) -> Result<(), Error> {
+    let id = arg_matches.value_of("id").expect("Has id");
+    let tags: Vec<String> = arg_matches
+        .values_of("tags")
+        .expect("Has tags")
+        .map(str::to_string)
+        .collect();
+
+    let connection = CrowDBConnection::new(FilePath::from_args(Some(arg_matches), &config))
+        .set_sort_order(config.sort_db);
+
+    let mut commands = connection.commands().to_vec();
+    let command = commands
+        .iter_mut()
+        .find(|c| c.id == id)
+        .ok_or_else(|| exit_code::unknown_id(id))?;
+
+    if arg_matches.is_present("remove") {
+        command.remove_tags(&tags);
+    } else {
+        command.add_tags(&tags);
+    }
+
+    println!("Tags of '{}': {}", command.command, command.tags.join(", "));
+
+    connection.set_commands(commands).write();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use nanoid::nanoid;
+
+    use crate::{
+        config::Config,
+        crow_commands::CrowCommand,
+        crow_db::{CrowDBConnection, FilePath},
+        exit_code, initialize_arg_parser,
+    };
+
+    use super::run;
+
+    #[test]
+    fn adds_and_removes_tags() {
+        let fn_path = &format!("./testdata/tmp/{}", nanoid!());
+        let file_path = FilePath::new(Some(fn_path), Some("crow.json"));
+        CrowDBConnection::new(file_path.clone())
+            .set_commands(vec![
+                CrowCommand::test("1", "docker ps").with_tags(&["docker"]),
+                CrowCommand::test("2", "ls"),
+            ])
+            .write();
+
+        let tag = |args: &[&str]| {
+            let mut all_args = vec!["crow", "tag", "--path", fn_path, "--file", "crow.json"];
+            all_args.extend_from_slice(args);
+            let matches = initialize_arg_parser().get_matches_from(all_args);
+
+            run(
+                matches.subcommand_matches("tag").unwrap(),
+                Config::default(),
+            )
+        };
+
+        tag(&["1", "Containers", "docker", "ops"]).unwrap();
+        let commands = CrowDBConnection::new(file_path.clone()).commands().to_vec();
+        assert_eq!(commands[0].tags, vec!["docker", "containers", "ops"]);
+        assert!(commands[1].tags.is_empty());
+
+        tag(&["--remove", "1", "DOCKER", "ops", "missing"]).unwrap();
+        let commands = CrowDBConnection::new(file_path.clone()).commands().to_vec();
+        assert_eq!(commands[0].tags, vec!["containers"]);
+
+        let error = tag(&["unknown", "ops"]).unwrap_err();
+        assert_eq!(exit_code::from_error(&error), exit_code::NOT_FOUND);
+
+        fs::remove_dir_all(fn_path).unwrap();
+    }
+}
@@ -0,0 +1,27 @@
+use clap::ArgMatches;
+
+use crate::{
+    commands::doctor::{describe_file, resolve_files},
+    config::Config,
+};
+
+use std::io::Error;
+
+/// Prints the database and config file which crow resolves from the arguments, environment
+/// variables and default locations, together with whether each of them exists.
+/// The files are resolved and described like in `crow doctor` (see [resolve_files]).
+pub fn run(
+    arg_matches: &ArgMatches,
+    config_arg: Option<&str>,
+    config: Config,
+) -> Result<(), Error> {
+    let (db_path, config_path) = resolve_files(arg_matches, config_arg, &config);
+
+    println!(
+        "{}",
+        describe_file("Database file", Some(db_path.as_path()))
+    );
+    println!("{}", describe_file("Config file", config_path.as_deref()));
+
+    Ok(())
+}
@@ -0,0 +1,587 @@
+//! User configuration of crow which is read from an optional toml file
+//! (typically `$HOME/.config/crow/config.toml` on UNIX systems).
+//!
+//! The config file is discovered in the following order:
+//! 1. `--config <path>` argument
+//! 2. `$CROW_CONFIG` environment variable
+//! 3. `$XDG_CONFIG_HOME/crow/config.toml`
+//! 4. `$HOME/.config/crow/config.toml`
+
+use serde::{de::IntoDeserializer, Deserialize};
+use std::{
+    env,
+    fs::read_to_string,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use dirs::home_dir;
+use log::debug;
+
+use crate::{
+    crow_commands::CrowCommand, eject, fuzzy::SearchWeights, ids::IdScheme, rendering::BorderStyle,
+};
+
+/// The file format commands are stored in
+#[derive(Deserialize, Debug, Default, Copy, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Storage {
+    /// A single json document containing all commands (default)
+    #[default]
+    Json,
+    /// One json document per command and line. New commands are appended to the file instead of
+    /// rewriting the whole file, which is faster for very frequent adds.
+    Jsonl,
+}
+
+impl Storage {
+    /// Returns the default database file name for this storage format
+    pub fn default_file_name(&self) -> &'static str {
+        match self {
+            Self::Json => "crow_db.json",
+            Self::Jsonl => "crow_db.jsonl",
+        }
+    }
+}
+
+/// A stable order in which commands are written to the database file, so that diffs of a version
+/// controlled database stay minimal
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    /// Sorted by the id of the command
+    Id,
+    /// Sorted by the command text (and by id for identical commands)
+    Command,
+}
+
+impl SortOrder {
+    /// Sorts the given commands in place
+    pub fn sort(&self, commands: &mut [CrowCommand]) {
+        match self {
+            Self::Id => commands.sort_by(CrowCommand::cmp_by_id),
+            Self::Command => commands.sort_by(CrowCommand::cmp_by_command),
+        }
+    }
+}
+
+/// Order of the command list while the search input is empty
+#[derive(Deserialize, Debug, Default, Copy, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BrowseSort {
+    /// The order of the database file, which can be changed with ctrl+↑/↓ (default)
+    #[default]
+    Manual,
+    /// Most recently used commands first, never used commands last
+    Recent,
+    /// Alphabetically by the command text
+    Alpha,
+    /// Most frequently used commands first
+    #[serde(alias = "frequency")]
+    Usage,
+}
+
+impl BrowseSort {
+    /// Values of `browse_sort` in the config, which are accepted by `crow search --sort` as well
+    pub const VALUES: &'static [&'static str] =
+        &["manual", "recent", "alpha", "usage", "frequency"];
+
+    /// Sorts the given commands in place. The sort is stable, so commands which are equal
+    /// according to this order keep the order of the database file.
+    pub fn sort(&self, commands: &mut [CrowCommand]) {
+        match self {
+            Self::Manual => {}
+            Self::Recent => commands.sort_by(CrowCommand::cmp_by_recent_use),
+            Self::Alpha => commands.sort_by(CrowCommand::cmp_by_command_ignore_case),
+            Self::Usage => commands.sort_by(CrowCommand::cmp_by_usage),
+        }
+    }
+}
+
+/// Parses the values of `browse_sort` (see [BrowseSort::VALUES]), e.g. of the `--sort` argument
+impl FromStr for BrowseSort {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::deserialize(value.into_deserializer())
+            .map_err(|e: serde::de::value::Error| format!("Invalid sort '{}'. {}", value, e))
+    }
+}
+
+/// What makes a new command a duplicate of an already saved command
+#[derive(Deserialize, Debug, Default, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateKey {
+    /// The same command text (default)
+    #[default]
+    Command,
+    /// The same command text and description, so that a command can intentionally be saved
+    /// multiple times with different descriptions
+    CommandDescription,
+}
+
+impl DuplicateKey {
+    /// Checks whether both commands are duplicates of each other. Surrounding whitespace is
+    /// ignored.
+    pub fn is_duplicate(&self, a: &CrowCommand, b: &CrowCommand) -> bool {
+        let same_command = a.command.trim() == b.command.trim();
+
+        match self {
+            Self::Command => same_command,
+            Self::CommandDescription => {
+                same_command && a.description.trim() == b.description.trim()
+            }
+        }
+    }
+
+    /// Whether duplicates can only be detected once the description of a new command is known
+    pub fn depends_on_description(&self) -> bool {
+        matches!(self, Self::CommandDescription)
+    }
+
+    /// Returns the first of the given commands which is a duplicate of the command
+    pub fn find_duplicate<'a>(
+        &self,
+        commands: &'a [CrowCommand],
+        command: &CrowCommand,
+    ) -> Option<&'a CrowCommand> {
+        commands.iter().find(|c| self.is_duplicate(c, command))
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Maximum number of fuzzy search results which are rendered inside the command list.
+    /// All results are rendered if this is not set. At least one result is always rendered, so
+    /// that a command can be selected.
+    pub max_results: Option<NonZeroUsize>,
+
+    /// Maximum number of characters of a description. Longer descriptions are not rejected, but
+    /// the user is warned and offered to truncate them when adding or editing a command.
+    pub max_description_len: Option<usize>,
+
+    /// The file format of the default database file
+    pub storage: Storage,
+
+    /// Template of the text which is copied when pressing enter, see
+    /// [crate::clipboard::apply_copy_template] for the available placeholders
+    pub copy_template: String,
+
+    /// Whether the selected command is copied whenever the selection changes, so that crow can
+    /// be quit with the last viewed command already in the clipboard
+    pub copy_on_select: bool,
+
+    /// Whether the description is added as a comment line when copying a command as code block
+    pub code_block_description: bool,
+
+    /// Whether the clipboard is read back after copying to warn if its content has been changed
+    /// (e.g. by a clipboard manager). Not all clipboard backends support immediate readback.
+    pub verify_clipboard: bool,
+
+    /// Whether shell comments (e.g. `ls -la # show hidden files`) are removed from commands when
+    /// copying them. The saved command keeps its comments.
+    pub strip_comments_on_copy: bool,
+
+    /// Whether ANSI escape sequences and other control chars are removed from commands when
+    /// copying them. They are always shown as visible symbols inside the TUI.
+    pub sanitize_copies: bool,
+
+    /// Order in which commands are written to the database file.
+    /// Commands are written in their list order (which can be changed by the user) if this is not
+    /// set.
+    pub sort_db: Option<SortOrder>,
+
+    /// Order of the command list while the search input is empty
+    pub browse_sort: BrowseSort,
+
+    /// The scheme by which ids of new commands are generated
+    pub id_scheme: IdScheme,
+
+    /// What makes a new command a duplicate of an already saved command, which is warned about
+    /// when adding it
+    pub duplicate_key: DuplicateKey,
+
+    /// Weights of the command and description when ranking fuzzy search results
+    pub weights: SearchWeights,
+
+    /// Whether the list selection wraps around (from the last to the first command and vice
+    /// versa) when navigating with up/down
+    pub wrap_navigation: bool,
+
+    /// Whether the last selected command is remembered per query and selected again when the
+    /// same query is entered
+    pub remember_selection: bool,
+
+    /// Whether the first command is selected after every change of the search input. Otherwise
+    /// the selected command stays selected as long as it matches the input.
+    pub reset_selection_on_type: bool,
+
+    /// Whether commands are syntax highlighted inside the command detail (preview only, the
+    /// copied command is never changed)
+    pub syntax_highlight: bool,
+
+    /// Whether line numbers are shown for multiline commands inside the command detail
+    pub line_numbers: bool,
+
+    /// Whether the first description line of every command is shown (dimmed) below the command
+    /// inside the command list. Can be toggled at runtime with ctrl+l.
+    pub inline_descriptions: bool,
+
+    /// Number of columns between tab stops when tabs of commands are displayed. Tabs are only
+    /// expanded to spaces for rendering, the copied command keeps its tabs.
+    pub tab_width: usize,
+
+    /// The border type of all blocks
+    pub border_style: BorderStyle,
+
+    /// Whether blocks are rendered with titles (e.g. "Commands" or the key hints)
+    pub show_titles: bool,
+
+    /// Whether the output outside of the fuzzy mode (e.g. of `crow add`) is colored.
+    /// Colors are always disabled by `--no-color` and the `NO_COLOR` environment variable.
+    pub color: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_results: None,
+            max_description_len: None,
+            storage: Storage::default(),
+            copy_template: "{command}".to_string(),
+            copy_on_select: false,
+            code_block_description: false,
+            verify_clipboard: false,
+            strip_comments_on_copy: false,
+            sanitize_copies: false,
+            sort_db: None,
+            browse_sort: BrowseSort::default(),
+            id_scheme: IdScheme::default(),
+            duplicate_key: DuplicateKey::default(),
+            weights: SearchWeights::default(),
+            wrap_navigation: true,
+            remember_selection: false,
+            reset_selection_on_type: true,
+            syntax_highlight: false,
+            line_numbers: false,
+            inline_descriptions: false,
+            tab_width: 4,
+            border_style: BorderStyle::default(),
+            show_titles: true,
+            color: true,
+        }
+    }
+}
+
+impl Config {
+    const DEFAULT_CONFIG_FILE: &'static str = "config.toml";
+
+    /// Reads the discovered config file (see [Config::path]) or returns the default config if
+    /// there is no config file.
+    ///
+    /// # Panics
+    /// This function terminates if a config file has been given explicitly (by `--config` or
+    /// `$CROW_CONFIG`) but can't be read.
+    pub fn load(config_arg: Option<&str>) -> Self {
+        match Self::path(config_arg) {
+            Some(path) => {
+                debug!("Using config file: {:?}", path);
+                Self::from_file(path)
+            }
+            None => {
+                debug!("No config file found, using defaults");
+                Self::default()
+            }
+        }
+    }
+
+    /// Returns the path of the config file which is used, according to the discovery order of
+    /// this module. Returns `None` if no config file exists.
+    pub fn path(config_arg: Option<&str>) -> Option<PathBuf> {
+        Self::discover_path(
+            config_arg,
+            env::var("CROW_CONFIG").ok(),
+            env::var("XDG_CONFIG_HOME").ok(),
+            home_dir(),
+        )
+    }
+
+    /// Discovers the config file path from the given sources.
+    /// Explicitly given paths are always returned (even if they don't exist, so that a typo
+    /// leads to an error instead of silently using the defaults), default locations only if the
+    /// file exists.
+    fn discover_path(
+        config_arg: Option<&str>,
+        crow_config: Option<String>,
+        xdg_config_home: Option<String>,
+        home_dir: Option<PathBuf>,
+    ) -> Option<PathBuf> {
+        let explicit_path = config_arg
+            .map(str::to_string)
+            .or(crow_config)
+            .filter(|path| !path.is_empty());
+
+        if let Some(path) = explicit_path {
+            return Some(PathBuf::from(shellexpand::tilde(&path).as_ref()));
+        }
+
+        let xdg_path = xdg_config_home
+            .filter(|path| !path.is_empty())
+            .map(|path| PathBuf::from(path).join("crow"));
+        let home_path = home_dir.map(|path| path.join(".config/crow"));
+
+        xdg_path
+            .into_iter()
+            .chain(home_path)
+            .map(|path| path.join(Self::DEFAULT_CONFIG_FILE))
+            .find(|path| path.exists())
+    }
+
+    /// Reads and parses the given config file.
+    ///
+    /// # Panics
+    /// This function terminates if the file can't be read or contains an invalid configuration.
+    pub fn from_file(path: PathBuf) -> Self {
+        Self::try_from_file(&path).unwrap_or_else(|e| eject(&e))
+    }
+
+    /// Reads and parses the given config file like [Config::from_file], but returns the error
+    /// message instead of terminating.
+    pub fn try_from_file(path: &Path) -> Result<Self, String> {
+        let content = read_to_string(path)
+            .map_err(|e| format!("Could not read config file {:?}. {}", path, e))?;
+
+        Self::parse(&content).map_err(|e| format!("Could not parse config file {:?}. {}", path, e))
+    }
+
+    /// Parses a toml configuration.
+    pub fn parse(content: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{num::NonZeroUsize, path::PathBuf};
+
+    use crate::crow_commands::CrowCommand;
+
+    use crate::{ids::IdScheme, rendering::BorderStyle};
+
+    use super::{BrowseSort, Config, DuplicateKey, SortOrder, Storage};
+
+    #[test]
+    fn discovers_config_path_in_order() {
+        let xdg = Some("./testdata/xdg_config".to_string());
+        let home = Some(PathBuf::from("./testdata/home"));
+        let crow_config = Some("./testdata/crow_config.toml".to_string());
+
+        assert_eq!(
+            Config::discover_path(
+                Some("./custom.toml"),
+                crow_config.clone(),
+                xdg.clone(),
+                home.clone()
+            ),
+            Some(PathBuf::from("./custom.toml"))
+        );
+        assert_eq!(
+            Config::discover_path(None, crow_config, xdg.clone(), home.clone()),
+            Some(PathBuf::from("./testdata/crow_config.toml"))
+        );
+        assert_eq!(
+            Config::discover_path(None, None, xdg, home.clone()),
+            Some(PathBuf::from("./testdata/xdg_config/crow/config.toml"))
+        );
+        assert_eq!(
+            Config::discover_path(None, None, Some("./testdata/missing".to_string()), home),
+            Some(PathBuf::from("./testdata/home/.config/crow/config.toml"))
+        );
+        assert_eq!(
+            Config::discover_path(None, Some("".to_string()), None, None),
+            None
+        );
+    }
+
+    #[test]
+    fn parses_empty_config_as_default() {
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn parses_max_results() {
+        let config = Config::parse("max_results = 20").unwrap();
+
+        assert_eq!(config.max_results, NonZeroUsize::new(20));
+        assert!(Config::parse("max_results = 0").is_err());
+    }
+
+    #[test]
+    fn parses_weights() {
+        let config = Config::parse("[weights]\ndescription = 0.8").unwrap();
+
+        assert_eq!(config.weights.command, 1.0);
+        assert_eq!(config.weights.description, 0.8);
+    }
+
+    #[test]
+    fn wraps_navigation_by_default() {
+        assert!(Config::parse("").unwrap().wrap_navigation);
+        assert!(
+            !Config::parse("wrap_navigation = false")
+                .unwrap()
+                .wrap_navigation
+        );
+    }
+
+    #[test]
+    fn parses_storage() {
+        let config = Config::parse("storage = \"jsonl\"").unwrap();
+
+        assert_eq!(config.storage, Storage::Jsonl);
+        assert_eq!(config.storage.default_file_name(), "crow_db.jsonl");
+        assert!(Config::parse("storage = \"yaml\"").is_err());
+    }
+
+    #[test]
+    fn parses_border_style() {
+        let config = Config::parse("border_style = \"rounded\"\nshow_titles = false").unwrap();
+
+        assert_eq!(config.border_style, BorderStyle::Rounded);
+        assert!(!config.show_titles);
+        assert!(Config::parse("").unwrap().show_titles);
+        assert!(Config::parse("border_style = \"dotted\"").is_err());
+    }
+
+    #[test]
+    fn parses_id_scheme() {
+        assert_eq!(Config::parse("").unwrap().id_scheme, IdScheme::Nanoid);
+        assert_eq!(
+            Config::parse("id_scheme = \"slug\"").unwrap().id_scheme,
+            IdScheme::Slug
+        );
+        assert!(Config::parse("id_scheme = \"sequential\"").is_err());
+    }
+
+    #[test]
+    fn detects_duplicates_by_command() {
+        let config = Config::parse("").unwrap();
+        assert_eq!(config.duplicate_key, DuplicateKey::Command);
+
+        let commands = vec![CrowCommand::test("1", "ls -la").with_description("list all files")];
+
+        assert_eq!(
+            config.duplicate_key.find_duplicate(
+                &commands,
+                &CrowCommand::test("2", " ls -la ").with_description("show hidden files")
+            ),
+            Some(&commands[0])
+        );
+        assert_eq!(
+            config.duplicate_key.find_duplicate(
+                &commands,
+                &CrowCommand::test("2", "ls").with_description("list all files")
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn detects_duplicates_by_command_and_description() {
+        let config = Config::parse("duplicate_key = \"command_description\"").unwrap();
+        assert_eq!(config.duplicate_key, DuplicateKey::CommandDescription);
+        assert!(Config::parse("duplicate_key = \"description\"").is_err());
+
+        let commands = vec![CrowCommand::test("1", "ls -la").with_description("list all files")];
+
+        assert_eq!(
+            config.duplicate_key.find_duplicate(
+                &commands,
+                &CrowCommand::test("2", "ls -la").with_description("show hidden files")
+            ),
+            None
+        );
+        assert_eq!(
+            config.duplicate_key.find_duplicate(
+                &commands,
+                &CrowCommand::test("2", "ls -la").with_description("list all files\n")
+            ),
+            Some(&commands[0])
+        );
+    }
+
+    #[test]
+    fn sorts_commands_by_sort_order() {
+        let config = Config::parse("sort_db = \"command\"").unwrap();
+        assert_eq!(config.sort_db, Some(SortOrder::Command));
+
+        let mut commands = vec![
+            CrowCommand::test("b", "ls"),
+            CrowCommand::test("c", "git"),
+            CrowCommand::test("a", "ls"),
+        ];
+
+        SortOrder::Command.sort(&mut commands);
+        let ids: Vec<&str> = commands.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["c", "a", "b"]);
+
+        SortOrder::Id.sort(&mut commands);
+        let ids: Vec<&str> = commands.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn sorts_commands_for_browsing() {
+        let config = Config::parse("browse_sort = \"recent\"").unwrap();
+        assert_eq!(config.browse_sort, BrowseSort::Recent);
+        assert!(Config::parse("browse_sort = \"random\"").is_err());
+
+        let mut commands = vec![
+            CrowCommand::test("a", "ls"),
+            CrowCommand::test("b", "Git log")
+                .with_last_used_at(10)
+                .with_use_count(5),
+            CrowCommand::test("c", "docker ps")
+                .with_last_used_at(20)
+                .with_use_count(1),
+            CrowCommand::test("d", "cat"),
+        ];
+        let ids = |commands: &[CrowCommand]| {
+            commands
+                .iter()
+                .map(|c| c.id.clone())
+                .collect::<Vec<String>>()
+        };
+
+        BrowseSort::Manual.sort(&mut commands);
+        assert_eq!(ids(&commands), vec!["a", "b", "c", "d"]);
+
+        BrowseSort::Recent.sort(&mut commands);
+        assert_eq!(ids(&commands), vec!["c", "b", "a", "d"]);
+
+        BrowseSort::Usage.sort(&mut commands);
+        assert_eq!(ids(&commands), vec!["b", "c", "a", "d"]);
+
+        BrowseSort::Alpha.sort(&mut commands);
+        assert_eq!(ids(&commands), vec!["d", "c", "b", "a"]);
+    }
+
+    #[test]
+    fn parses_sort_args_like_the_config() {
+        for value in BrowseSort::VALUES {
+            let config = Config::parse(&format!("browse_sort = \"{}\"", value)).unwrap();
+
+            assert_eq!(value.parse(), Ok(config.browse_sort));
+        }
+        assert_eq!("manual".parse(), Ok(BrowseSort::Manual));
+        assert_eq!("frequency".parse(), Ok(BrowseSort::Usage));
+        assert!("random".parse::<BrowseSort>().is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_options() {
+        assert!(Config::parse("unknown_option = true").is_err());
+    }
+}
@@ -4,28 +4,223 @@
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use std::{
+    cmp::{Ordering, Reverse},
     fmt::{self, Debug, Display},
     ops::{Deref, DerefMut},
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 // TODO maybe change this so that it uses the newtype pattern
 pub type Id = String;
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, PartialOrd)]
+/// A command saved by the user
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, PartialOrd)]
 pub struct CrowCommand {
+    /// Unique id of the command
     pub id: Id,
+
+    /// The actual command which is copied to the clipboard
     pub command: String,
+
+    /// Description of the command which is used to find it again
     pub description: String,
+
+    /// Alternative search keywords which are only used for matching and are never displayed as
+    /// the primary label of a command.
+    #[serde(default)]
+    pub keywords: Vec<String>,
+
+    /// Tags which can be used to group commands. They are searched like keywords.
+    /// Missing in databases of older versions of crow, which is why it defaults to no tags.
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// Optional note on when to use the command, which helps to memorize it. It is shown
+    /// separately from the description inside the command detail.
+    #[serde(default)]
+    pub usage_hint: Option<String>,
+
+    /// Optional directory in which the command is meant to be used, e.g. because it relies on
+    /// relative paths. It is shown inside the command detail.
+    #[serde(default)]
+    pub cwd: Option<String>,
+
+    /// Optional reference to where the command has been found (e.g. the URL of a blog post).
+    /// It is shown inside the command detail, but is not searched.
+    #[serde(default)]
+    pub source: Option<String>,
+
+    /// Unix timestamp (in seconds) of the last time the command was copied from the fuzzy mode.
+    /// `None` if the command has never been used.
+    #[serde(default)]
+    pub last_used_at: Option<u64>,
+
+    /// Number of times the command has been copied from the fuzzy mode
+    #[serde(default)]
+    pub use_count: u64,
+
+    /// Unix timestamp (in seconds) of the time the command was saved.
+    /// `None` for commands which have been saved by older versions of crow.
+    #[serde(default)]
+    pub created_at: Option<u64>,
 }
 
 impl CrowCommand {
     /// Creates a single string from the command and the description which can
     /// be used to be matched agains (e.g. for fuzzy searching).
+    /// Keywords and tags are appended after the description, so that the indices of the command
+    /// and description stay the same for highlighting.
     pub fn match_str(&self) -> String {
-        format!("{}: {}", &self.command, &self.description)
+        format!("{}: {}", &self.command, self.description_match_str())
+    }
+
+    /// Creates the part of the [CrowCommand::match_str] after the command: the description
+    /// followed by the keywords and tags
+    pub fn description_match_str(&self) -> String {
+        self.keywords
+            .iter()
+            .chain(self.tags.iter())
+            .fold(self.description.clone(), |match_str, term| {
+                format!("{} {}", match_str, term)
+            })
+    }
+
+    /// Orders commands by their id
+    pub fn cmp_by_id(&self, other: &Self) -> Ordering {
+        self.id.cmp(&other.id)
+    }
+
+    /// Orders commands by their command text and identical commands by their id, which is a total
+    /// order (e.g. for writing the database file in a stable order)
+    pub fn cmp_by_command(&self, other: &Self) -> Ordering {
+        self.command
+            .cmp(&other.command)
+            .then_with(|| self.cmp_by_id(other))
+    }
+
+    /// Orders commands alphabetically by their command text, ignoring the case. Commands which
+    /// only differ in case are equal, so a stable sort keeps their previous order.
+    pub fn cmp_by_command_ignore_case(&self, other: &Self) -> Ordering {
+        self.command
+            .to_lowercase()
+            .cmp(&other.command.to_lowercase())
+    }
+
+    /// Orders the most recently used commands first and never used commands last
+    pub fn cmp_by_recent_use(&self, other: &Self) -> Ordering {
+        Reverse(self.last_used_at).cmp(&Reverse(other.last_used_at))
+    }
+
+    /// Orders the most frequently used commands first
+    pub fn cmp_by_usage(&self, other: &Self) -> Ordering {
+        Reverse(self.use_count).cmp(&Reverse(other.use_count))
+    }
+
+    /// Checks if the command has the given tag, ignoring the case (see [normalize_tag])
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = normalize_tag(tag);
+
+        self.tags.iter().any(|t| normalize_tag(t) == tag)
+    }
+
+    /// Adds all given tags which the command does not have yet. Tags are normalized (see
+    /// [normalize_tag]) and empty tags are ignored.
+    pub fn add_tags(&mut self, tags: &[String]) {
+        for tag in tags.iter().map(|t| normalize_tag(t)) {
+            if !tag.is_empty() && !self.has_tag(&tag) {
+                self.tags.push(tag);
+            }
+        }
+    }
+
+    /// Removes all given tags from the command, ignoring the case
+    pub fn remove_tags(&mut self, tags: &[String]) {
+        let tags: Vec<String> = tags.iter().map(|t| normalize_tag(t)).collect();
+
+        self.tags.retain(|t| !tags.contains(&normalize_tag(t)));
     }
 }
 
+/// Builds commands for tests, e.g. `CrowCommand::test("1", "ls").with_description("list files")`
+#[cfg(test)]
+impl CrowCommand {
+    /// Creates a command with the given id and command, all other fields are empty
+    pub fn test(id: &str, command: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            command: command.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the description
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = description.to_string();
+        self
+    }
+
+    /// Sets the tags
+    pub fn with_tags(mut self, tags: &[&str]) -> Self {
+        self.tags = tags.iter().map(|t| t.to_string()).collect();
+        self
+    }
+
+    /// Sets the use count
+    pub fn with_use_count(mut self, use_count: u64) -> Self {
+        self.use_count = use_count;
+        self
+    }
+
+    /// Sets the time of the last use
+    pub fn with_last_used_at(mut self, last_used_at: u64) -> Self {
+        self.last_used_at = Some(last_used_at);
+        self
+    }
+
+    /// Sets the time the command was saved
+    pub fn with_created_at(mut self, created_at: u64) -> Self {
+        self.created_at = Some(created_at);
+        self
+    }
+}
+
+/// Normalizes a tag to its stored form (trimmed and lowercase), so that e.g. `Git`, `git` and
+/// `GIT` are the same tag.
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim().to_lowercase()
+}
+
+/// Parses a comma separated list of keywords (e.g. user input) into a list of trimmed,
+/// non-empty keywords.
+pub fn parse_keywords(input: &str) -> Vec<String> {
+    input
+        .split(',')
+        .map(|k| k.trim().to_string())
+        .filter(|k| !k.is_empty())
+        .collect()
+}
+
+/// Shortens a description to at most `max_len` characters (see
+/// [crate::config::Config::max_description_len]). Returns `None` if the description without
+/// trailing whitespace isn't longer than that.
+pub fn truncate_description(description: &str, max_len: usize) -> Option<String> {
+    let description = description.trim_end();
+    if description.chars().count() <= max_len {
+        return None;
+    }
+
+    let truncated: String = description.chars().take(max_len).collect();
+    Some(truncated.trim_end().to_string())
+}
+
+/// Returns the current time as unix timestamp in seconds
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
 impl Display for CrowCommand {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -55,6 +250,12 @@ impl Commands {
         self.values()
     }
 
+    /// Looks up a command by its id. Returns `None` if there is no command with that id (anymore),
+    /// e.g. because it has been removed in the meantime.
+    pub fn find_by_id(&self, command_id: &Id) -> Option<&CrowCommand> {
+        self.get(command_id)
+    }
+
     pub fn update_command(&mut self, command_id: Id, command: &str) {
         if let Some(c) = self.get_mut(&command_id) {
             *c = CrowCommand {
@@ -72,6 +273,45 @@ impl Commands {
             }
         }
     }
+
+    pub fn update_keywords(&mut self, command_id: Id, keywords: Vec<String>) {
+        if let Some(c) = self.get_mut(&command_id) {
+            *c = CrowCommand {
+                keywords,
+                ..c.clone()
+            }
+        }
+    }
+
+    /// Replaces all tags of the command, e.g. after editing them in the editor. Tags are
+    /// normalized (see [normalize_tag]), duplicates and empty tags are removed.
+    pub fn update_tags(&mut self, command_id: Id, tags: Vec<String>) {
+        if let Some(c) = self.get_mut(&command_id) {
+            c.tags.clear();
+            c.add_tags(&tags);
+        }
+    }
+
+    /// Removes the given tag from the command if it has the tag, otherwise adds it
+    pub fn toggle_tag(&mut self, command_id: &Id, tag: &str) {
+        if let Some(c) = self.get_mut(command_id) {
+            let tag = [tag.to_string()];
+
+            if c.has_tag(&tag[0]) {
+                c.remove_tags(&tag);
+            } else {
+                c.add_tags(&tag);
+            }
+        }
+    }
+
+    /// Records that the command has been used at the given unix timestamp
+    pub fn mark_used(&mut self, command_id: &Id, timestamp: u64) {
+        if let Some(c) = self.get_mut(command_id) {
+            c.last_used_at = Some(timestamp);
+            c.use_count += 1;
+        }
+    }
 }
 
 impl Deref for Commands {
@@ -137,4 +377,286 @@ impl CrowCommands {
     pub fn set_commands(&mut self, commands: Commands) {
         self.commands = commands;
     }
+
+    /// Appends a copy of the command with the given id under the new id. The copy has not been
+    /// used yet. Returns `false` if there is no command with the given id.
+    pub fn duplicate(&mut self, command_id: &Id, new_id: Id) -> bool {
+        let mut command = match self.commands.find_by_id(command_id) {
+            Some(c) => c.clone(),
+            None => return false,
+        };
+
+        command.id = new_id.clone();
+        command.last_used_at = None;
+        command.use_count = 0;
+        command.created_at = Some(unix_timestamp());
+
+        self.commands.insert(new_id.clone(), command);
+        self.command_ids.push(new_id);
+        true
+    }
+
+    /// Swaps the position of the two commands at the given indices, which changes the order in
+    /// which commands are listed and persisted.
+    pub fn swap_commands(&mut self, a: usize, b: usize) {
+        self.commands.swap_indices(a, b);
+        self.command_ids.swap(a, b);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use super::{parse_keywords, truncate_description, Commands, CrowCommand, CrowCommands};
+
+    #[test]
+    fn finds_command_by_id_until_it_is_removed() {
+        let command = CrowCommand {
+            id: "1".to_string(),
+            command: "ls".to_string(),
+            ..Default::default()
+        };
+        let mut commands = Commands::normalize(std::slice::from_ref(&command));
+
+        assert_eq!(commands.find_by_id(&"1".to_string()), Some(&command));
+        assert_eq!(commands.find_by_id(&"2".to_string()), None);
+
+        commands.shift_remove(&"1".to_string());
+
+        assert_eq!(commands.find_by_id(&"1".to_string()), None);
+    }
+
+    #[test]
+    fn parses_comma_separated_keywords() {
+        assert_eq!(
+            parse_keywords(" list containers, pods ,, "),
+            vec!["list containers".to_string(), "pods".to_string()]
+        );
+        assert_eq!(parse_keywords(""), Vec::<String>::new());
+    }
+
+    #[test]
+    fn truncates_long_descriptions() {
+        assert_eq!(truncate_description("list containers\n", 15), None);
+        assert_eq!(
+            truncate_description("list all containers", 9),
+            Some("list all".to_string())
+        );
+        assert_eq!(truncate_description("äöü", 2), Some("äö".to_string()));
+    }
+
+    #[test]
+    fn compares_commands_by_sort_keys() {
+        let commands = vec![
+            CrowCommand::test("c", "ls")
+                .with_last_used_at(20)
+                .with_use_count(1),
+            CrowCommand::test("a", "git status").with_use_count(5),
+            CrowCommand::test("d", "Git log").with_last_used_at(30),
+            CrowCommand::test("b", "ls")
+                .with_last_used_at(10)
+                .with_use_count(5),
+        ];
+        let sorted_ids = |compare: fn(&CrowCommand, &CrowCommand) -> Ordering| {
+            let mut commands = commands.clone();
+            commands.sort_by(compare);
+            commands.into_iter().map(|c| c.id).collect::<Vec<_>>()
+        };
+
+        assert_eq!(sorted_ids(CrowCommand::cmp_by_id), ["a", "b", "c", "d"]);
+        assert_eq!(
+            sorted_ids(CrowCommand::cmp_by_command),
+            ["d", "a", "b", "c"]
+        );
+        assert_eq!(
+            sorted_ids(CrowCommand::cmp_by_command_ignore_case),
+            ["d", "a", "c", "b"]
+        );
+        assert_eq!(
+            sorted_ids(CrowCommand::cmp_by_recent_use),
+            ["d", "c", "b", "a"]
+        );
+        // Equally often used commands keep their order
+        assert_eq!(sorted_ids(CrowCommand::cmp_by_usage), ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn adds_and_removes_tags() {
+        let mut command = CrowCommand {
+            id: "1".to_string(),
+            command: "git status".to_string(),
+            ..Default::default()
+        };
+
+        command.add_tags(&["git".to_string(), "vcs".to_string()]);
+        command.add_tags(&["git".to_string()]);
+        assert_eq!(command.tags, vec!["git".to_string(), "vcs".to_string()]);
+
+        command.remove_tags(&["git".to_string(), "unknown".to_string()]);
+        assert_eq!(command.tags, vec!["vcs".to_string()]);
+    }
+
+    #[test]
+    fn normalizes_mixed_case_tags() {
+        let mut command = CrowCommand {
+            id: "1".to_string(),
+            command: "git status".to_string(),
+            tags: vec!["Docker".to_string()],
+            ..Default::default()
+        };
+
+        command.add_tags(&["Git".to_string(), " GIT ".to_string(), "".to_string()]);
+        assert_eq!(command.tags, vec!["Docker".to_string(), "git".to_string()]);
+        assert!(command.has_tag("gIt"));
+        assert!(command.has_tag("docker"));
+
+        command.remove_tags(&["DOCKER".to_string()]);
+        assert_eq!(command.tags, vec!["git".to_string()]);
+
+        let mut commands = Commands::normalize(&[command]);
+        let id = "1".to_string();
+
+        commands.toggle_tag(&id, "Git");
+        assert!(commands.find_by_id(&id).unwrap().tags.is_empty());
+    }
+
+    #[test]
+    fn toggles_tags() {
+        let command = CrowCommand {
+            id: "1".to_string(),
+            command: "git status".to_string(),
+            tags: vec!["git".to_string()],
+            ..Default::default()
+        };
+        let mut commands = Commands::normalize(&[command]);
+        let id = "1".to_string();
+
+        commands.toggle_tag(&id, "vcs");
+        assert_eq!(
+            commands.find_by_id(&id).unwrap().tags,
+            vec!["git".to_string(), "vcs".to_string()]
+        );
+
+        commands.toggle_tag(&id, "git");
+        assert_eq!(
+            commands.find_by_id(&id).unwrap().tags,
+            vec!["vcs".to_string()]
+        );
+    }
+
+    #[test]
+    fn duplicates_command_with_new_id() {
+        let command = CrowCommand {
+            id: "1".to_string(),
+            command: "git status".to_string(),
+            description: "show repo status".to_string(),
+            last_used_at: Some(42),
+            use_count: 3,
+            ..Default::default()
+        };
+        let mut crow_commands = CrowCommands::_new(
+            Commands::normalize(std::slice::from_ref(&command)),
+            vec!["1".to_string()],
+        );
+
+        assert!(crow_commands.duplicate(&"1".to_string(), "2".to_string()));
+        assert!(!crow_commands.duplicate(&"3".to_string(), "4".to_string()));
+
+        let duplicate = crow_commands
+            .commands()
+            .find_by_id(&"2".to_string())
+            .unwrap();
+        assert!(duplicate.created_at.is_some());
+        assert_eq!(
+            duplicate,
+            &CrowCommand {
+                id: "2".to_string(),
+                last_used_at: None,
+                use_count: 0,
+                created_at: duplicate.created_at,
+                ..command.clone()
+            }
+        );
+        assert_eq!(
+            crow_commands.commands().find_by_id(&"1".to_string()),
+            Some(&command)
+        );
+        assert_eq!(crow_commands.commands().len(), 2);
+    }
+
+    #[test]
+    fn appends_keywords_to_match_str() {
+        let mut command = CrowCommand {
+            id: "1".to_string(),
+            command: "kubectl get pods".to_string(),
+            description: "show pods".to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(command.match_str(), "kubectl get pods: show pods");
+
+        command.keywords = vec!["list".to_string(), "containers".to_string()];
+
+        assert_eq!(
+            command.match_str(),
+            "kubectl get pods: show pods list containers"
+        );
+    }
+
+    #[test]
+    fn appends_tags_to_match_str() {
+        let command = CrowCommand {
+            id: "1".to_string(),
+            command: "kubectl get pods".to_string(),
+            description: "show pods".to_string(),
+            keywords: vec!["list".to_string()],
+            tags: vec!["k8s".to_string(), "ops".to_string()],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            command.match_str(),
+            "kubectl get pods: show pods list k8s ops"
+        );
+    }
+
+    #[test]
+    fn reads_commands_of_older_versions_without_tags() {
+        let command: CrowCommand =
+            serde_json::from_str(r#"{"id":"1","command":"ls","description":"list files"}"#)
+                .unwrap();
+
+        assert!(command.tags.is_empty());
+        assert_eq!(command.match_str(), "ls: list files");
+    }
+
+    #[test]
+    fn does_not_match_source() {
+        let command = CrowCommand {
+            id: "1".to_string(),
+            command: "kubectl get pods".to_string(),
+            description: "show pods".to_string(),
+            source: Some("https://kubernetes.io/docs/reference/kubectl/".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(command.match_str(), "kubectl get pods: show pods");
+    }
+
+    #[test]
+    fn round_trips_usage_hint() {
+        let command = CrowCommand {
+            usage_hint: Some("when the tests fail for no reason".to_string()),
+            ..CrowCommand::test("1", "cargo clean")
+        };
+
+        let json = serde_json::to_string(&command).unwrap();
+        assert!(json.contains(r#""usage_hint":"when the tests fail for no reason""#));
+        assert_eq!(serde_json::from_str::<CrowCommand>(&json).unwrap(), command);
+
+        let command: CrowCommand =
+            serde_json::from_str(r#"{"id":"1","command":"ls","description":""}"#).unwrap();
+        assert_eq!(command.usage_hint, None);
+    }
 }
@@ -1,16 +1,28 @@
 //! Abstraction of read and write processes to the crow configuration file.
 
+use clap::ArgMatches;
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use std::{
-    fmt::Display,
-    fs::{create_dir_all, read_to_string, write},
+    env::current_dir,
+    ffi::OsStr,
+    fmt::{Debug, Display},
+    fs::{create_dir_all, read_to_string, write, OpenOptions},
+    io::{ErrorKind, Write},
     ops::Deref,
     path::{Path, PathBuf},
+    sync::Once,
 };
 
 use dirs::home_dir;
+use log::{debug, info, warn};
+use nanoid::nanoid;
 
-use crate::{crow_commands::CrowCommand, eject};
+use crate::{
+    config::{Config, SortOrder},
+    crow_commands::CrowCommand,
+    eject,
+};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 struct Commands {
@@ -40,7 +52,9 @@ impl Commands {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// Path of the database file. The default is an empty path, which is only a placeholder until a
+/// database file is loaded, so that it doesn't resolve (and create) the default directory.
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct FilePath(PathBuf);
 
 impl Deref for FilePath {
@@ -51,15 +65,9 @@ impl Deref for FilePath {
     }
 }
 
-impl Default for FilePath {
-    fn default() -> Self {
-        Self(Self::create_path_and_intermediate_dirs(None, None))
-    }
-}
-
 impl Display for FilePath {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", *self)
+        write!(f, "{}", &**self)
     }
 }
 
@@ -82,6 +90,20 @@ impl FilePath {
         ))
     }
 
+    /// Creates the file path from the `--path`/`--file` arguments of a command. If no file name
+    /// is given, the default file name of the configured storage format is used.
+    pub fn from_args(arg_matches: Option<&ArgMatches>, config: &Config) -> Self {
+        let default_file_name = config.storage.default_file_name();
+
+        match arg_matches {
+            Some(matches) => Self::new(
+                matches.value_of("db_path"),
+                Some(matches.value_of("db_name").unwrap_or(default_file_name)),
+            ),
+            None => Self::new(None, Some(default_file_name)),
+        }
+    }
+
     pub fn as_path(&self) -> &Path {
         self.0.as_path()
     }
@@ -96,8 +118,8 @@ impl FilePath {
     ///
     /// # Panics
     ///
-    /// If this function is somehow unable to either find the home directory or
-    /// create the full path, it will panic.
+    /// If this function is somehow unable to find neither the home directory nor the current
+    /// directory or to create the full path, it will panic.
     fn create_path_and_intermediate_dirs(
         path_buffer: Option<PathBuf>,
         file: Option<&str>,
@@ -107,36 +129,178 @@ impl FilePath {
         if !path_buffer.as_path().exists() {
             match path_buffer.to_str() {
                 Some(str) => {
-                    println!("Creating config path: {}", str);
+                    info!("Creating config path: {}", str);
                 }
                 None => eject("Could not parse config path to string"),
             }
-
-            if let Err(error) = create_dir_all(path_buffer.as_path()) {
-                eject(&format!(
-                    "Could not create directories up to config path. {}",
-                    error
-                ));
-            };
         }
 
+        if let Err(error) = Self::create_dirs(path_buffer.as_path()) {
+            eject(&error);
+        };
+
         path_buffer.push(file.unwrap_or(Self::DEFAULT_CONFIG_FILE));
         path_buffer
     }
 
+    /// Creates the given directory and all of its parents if they don't exist yet.
+    /// Returns a targeted error message if the directories could not be created, e.g. because a
+    /// part of the path already exists as a file.
+    fn create_dirs(path: &Path) -> Result<(), String> {
+        // `create_dir_all` fails with rather unspecific errors if a part of the path is a file,
+        // so we check this beforehand
+        if let Some(file) = path.ancestors().find(|p| p.is_file()) {
+            return Err(format!(
+                "Could not create config path {:?}, because {:?} is a file and not a directory",
+                path, file
+            ));
+        }
+
+        create_dir_all(path).map_err(|error| match error.kind() {
+            ErrorKind::PermissionDenied => format!(
+                "Could not create config path {:?}, because the permission was denied. {}",
+                path, error
+            ),
+            _ => format!(
+                "Could not create directories up to config path {:?}. {}",
+                path, error
+            ),
+        })
+    }
+
     fn default_path() -> PathBuf {
-        let mut path_buffer = PathBuf::new();
-        let home_dir = match home_dir() {
-            Some(dir) => dir,
-            None => eject("Could not retrieve home directory. {}"),
-        };
-        let home_dir = match home_dir.to_str() {
-            Some(str) => str,
-            None => eject("Could not parse home directory into string"),
-        };
+        static WARN_FALLBACK: Once = Once::new();
+
+        let (path, is_fallback) = Self::resolve_default_path(home_dir(), current_dir().ok())
+            .unwrap_or_else(|e| eject(&e));
+
+        if is_fallback {
+            WARN_FALLBACK.call_once(|| {
+                warn!(
+                    "Could not determine your home directory, using the current directory {:?} instead. Set the database path with --path to use another directory.",
+                    path
+                )
+            });
+        }
 
-        path_buffer.push(format!("{}/.config/crow/", home_dir));
-        path_buffer
+        path
+    }
+
+    /// Resolves the default directory of the database file inside the given home directory.
+    /// Without a home directory (e.g. in containers without `$HOME`) the given current directory
+    /// is used instead, which is signaled by `true` so that the user can be told to set the path
+    /// explicitly.
+    fn resolve_default_path(
+        home_dir: Option<PathBuf>,
+        current_dir: Option<PathBuf>,
+    ) -> Result<(PathBuf, bool), String> {
+        match (home_dir, current_dir) {
+            (Some(home_dir), _) => Ok((home_dir.join(".config/crow/"), false)),
+            (None, Some(current_dir)) => Ok((current_dir, true)),
+            (None, None) => Err(
+                "Could not determine your home directory or the current directory. Set the database path with --path."
+                    .to_string(),
+            ),
+        }
+    }
+}
+
+/// Storage of the serialized database, which is the database file by default (see [FileStore]).
+/// Tests can use a [MemoryStore] instead, which neither touches the file system nor has to be
+/// cleaned up.
+pub trait CommandStore: Debug {
+    /// Returns the stored content or `None` if nothing has been stored yet
+    fn read(&self) -> Result<Option<String>, String>;
+
+    /// Replaces the stored content
+    fn write(&mut self, content: &str) -> Result<(), String>;
+
+    /// Appends to the stored content, which is used to add commands to JSON lines databases
+    fn append(&mut self, content: &str) -> Result<(), String>;
+
+    /// Returns a store which reads and writes the same content
+    fn clone_box(&self) -> Box<dyn CommandStore>;
+}
+
+impl Clone for Box<dyn CommandStore> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+impl Default for Box<dyn CommandStore> {
+    fn default() -> Self {
+        Box::new(FileStore::new(FilePath::default()))
+    }
+}
+
+/// Stores the database in the given database file
+#[derive(Clone, Debug)]
+pub struct FileStore {
+    path: FilePath,
+}
+
+impl FileStore {
+    pub fn new(path: FilePath) -> Self {
+        Self { path }
+    }
+}
+
+impl CommandStore for FileStore {
+    fn read(&self) -> Result<Option<String>, String> {
+        match read_to_string(self.path.as_path()) {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    fn write(&mut self, content: &str) -> Result<(), String> {
+        write(self.path.as_path(), content).map_err(|e| e.to_string())
+    }
+
+    fn append(&mut self, content: &str) -> Result<(), String> {
+        OpenOptions::new()
+            .append(true)
+            .open(self.path.as_path())
+            .and_then(|mut file| file.write_all(content.as_bytes()))
+            .map_err(|e| e.to_string())
+    }
+
+    fn clone_box(&self) -> Box<dyn CommandStore> {
+        Box::new(self.clone())
+    }
+}
+
+/// Keeps the database in memory, which is shared by all clones of the store (e.g. by the
+/// connections which [crate::state::State] creates for every write)
+#[cfg(test)]
+#[derive(Clone, Debug, Default)]
+pub struct MemoryStore {
+    content: std::rc::Rc<std::cell::RefCell<Option<String>>>,
+}
+
+#[cfg(test)]
+impl CommandStore for MemoryStore {
+    fn read(&self) -> Result<Option<String>, String> {
+        Ok(self.content.borrow().clone())
+    }
+
+    fn write(&mut self, content: &str) -> Result<(), String> {
+        *self.content.borrow_mut() = Some(content.to_string());
+        Ok(())
+    }
+
+    fn append(&mut self, content: &str) -> Result<(), String> {
+        self.content
+            .borrow_mut()
+            .get_or_insert_with(String::new)
+            .push_str(content);
+        Ok(())
+    }
+
+    fn clone_box(&self) -> Box<dyn CommandStore> {
+        Box::new(self.clone())
     }
 }
 
@@ -144,6 +308,16 @@ impl FilePath {
 pub struct CrowDBConnection {
     commands: Commands,
     path: FilePath,
+
+    /// Where the serialized commands are read from and written to
+    store: Box<dyn CommandStore>,
+
+    /// Order in which commands are written to the database file
+    sort_order: Option<SortOrder>,
+
+    /// Whether the JSON lines database file contains lines which have been superseded by later
+    /// lines of the same command, which are dropped the next time a command is added
+    has_superseded_lines: bool,
 }
 
 impl Default for CrowDBConnection {
@@ -151,13 +325,27 @@ impl Default for CrowDBConnection {
         Self {
             commands: Commands::default(),
             path: FilePath::default(),
+            store: Box::default(),
+            sort_order: None,
+            has_superseded_lines: false,
         }
     }
 }
 
 impl CrowDBConnection {
     pub fn new(file_path: FilePath) -> Self {
-        Self::connect_and_initialize_file_if_not_exists(file_path)
+        let store = Box::new(FileStore::new(file_path.clone()));
+
+        Self::with_store(file_path, store)
+    }
+
+    /// Connects to the database inside the given store. The path is still used to determine the
+    /// format of the database (see [CrowDBConnection::is_json_lines]) and to tell the user where
+    /// the commands are saved.
+    pub fn with_store(file_path: FilePath, store: Box<dyn CommandStore>) -> Self {
+        debug!("Using database file: {}", file_path);
+
+        Self::connect_and_initialize_file_if_not_exists(file_path, store)
     }
 
     /// Initializes the crow database json file if it does not exist (typically at `$HOME/.config/crow/crow_db.json` on UNIX systems).
@@ -167,29 +355,39 @@ impl CrowDBConnection {
     /// * if paths could not be resolved
     /// * if the file could not be written
     /// * if the default content of the file could not be parsed to JSON
-    fn connect_and_initialize_file_if_not_exists(file_path: FilePath) -> Self {
-        if !file_path.as_path().exists() {
-            match file_path.to_str() {
+    fn connect_and_initialize_file_if_not_exists(
+        file_path: FilePath,
+        store: Box<dyn CommandStore>,
+    ) -> Self {
+        let mut connection = Self {
+            commands: Commands::default(),
+            path: file_path,
+            store,
+            sort_order: None,
+            has_superseded_lines: false,
+        };
+
+        if connection.stored_content().is_none() {
+            match connection.path.to_str() {
                 Some(file_path) => {
-                    println!("Creating config file: {}", file_path);
+                    info!("Creating config file: {}", file_path);
                 }
                 None => eject("Could not parse path to string"),
             }
 
-            let connection = Self {
-                commands: Commands::default(),
-                path: file_path,
-            };
             connection.write();
 
             return connection;
         }
 
-        Self {
-            commands: Commands::default(),
-            path: file_path,
-        }
-        .read()
+        connection.read()
+    }
+
+    /// Returns the content of the store or `None` if the database has not been initialized yet
+    fn stored_content(&self) -> Option<String> {
+        self.store
+            .read()
+            .unwrap_or_else(|e| eject(&format!("Could not read database file. {}", e)))
     }
 
     /// Returns a list reference to the commands in the database
@@ -197,28 +395,130 @@ impl CrowDBConnection {
         self.commands.commands()
     }
 
+    /// Checks if the database file uses the JSON lines format (one command per line), which is
+    /// determined by the `.jsonl` file extension.
+    fn is_json_lines(&self) -> bool {
+        Self::is_json_lines_path(self.path())
+    }
+
+    fn is_json_lines_path(path: &FilePath) -> bool {
+        path.as_path().extension() == Some(OsStr::new("jsonl"))
+    }
+
+    /// Checks whether the given database file (inside the given store) can be read and parsed,
+    /// e.g. after it has been edited by hand. Returns a description of the problem otherwise.
+    pub fn validate(path: &FilePath, store: &dyn CommandStore) -> Result<(), String> {
+        let db_file = store
+            .read()?
+            .ok_or_else(|| format!("{} does not exist", path))?;
+
+        Self::parse(&db_file, Self::is_json_lines_path(path))
+            .map(|_| ())
+            .map_err(|e| format!("{} is not a valid database file. {}", path, e))
+    }
+
+    /// Checks whether the given database file (inside the given store) currently contains any
+    /// commands. A file which cannot be parsed counts as containing commands if it is not blank,
+    /// so that it is not mistaken for an empty database.
+    pub fn contains_commands(path: &FilePath, store: &dyn CommandStore) -> bool {
+        match store.read() {
+            Ok(Some(db_file)) => match Self::parse(&db_file, Self::is_json_lines_path(path)) {
+                Ok(commands) => !commands.commands().is_empty(),
+                Err(_) => !db_file.trim().is_empty(),
+            },
+            _ => false,
+        }
+    }
+
     /// Writes all commands which are currently inside the memory database into
     /// the crow_db file.
-    pub fn write(&self) -> &Self {
-        let crow_db_json = match serde_json::to_string(&self.commands) {
-            Ok(json) => json,
-            Err(error) => eject(&format!("Could not parse to JSON. {}", error)),
+    /// For JSON lines files this also compacts the file, because entries which have been
+    /// superseded by later appended entries are dropped.
+    /// If a sort order is set, commands are written in that order.
+    pub fn write(&mut self) -> &mut Self {
+        let mut commands = self.commands.clone();
+
+        if let Some(sort_order) = self.sort_order {
+            sort_order.sort(commands.commands_mut());
+        }
+
+        let crow_db_json = if self.is_json_lines() {
+            commands
+                .commands()
+                .iter()
+                .map(|c| Self::to_json(c) + "\n")
+                .collect::<String>()
+        } else {
+            Self::to_json(&commands)
         };
 
-        if let Err(error) = write(self.path().as_path(), crow_db_json) {
+        if let Err(error) = self.store.write(&crow_db_json) {
             eject(&format!("Could not write database file. {}", error));
         };
+        self.has_superseded_lines = false;
 
         self
     }
 
+    fn to_json<T: Serialize>(value: &T) -> String {
+        match serde_json::to_string(value) {
+            Ok(json) => json,
+            Err(error) => eject(&format!("Could not parse to JSON. {}", error)),
+        }
+    }
+
+    /// Returns the command with a newly generated id if its id is already used by another
+    /// command inside the database, so that two different commands can never share an id and
+    /// overwrite each other.
+    fn with_unique_id(&self, mut command: CrowCommand) -> CrowCommand {
+        while self.commands().iter().any(|c| c.id == command.id) {
+            let id = nanoid!();
+            warn!(
+                "Command id '{}' already exists, using new id '{}'",
+                command.id, id
+            );
+            command.id = id;
+        }
+
+        command
+    }
+
     /// Adds a command to the in memory database.
+    /// If the id of the command is already taken, a new id is generated.
     /// [self.write()] needs to be called in order to save to the json file.
     pub fn add_command(&mut self, command: CrowCommand) -> &mut Self {
+        let command = self.with_unique_id(command);
         self.commands.commands_mut().push(command);
         self
     }
 
+    /// Adds a command to the in memory database and saves it to the database file.
+    /// For JSON lines files only the new command is appended to the file, unless the file
+    /// contains superseded lines. Otherwise the whole file is rewritten, which also compacts
+    /// JSON lines files so that they don't keep growing.
+    /// Returns the saved command, which has a new id if its id was already taken.
+    pub fn append_command(&mut self, command: CrowCommand) -> CrowCommand {
+        let command = self.with_unique_id(command);
+
+        if !self.is_json_lines() || self.has_superseded_lines {
+            if self.has_superseded_lines {
+                debug!("Compacting database file: {}", self.path);
+            }
+
+            self.add_command(command.clone()).write();
+            return command;
+        }
+
+        let line = Self::to_json(&command) + "\n";
+        self.add_command(command.clone());
+
+        if let Err(error) = self.store.append(&line) {
+            eject(&format!("Could not append to database file. {}", error));
+        }
+
+        command
+    }
+
     /// Removes a command from the in memory database.
     /// [self.write()] needs to be called in order to save to the json file.
     pub fn remove_command(&mut self, command: &CrowCommand) -> &mut Self {
@@ -227,14 +527,74 @@ impl CrowDBConnection {
     }
 
     /// Reads the database json file into an existing connection, parses the json and returns an in-memory [CrowDBConnection]
+    /// The file is never changed by reading it, superseded entries of JSON lines files (see
+    /// [CrowDBConnection::parse_json_lines]) are only dropped by the next write (see
+    /// [CrowDBConnection::append_command]).
     pub fn read(mut self) -> Self {
-        let db_file = read_to_string(self.path().as_path())
+        let db_file = self
+            .stored_content()
             .expect("Error: crow_db.json file has not been initialized!");
 
-        let commands: Commands =
-            serde_json::from_str(&db_file).expect("Error: unable to parse crow_db.json file!");
+        let commands = if self.is_json_lines() {
+            Self::parse(&db_file, true).expect("Error: unable to parse crow_db.jsonl file!")
+        } else {
+            Self::parse(&db_file, false).expect("Error: unable to parse crow_db.json file!")
+        };
 
         self.commands = commands;
+        self.has_superseded_lines =
+            self.is_json_lines() && Self::count_lines(&db_file) > self.commands().len();
+
+        self
+    }
+
+    /// Counts the non-empty lines of a JSON lines database
+    fn count_lines(db_file: &str) -> usize {
+        db_file.lines().filter(|l| !l.trim().is_empty()).count()
+    }
+
+    /// Parses the content of a database file, which is either a JSON object or JSON lines (see
+    /// [CrowDBConnection::parse_json_lines])
+    fn parse(db_file: &str, json_lines: bool) -> Result<Commands, serde_json::Error> {
+        if json_lines {
+            Self::parse_json_lines(db_file)
+        } else {
+            serde_json::from_str(db_file)
+        }
+    }
+
+    /// Parses a JSON lines database where each line contains one command.
+    /// If a command id appears multiple times, the last line wins.
+    fn parse_json_lines(db_file: &str) -> Result<Commands, serde_json::Error> {
+        let mut commands: IndexMap<String, CrowCommand> = IndexMap::new();
+
+        for line in db_file.lines().filter(|l| !l.trim().is_empty()) {
+            let command: CrowCommand = serde_json::from_str(line)?;
+            commands.insert(command.id.clone(), command);
+        }
+
+        Ok(Commands {
+            commands: commands.into_iter().map(|(_, c)| c).collect(),
+        })
+    }
+
+    /// Copies the database file to a backup file next to it (e.g. `crow_db.json.bak`), which
+    /// replaces an existing backup. Returns the path of the backup file.
+    pub fn backup(&self) -> Result<PathBuf, std::io::Error> {
+        let mut backup_path = self.path().as_path().as_os_str().to_owned();
+        backup_path.push(".bak");
+        let backup_path = PathBuf::from(backup_path);
+
+        std::fs::copy(self.path().as_path(), &backup_path)?;
+
+        Ok(backup_path)
+    }
+
+    /// Set the order in which commands are written to the database file.
+    /// NOTE: new commands are still appended at the end of JSON lines files, the file is only
+    /// sorted when it is rewritten completely.
+    pub fn set_sort_order(mut self, sort_order: Option<SortOrder>) -> Self {
+        self.sort_order = sort_order;
         self
     }
 
@@ -257,7 +617,7 @@ mod tests {
 
     mod file_path {
         use nanoid::nanoid;
-        use std::path::Path;
+        use std::path::{Path, PathBuf};
 
         use crate::crow_db::FilePath;
 
@@ -281,13 +641,51 @@ mod tests {
 
             std::fs::remove_dir_all(expected_path).unwrap();
         }
+
+        #[test]
+        fn fails_if_part_of_the_path_is_a_file() {
+            let fn_path = format!("./testdata/tmp/{}", nanoid!());
+            std::fs::create_dir_all(&fn_path).unwrap();
+
+            let file = Path::new(&fn_path).join("file");
+            std::fs::write(&file, "").unwrap();
+
+            let error = FilePath::create_dirs(&file.join("sub")).unwrap_err();
+            assert!(error.contains("is a file and not a directory"), "{}", error);
+
+            let error = FilePath::create_dirs(&file).unwrap_err();
+            assert!(error.contains("is a file and not a directory"), "{}", error);
+
+            assert_eq!(FilePath::create_dirs(Path::new(&fn_path)), Ok(()));
+
+            std::fs::remove_dir_all(&fn_path).unwrap();
+        }
+
+        #[test]
+        fn falls_back_to_current_dir_without_home_dir() {
+            let home_dir = Some(PathBuf::from("/home/crow"));
+            let current_dir = Some(PathBuf::from("/work"));
+
+            assert_eq!(
+                FilePath::resolve_default_path(home_dir, current_dir.clone()),
+                Ok((PathBuf::from("/home/crow/.config/crow/"), false))
+            );
+            assert_eq!(
+                FilePath::resolve_default_path(None, current_dir),
+                Ok((PathBuf::from("/work"), true))
+            );
+
+            let error = FilePath::resolve_default_path(None, None).unwrap_err();
+            assert!(error.contains("--path"), "{}", error);
+        }
     }
 
     mod shell {
         use nanoid::nanoid;
-        use std::path::Path;
+        use std::{fs::OpenOptions, io::Write, path::Path};
 
         use crate::{
+            config::SortOrder,
             crow_commands::CrowCommand,
             crow_db::{CrowDBConnection, FilePath},
         };
@@ -297,7 +695,7 @@ mod tests {
             let fn_path = &format!("./testdata/tmp/{}", nanoid!());
             let file_path = FilePath::new(Some(&fn_path), Some("crow_db.json"));
 
-            let connection = CrowDBConnection::new(file_path.clone());
+            let mut connection = CrowDBConnection::new(file_path.clone());
 
             connection.write();
 
@@ -322,11 +720,13 @@ mod tests {
                 id: "test_command_1".to_string(),
                 command: "echo 'hi from db'".to_string(),
                 description: "This is a test command".to_string(),
+                ..Default::default()
             };
             let expected_command_2 = CrowCommand {
                 id: "test_command_2".to_string(),
                 command: "".to_string(),
                 description: "".to_string(),
+                ..Default::default()
             };
 
             assert_eq!(
@@ -344,12 +744,14 @@ mod tests {
                 id: "1".to_string(),
                 command: "".to_string(),
                 description: "".to_string(),
+                ..Default::default()
             };
 
             let command_2 = CrowCommand {
                 id: "2".to_string(),
                 command: "".to_string(),
                 description: "".to_string(),
+                ..Default::default()
             };
 
             let mut connection = CrowDBConnection::new(file_path);
@@ -361,6 +763,156 @@ mod tests {
             std::fs::remove_dir_all(Path::new(fn_path)).unwrap();
         }
 
+        #[test]
+        fn appends_commands_to_json_lines_file() {
+            let fn_path = &format!("./testdata/tmp/{}", nanoid!());
+            let file_path = FilePath::new(Some(fn_path), Some("crow.jsonl"));
+
+            let command_1 = CrowCommand {
+                id: "1".to_string(),
+                command: "echo 1".to_string(),
+                ..Default::default()
+            };
+
+            let command_2 = CrowCommand {
+                id: "2".to_string(),
+                command: "echo 2".to_string(),
+                ..Default::default()
+            };
+
+            CrowDBConnection::new(file_path.clone()).append_command(command_1.clone());
+            CrowDBConnection::new(file_path.clone()).append_command(command_2.clone());
+
+            let content = std::fs::read_to_string(file_path.as_path()).unwrap();
+            assert_eq!(content.lines().count(), 2);
+
+            let connection = CrowDBConnection::new(file_path.clone());
+            assert_eq!(connection.commands(), &[command_1, command_2.clone()]);
+
+            // Later lines win and writing compacts the file
+            let command_1_updated = CrowCommand {
+                id: "1".to_string(),
+                command: "echo updated".to_string(),
+                ..Default::default()
+            };
+            OpenOptions::new()
+                .append(true)
+                .open(file_path.as_path())
+                .and_then(|mut file| {
+                    writeln!(
+                        file,
+                        "{}",
+                        serde_json::to_string(&command_1_updated).unwrap()
+                    )
+                })
+                .unwrap();
+
+            let mut connection = CrowDBConnection::new(file_path.clone());
+            assert_eq!(connection.commands(), &[command_1_updated, command_2]);
+
+            connection.write();
+            let content = std::fs::read_to_string(file_path.as_path()).unwrap();
+            assert_eq!(content.lines().count(), 2);
+
+            std::fs::remove_dir_all(Path::new(fn_path)).unwrap();
+        }
+
+        #[test]
+        fn regenerates_id_of_colliding_command() {
+            let fn_path = &format!("./testdata/tmp/{}", nanoid!());
+            let file_path = FilePath::new(Some(fn_path), Some("crow.json"));
+
+            let command_1 = CrowCommand {
+                id: "1".to_string(),
+                command: "ls".to_string(),
+                ..Default::default()
+            };
+
+            // e.g. an imported command which has the same id as an existing one
+            let command_2 = CrowCommand {
+                id: "1".to_string(),
+                command: "git status".to_string(),
+                ..Default::default()
+            };
+
+            let mut connection = CrowDBConnection::new(file_path.clone());
+            connection.append_command(command_1.clone());
+            let saved_command_2 = connection.append_command(command_2.clone());
+
+            assert_ne!(saved_command_2.id, command_2.id);
+            assert_eq!(saved_command_2.command, command_2.command);
+
+            let connection = CrowDBConnection::new(file_path);
+            assert_eq!(connection.commands(), &[command_1, saved_command_2]);
+
+            std::fs::remove_dir_all(Path::new(fn_path)).unwrap();
+        }
+
+        #[test]
+        fn backs_up_database_file() {
+            let fn_path = &format!("./testdata/tmp/{}", nanoid!());
+            let file_path = FilePath::new(Some(fn_path), Some("crow.json"));
+
+            let command = CrowCommand {
+                id: "1".to_string(),
+                command: "ls".to_string(),
+                ..Default::default()
+            };
+
+            let mut connection = CrowDBConnection::new(file_path.clone());
+            connection.append_command(command.clone());
+
+            let backup_path = connection.backup().unwrap();
+            assert_eq!(backup_path, Path::new(fn_path).join("crow.json.bak"));
+
+            connection.set_commands(vec![]).write();
+
+            assert_eq!(CrowDBConnection::new(file_path).commands(), &[]);
+            assert_eq!(
+                CrowDBConnection::new(FilePath::new(Some(fn_path), Some("crow.json.bak")))
+                    .commands(),
+                &[command]
+            );
+
+            std::fs::remove_dir_all(Path::new(fn_path)).unwrap();
+        }
+
+        #[test]
+        fn writes_commands_in_sort_order() {
+            let fn_path = &format!("./testdata/tmp/{}", nanoid!());
+            let file_path = FilePath::new(Some(fn_path), Some("crow.json"));
+
+            let command_1 = CrowCommand {
+                id: "1".to_string(),
+                command: "ls".to_string(),
+                ..Default::default()
+            };
+
+            let command_2 = CrowCommand {
+                id: "2".to_string(),
+                command: "git status".to_string(),
+                ..Default::default()
+            };
+
+            let mut connection =
+                CrowDBConnection::new(file_path.clone()).set_sort_order(Some(SortOrder::Command));
+            connection
+                .add_command(command_1.clone())
+                .add_command(command_2.clone())
+                .write();
+
+            // The in memory order stays untouched
+            assert_eq!(
+                connection.commands(),
+                &[command_1.clone(), command_2.clone()]
+            );
+
+            let connection = CrowDBConnection::new(file_path);
+            assert_eq!(connection.commands(), &[command_2, command_1]);
+
+            std::fs::remove_dir_all(Path::new(fn_path)).unwrap();
+        }
+
         #[test]
         fn correctly_removes_command() {
             let fn_path = &format!("./testdata/tmp/{}", nanoid!());
@@ -370,12 +922,14 @@ mod tests {
                 id: "1".to_string(),
                 command: "".to_string(),
                 description: "".to_string(),
+                ..Default::default()
             };
 
             let command_2 = CrowCommand {
                 id: "2".to_string(),
                 command: "".to_string(),
                 description: "".to_string(),
+                ..Default::default()
             };
 
             let mut connection = CrowDBConnection::new(file_path.clone());
@@ -405,4 +959,81 @@ mod tests {
             std::fs::remove_dir_all(Path::new(fn_path)).unwrap();
         }
     }
+
+    mod memory {
+        use crate::{
+            crow_commands::CrowCommand,
+            crow_db::{CommandStore, CrowDBConnection, FilePath, MemoryStore},
+        };
+
+        #[test]
+        fn stores_commands_in_memory() {
+            let file_path = FilePath::new(Some("./testdata"), Some("memory.json"));
+            let store = MemoryStore::default();
+            let command = CrowCommand {
+                id: "1".to_string(),
+                command: "docker ps".to_string(),
+                ..Default::default()
+            };
+
+            CrowDBConnection::with_store(file_path.clone(), Box::new(store.clone()))
+                .add_command(command.clone())
+                .write();
+
+            assert!(!file_path.as_path().exists());
+            assert_eq!(
+                CrowDBConnection::with_store(file_path, Box::new(store)).commands(),
+                &[command]
+            );
+        }
+
+        #[test]
+        fn appends_commands_to_json_lines_store() {
+            let file_path = FilePath::new(Some("./testdata"), Some("memory.jsonl"));
+            let store = MemoryStore::default();
+
+            CrowDBConnection::with_store(file_path.clone(), Box::new(store.clone()))
+                .append_command(CrowCommand::test("1", "echo 1"));
+            CrowDBConnection::with_store(file_path.clone(), Box::new(store.clone()))
+                .append_command(CrowCommand::test("2", "echo 2"));
+
+            assert!(!file_path.as_path().exists());
+            assert_eq!(
+                CrowDBConnection::with_store(file_path, Box::new(store)).commands(),
+                &[
+                    CrowCommand::test("1", "echo 1"),
+                    CrowCommand::test("2", "echo 2")
+                ]
+            );
+        }
+
+        #[test]
+        fn compacts_superseded_json_lines_when_adding_commands() {
+            let file_path = FilePath::new(Some("./testdata"), Some("memory.jsonl"));
+            let mut store = MemoryStore::default();
+            let line = |id: &str, command: &str| {
+                serde_json::to_string(&CrowCommand::test(id, command)).unwrap() + "\n"
+            };
+            let content = line("1", "echo old") + &line("1", "echo new");
+            store.write(&content).unwrap();
+
+            // Reading never changes the file
+            let mut connection = CrowDBConnection::with_store(file_path, Box::new(store.clone()));
+            assert_eq!(connection.commands(), &[CrowCommand::test("1", "echo new")]);
+            assert_eq!(store.read().unwrap(), Some(content));
+
+            connection.append_command(CrowCommand::test("2", "ls"));
+            assert_eq!(
+                store.read().unwrap(),
+                Some(line("1", "echo new") + &line("2", "ls"))
+            );
+
+            // Without superseded lines commands are appended again
+            connection.append_command(CrowCommand::test("3", "pwd"));
+            assert_eq!(
+                store.read().unwrap(),
+                Some(line("1", "echo new") + &line("2", "ls") + &line("3", "pwd"))
+            );
+        }
+    }
 }
@@ -0,0 +1,197 @@
+//! Parsing of the dates which are given on the command line (e.g. `crow list --since
+//! 2024-01-01`) into unix timestamps, which is all crow needs of a date and time library.
+
+const SECONDS_PER_DAY: i64 = 60 * 60 * 24;
+
+/// Parses a date given as `YYYY-MM-DD` (midnight UTC) or as RFC 3339 timestamp (e.g.
+/// `2024-01-01T12:00:00+02:00`) into a unix timestamp. The time may also be separated by a space,
+/// and seconds as well as the time zone (UTC) may be left out.
+pub fn parse_date(input: &str) -> Result<u64, String> {
+    let error = || {
+        format!(
+            "'{}' is not a date, expected YYYY-MM-DD or an RFC 3339 timestamp like 2024-01-01T12:00:00Z",
+            input
+        )
+    };
+
+    let input = input.trim();
+    let (date, time) = match input.find(['T', 't', ' ']) {
+        Some(index) => (&input[..index], Some(input[index + 1..].trim())),
+        None => (input, None),
+    };
+
+    let date: Vec<&str> = date.split('-').collect();
+    let (year, month, day) = match date.as_slice() {
+        [year, month, day] => (
+            year.parse::<i64>().map_err(|_| error())?,
+            month.parse::<i64>().map_err(|_| error())?,
+            day.parse::<i64>().map_err(|_| error())?,
+        ),
+        _ => return Err(error()),
+    };
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return Err(error());
+    }
+
+    let seconds = match time {
+        Some(time) => parse_time(time).ok_or_else(error)?,
+        None => 0,
+    };
+
+    let timestamp = days_since_epoch(year, month, day) * SECONDS_PER_DAY + seconds;
+    if timestamp < 0 {
+        return Err(format!("'{}' is before 1970-01-01", input));
+    }
+
+    Ok(timestamp as u64)
+}
+
+/// Parses the time of an RFC 3339 timestamp (`HH:MM[:SS[.fraction]][Z|±HH:MM]`) into the seconds
+/// since midnight UTC. Fractions of seconds are ignored.
+fn parse_time(time: &str) -> Option<i64> {
+    let (time, offset) = match time.find(['Z', 'z', '+', '-']) {
+        Some(index) => (&time[..index], Some(&time[index..])),
+        None => (time, None),
+    };
+
+    let parts: Vec<&str> = time.split(':').collect();
+    let (hours, minutes, seconds) = match parts.as_slice() {
+        [hours, minutes] => (hours.parse::<i64>().ok()?, minutes.parse::<i64>().ok()?, 0),
+        [hours, minutes, seconds] => (
+            hours.parse::<i64>().ok()?,
+            minutes.parse::<i64>().ok()?,
+            seconds.split('.').next()?.parse::<i64>().ok()?,
+        ),
+        _ => return None,
+    };
+    if hours > 23 || minutes > 59 || seconds > 60 {
+        return None;
+    }
+
+    let offset = match offset {
+        None | Some("Z") | Some("z") => 0,
+        Some(offset) => {
+            let sign = if offset.starts_with('-') { -1 } else { 1 };
+            let (offset_hours, offset_minutes) = offset[1..].split_once(':')?;
+
+            sign * (offset_hours.parse::<i64>().ok()? * 3600
+                + offset_minutes.parse::<i64>().ok()? * 60)
+        }
+    };
+
+    Some(hours * 3600 + minutes * 60 + seconds - offset)
+}
+
+/// Returns the number of days of the month (1-12) in the given year
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Returns the number of days between 1970-01-01 and the given date of the (proleptic) Gregorian
+/// calendar
+fn days_since_epoch(year: i64, month: i64, day: i64) -> i64 {
+    // Counts years from March on, so that the leap day is the last day of the year
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_from_march = (month + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{days_in_month, days_since_epoch, parse_date, parse_time};
+
+    #[test]
+    fn parses_dates_leniently() {
+        assert_eq!(parse_date("1970-01-01"), Ok(0));
+        assert_eq!(parse_date("2024-01-01"), Ok(1_704_067_200));
+        assert_eq!(parse_date("2024-02-29"), Ok(1_709_164_800));
+        assert_eq!(parse_date("2024-01-01T12:30:00Z"), Ok(1_704_112_200));
+        assert_eq!(parse_date("2024-01-01 12:30"), Ok(1_704_112_200));
+        assert_eq!(parse_date("2024-01-01T12:30:00.123Z"), Ok(1_704_112_200));
+        assert_eq!(parse_date("2024-01-01T14:30:00+02:00"), Ok(1_704_112_200));
+        assert_eq!(parse_date("2024-01-01T10:00:00-02:30"), Ok(1_704_112_200));
+
+        assert!(parse_date("2023-02-29").is_err());
+        assert!(parse_date("2024-13-01").is_err());
+        assert!(parse_date("2024-01-01T25:00").is_err());
+        assert!(parse_date("yesterday").is_err());
+        assert!(parse_date("1969-12-31").is_err());
+    }
+
+    #[test]
+    fn knows_leap_years() {
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(2023, 2), 28);
+        assert_eq!(days_in_month(2000, 2), 29);
+        assert_eq!(days_in_month(1900, 2), 28);
+        assert_eq!(days_in_month(2100, 2), 28);
+
+        assert_eq!(parse_date("2000-02-29"), Ok(951_782_400));
+        assert!(parse_date("1900-02-29").is_err());
+        assert!(parse_date("2100-02-29").is_err());
+    }
+
+    #[test]
+    fn validates_the_end_of_months() {
+        assert_eq!(days_in_month(2024, 1), 31);
+        assert_eq!(days_in_month(2024, 4), 30);
+        assert_eq!(days_in_month(2024, 12), 31);
+
+        assert!(parse_date("2024-04-30").is_ok());
+        assert!(parse_date("2024-04-31").is_err());
+        assert!(parse_date("2024-12-32").is_err());
+        assert!(parse_date("2024-01-00").is_err());
+
+        // The day after the end of a month is the first of the next month
+        assert_eq!(
+            parse_date("2024-01-31").unwrap() + 86_400,
+            parse_date("2024-02-01").unwrap()
+        );
+        assert_eq!(
+            parse_date("2023-12-31").unwrap() + 86_400,
+            parse_date("2024-01-01").unwrap()
+        );
+    }
+
+    #[test]
+    fn counts_days_since_epoch() {
+        assert_eq!(days_since_epoch(1970, 1, 1), 0);
+        assert_eq!(days_since_epoch(1969, 12, 31), -1);
+        assert_eq!(days_since_epoch(2000, 3, 1), 11_017);
+        assert_eq!(days_since_epoch(2024, 3, 1), 19_783);
+    }
+
+    #[test]
+    fn applies_time_zone_offsets() {
+        assert_eq!(parse_time("00:00"), Some(0));
+        assert_eq!(parse_time("12:30:15Z"), Some(45_015));
+        assert_eq!(parse_time("01:00+02:00"), Some(-3_600));
+        assert_eq!(parse_time("23:00-02:00"), Some(90_000));
+        assert_eq!(parse_time("23:59:60"), Some(86_400));
+
+        assert_eq!(parse_time("12:00+0200"), None);
+        assert_eq!(parse_time("24:00"), None);
+        assert_eq!(parse_time("12:60"), None);
+
+        // Offsets can move the timestamp to another day
+        assert_eq!(
+            parse_date("2024-03-01T01:00:00+02:00"),
+            parse_date("2024-02-29T23:00:00Z")
+        );
+        assert_eq!(
+            parse_date("2023-12-31T23:00:00-02:00"),
+            parse_date("2024-01-01T01:00:00Z")
+        );
+        assert!(parse_date("1970-01-01T01:00:00+02:00").is_err());
+    }
+}
@@ -0,0 +1,96 @@
+//! Detection of the editor which is used to edit descriptions, commands and keywords
+
+use dialoguer::Editor;
+
+use std::{
+    env,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Editors which are looked up on `$PATH` (in this order) if neither `$VISUAL` nor `$EDITOR` is set
+const FALLBACK_EDITORS: &[&str] = &["nano", "vim", "vi"];
+
+/// Opens the given text inside the editor (see [editor]) and returns the edited text or `None`
+/// if the file was not saved.
+pub fn edit(text: &str) -> Result<Option<String>, String> {
+    editor()?.edit(text).map_err(|e| e.to_string())
+}
+
+/// Returns an [Editor] which uses `$VISUAL` or `$EDITOR` or, if neither is set, the first
+/// fallback editor (nano, vim or vi) which is found on `$PATH`.
+/// Returns an error if no editor is set and none of the fallback editors is installed.
+pub fn editor() -> Result<Editor, String> {
+    let mut editor = Editor::new();
+
+    if cfg!(windows) || env::var_os("VISUAL").is_some() || env::var_os("EDITOR").is_some() {
+        return Ok(editor);
+    }
+
+    editor.executable(fallback_editor()?);
+    Ok(editor)
+}
+
+/// Opens the given file inside the editor (see [editor]) and waits until the editor is closed
+pub fn edit_file(path: &Path) -> Result<(), String> {
+    let command = match env::var("VISUAL").or_else(|_| env::var("EDITOR")) {
+        Ok(command) => command,
+        Err(_) => fallback_editor()?.to_string_lossy().to_string(),
+    };
+
+    // The editor might be configured with arguments, e.g. `code --wait`
+    let mut parts = command.split_whitespace();
+    let program = parts.next().ok_or("The editor command is empty")?;
+
+    let status = Command::new(program)
+        .args(parts)
+        .arg(path)
+        .status()
+        .map_err(|e| format!("Could not start editor '{}'. {}", command, e))?;
+
+    if !status.success() {
+        return Err(format!("The editor '{}' failed ({})", command, status));
+    }
+
+    Ok(())
+}
+
+/// Returns the first fallback editor (see [FALLBACK_EDITORS]) which is found on `$PATH`
+fn fallback_editor() -> Result<PathBuf, String> {
+    env::var_os("PATH")
+        .and_then(|path| find_fallback_editor(&path))
+        .ok_or_else(|| {
+            format!(
+                "No editor found, please set $EDITOR (or install one of: {})",
+                FALLBACK_EDITORS.join(", ")
+            )
+        })
+}
+
+/// Returns the path of the first fallback editor inside the directories of the given `$PATH`
+fn find_fallback_editor(path: &OsStr) -> Option<PathBuf> {
+    let dirs: Vec<PathBuf> = env::split_paths(path).collect();
+
+    FALLBACK_EDITORS.iter().find_map(|name| {
+        dirs.iter()
+            .map(|dir| dir.join(name))
+            .find(|candidate| Path::is_file(candidate))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::OsStr, path::PathBuf};
+
+    use super::find_fallback_editor;
+
+    #[test]
+    fn finds_fallback_editor_on_path() {
+        assert_eq!(
+            find_fallback_editor(OsStr::new("./testdata/missing:./testdata/editors")),
+            Some(PathBuf::from("./testdata/editors/vim"))
+        );
+        assert_eq!(find_fallback_editor(OsStr::new("./testdata/missing")), None);
+    }
+}
@@ -5,9 +5,11 @@ pub enum CliEvent<I> {
     Tick,
 }
 
-/// An input event can either signal the application to [InputEvent::Quit] or
-/// to [InputEvent::Continue] running.
+/// An input event can either signal the application to [InputEvent::Quit] (after a command has
+/// been copied), to [InputEvent::Cancel] (quit without copying) or to [InputEvent::Continue]
+/// running.
 pub enum InputEvent {
     Quit,
+    Cancel,
     Continue,
 }
@@ -0,0 +1,370 @@
+//! Structured, non-fuzzy filters over [CrowCommand]s (e.g. `has:description` or `keyword:git`).
+//! Filters are applied before any fuzzy ranking and are shared between the `--filter` CLI
+//! argument and the fuzzy search input of the interactive mode.
+
+use crate::crow_commands::{normalize_tag, CrowCommand};
+
+/// Fields of a [CrowCommand] which can be checked for presence with `has:<field>`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Field {
+    Description,
+    Keywords,
+    Tags,
+}
+
+/// Comparison of a number in a filter like `used:>5`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Comparison {
+    Greater,
+    Less,
+    Equal,
+}
+
+impl Comparison {
+    /// Splits a value like `>5` into its comparison and number. Without an operator, the number
+    /// has to be equal.
+    fn parse(value: &str) -> Option<(Self, u64)> {
+        let (comparison, number) = match value.as_bytes().first()? {
+            b'>' => (Self::Greater, &value[1..]),
+            b'<' => (Self::Less, &value[1..]),
+            b'=' => (Self::Equal, &value[1..]),
+            _ => (Self::Equal, value),
+        };
+
+        number.parse().ok().map(|number| (comparison, number))
+    }
+
+    fn compare(&self, actual: u64, expected: u64) -> bool {
+        match self {
+            Self::Greater => actual > expected,
+            Self::Less => actual < expected,
+            Self::Equal => actual == expected,
+        }
+    }
+}
+
+/// A single filter predicate. Multiple predicates are always combined with AND.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Predicate {
+    /// `has:<field>` - the field is not empty
+    Has(Field),
+    /// `keyword:<keyword>` - the command has the keyword (case-insensitive)
+    Keyword(String),
+    /// `tag:<tag>` - the command has the tag (case-insensitive)
+    Tag(String),
+    /// `id:<id>` - the command has exactly this id
+    Id(String),
+    /// `used:>5`, `used:<5` or `used:5` - the command has been used more, less or exactly as often
+    Used(Comparison, u64),
+    /// `!<term>` - neither the command, the description, the keywords nor the tags contain the term
+    /// (case-insensitive)
+    Exclude(String),
+}
+
+impl Predicate {
+    /// Parses a single `<key>:<value>` or `!<term>` token into a [Predicate]
+    pub fn parse(token: &str) -> Result<Self, String> {
+        match token.strip_prefix('!') {
+            Some(term) if !term.is_empty() => return Ok(Self::Exclude(term.to_lowercase())),
+            _ => {}
+        }
+
+        let (key, value) = match token.split_once(':') {
+            Some((key, value)) if !value.is_empty() => (key, value),
+            _ => {
+                return Err(format!(
+                    "Invalid filter '{}', expected <key>:<value>",
+                    token
+                ))
+            }
+        };
+
+        match (key, value) {
+            ("has", "description") => Ok(Self::Has(Field::Description)),
+            ("has", "keywords") => Ok(Self::Has(Field::Keywords)),
+            ("has", "tags") => Ok(Self::Has(Field::Tags)),
+            ("has", _) => Err(format!(
+                "Unknown field '{}' in filter '{}', expected one of: description, keywords, tags",
+                value, token
+            )),
+            ("keyword", _) => Ok(Self::Keyword(value.to_string())),
+            ("tag", _) => Ok(Self::Tag(normalize_tag(value))),
+            ("id", _) => Ok(Self::Id(value.to_string())),
+            ("used", _) => match Comparison::parse(value) {
+                Some((comparison, count)) => Ok(Self::Used(comparison, count)),
+                None => Err(format!(
+                    "Invalid use count '{}' in filter '{}', expected e.g. used:>5, used:<5 or used:5",
+                    value, token
+                )),
+            },
+            _ => Err(format!(
+                "Unknown filter '{}', expected one of: has, keyword, tag, id, used",
+                key
+            )),
+        }
+    }
+
+    /// Checks if the given command satisfies this predicate
+    pub fn matches(&self, command: &CrowCommand) -> bool {
+        match self {
+            Self::Has(Field::Description) => !command.description.trim().is_empty(),
+            Self::Has(Field::Keywords) => !command.keywords.is_empty(),
+            Self::Has(Field::Tags) => !command.tags.is_empty(),
+            Self::Keyword(keyword) => command
+                .keywords
+                .iter()
+                .any(|k| k.eq_ignore_ascii_case(keyword)),
+            Self::Tag(tag) => command.has_tag(tag),
+            Self::Id(id) => &command.id == id,
+            Self::Used(comparison, count) => comparison.compare(command.use_count, *count),
+            Self::Exclude(term) => !command.match_str().to_lowercase().contains(term),
+        }
+    }
+}
+
+/// Parses a whitespace separated list of predicates (e.g. the value of `--filter`).
+/// Fails on the first token which is not a valid predicate.
+pub fn parse_predicates(input: &str) -> Result<Vec<Predicate>, String> {
+    input.split_whitespace().map(Predicate::parse).collect()
+}
+
+/// Parses all occurrences of the `--filter` CLI argument into a single list of predicates.
+pub fn parse_filter_args<'a>(
+    filters: impl Iterator<Item = &'a str>,
+) -> Result<Vec<Predicate>, String> {
+    let mut predicates = vec![];
+
+    for filter in filters {
+        predicates.extend(parse_predicates(filter)?);
+    }
+
+    Ok(predicates)
+}
+
+/// Splits a search query into its predicates and the remaining fuzzy search pattern.
+/// Negative terms (e.g. `!compose`) are predicates as well, so excluded commands are never ranked.
+/// A term starting with `\!` is searched for with a literal `!` instead (e.g. `\!!` for `!!`).
+/// Tokens which can't be parsed as a predicate (e.g. a half typed `has:des`) are treated as part of
+/// the fuzzy pattern.
+pub fn split_query(query: &str) -> (Vec<Predicate>, String) {
+    let mut predicates = vec![];
+    let mut pattern = vec![];
+
+    for token in query.split_whitespace() {
+        if let Some(literal) = token.strip_prefix('\\').filter(|t| t.starts_with('!')) {
+            pattern.push(literal);
+            continue;
+        }
+
+        match Predicate::parse(token) {
+            Ok(predicate) => predicates.push(predicate),
+            Err(_) => pattern.push(token),
+        }
+    }
+
+    (predicates, pattern.join(" "))
+}
+
+/// Returns all commands which satisfy every given predicate
+pub fn filter_commands(commands: Vec<CrowCommand>, predicates: &[Predicate]) -> Vec<CrowCommand> {
+    commands
+        .into_iter()
+        .filter(|c| predicates.iter().all(|p| p.matches(c)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crow_commands::CrowCommand;
+
+    use super::{filter_commands, parse_predicates, split_query, Comparison, Field, Predicate};
+
+    #[test]
+    fn parses_predicates() {
+        assert_eq!(
+            parse_predicates("has:description keyword:git id:abc"),
+            Ok(vec![
+                Predicate::Has(Field::Description),
+                Predicate::Keyword("git".to_string()),
+                Predicate::Id("abc".to_string()),
+            ])
+        );
+        assert!(parse_predicates("has:nothing").is_err());
+        assert!(parse_predicates("foo:bar").is_err());
+        assert!(parse_predicates("keyword:").is_err());
+    }
+
+    #[test]
+    fn parses_use_count_predicates() {
+        assert_eq!(
+            parse_predicates("used:>5 used:<2 used:=0 used:3"),
+            Ok(vec![
+                Predicate::Used(Comparison::Greater, 5),
+                Predicate::Used(Comparison::Less, 2),
+                Predicate::Used(Comparison::Equal, 0),
+                Predicate::Used(Comparison::Equal, 3),
+            ])
+        );
+        assert!(parse_predicates("used:>").is_err());
+        assert!(parse_predicates("used:>-1").is_err());
+        assert!(parse_predicates("used:often").is_err());
+    }
+
+    #[test]
+    fn filters_by_use_count() {
+        let command_1 = CrowCommand {
+            id: "1".to_string(),
+            command: "git status".to_string(),
+            use_count: 7,
+            ..Default::default()
+        };
+        let command_2 = CrowCommand {
+            id: "2".to_string(),
+            command: "git log".to_string(),
+            use_count: 5,
+            ..Default::default()
+        };
+        let command_3 = CrowCommand {
+            id: "3".to_string(),
+            command: "ls".to_string(),
+            ..Default::default()
+        };
+        let commands = vec![command_1.clone(), command_2.clone(), command_3.clone()];
+
+        let predicates = parse_predicates("used:>5").unwrap();
+        assert_eq!(
+            filter_commands(commands.clone(), &predicates),
+            vec![command_1]
+        );
+
+        let predicates = parse_predicates("used:<5").unwrap();
+        assert_eq!(
+            filter_commands(commands.clone(), &predicates),
+            vec![command_3]
+        );
+
+        let predicates = parse_predicates("used:5").unwrap();
+        assert_eq!(filter_commands(commands, &predicates), vec![command_2]);
+    }
+
+    #[test]
+    fn splits_query_into_predicates_and_pattern() {
+        assert_eq!(
+            split_query("docker has:description ps has:des"),
+            (
+                vec![Predicate::Has(Field::Description)],
+                "docker ps has:des".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn combines_predicates_with_and() {
+        let command_1 = CrowCommand {
+            id: "1".to_string(),
+            command: "git status".to_string(),
+            description: "show repo status".to_string(),
+            keywords: vec!["Git".to_string()],
+            ..Default::default()
+        };
+        let command_2 = CrowCommand {
+            id: "2".to_string(),
+            command: "git log".to_string(),
+            keywords: vec!["git".to_string()],
+            ..Default::default()
+        };
+        let command_3 = CrowCommand {
+            id: "3".to_string(),
+            command: "ls".to_string(),
+            description: "list files".to_string(),
+            ..Default::default()
+        };
+        let commands = vec![command_1.clone(), command_2.clone(), command_3];
+
+        let predicates = parse_predicates("keyword:git").unwrap();
+        assert_eq!(
+            filter_commands(commands.clone(), &predicates),
+            vec![command_1.clone(), command_2]
+        );
+
+        let predicates = parse_predicates("keyword:git has:description").unwrap();
+        assert_eq!(filter_commands(commands, &predicates), vec![command_1]);
+    }
+
+    #[test]
+    fn filters_tags_case_insensitively() {
+        let command_1 = CrowCommand {
+            id: "1".to_string(),
+            command: "git status".to_string(),
+            tags: vec!["Git".to_string()],
+            ..Default::default()
+        };
+        let command_2 = CrowCommand {
+            id: "2".to_string(),
+            command: "git log".to_string(),
+            tags: vec!["git".to_string()],
+            ..Default::default()
+        };
+        let command_3 = CrowCommand {
+            id: "3".to_string(),
+            command: "ls".to_string(),
+            ..Default::default()
+        };
+        let commands = vec![command_1.clone(), command_2.clone(), command_3];
+
+        let predicates = parse_predicates("tag:GIT").unwrap();
+        assert_eq!(predicates, vec![Predicate::Tag("git".to_string())]);
+        assert_eq!(
+            filter_commands(commands.clone(), &predicates),
+            vec![command_1.clone(), command_2.clone()]
+        );
+
+        let predicates = parse_predicates("has:tags").unwrap();
+        assert_eq!(
+            filter_commands(commands, &predicates),
+            vec![command_1, command_2]
+        );
+    }
+
+    #[test]
+    fn excludes_commands_containing_negative_terms() {
+        let command_1 = CrowCommand {
+            id: "1".to_string(),
+            command: "docker compose up".to_string(),
+            ..Default::default()
+        };
+        let command_2 = CrowCommand {
+            id: "2".to_string(),
+            command: "docker ps".to_string(),
+            keywords: vec!["containers".to_string()],
+            ..Default::default()
+        };
+        let commands = vec![command_1, command_2.clone()];
+
+        let (predicates, pattern) = split_query("docker !Compose !");
+        assert_eq!(predicates, vec![Predicate::Exclude("compose".to_string())]);
+        assert_eq!(pattern, "docker !");
+        assert_eq!(
+            filter_commands(commands.clone(), &predicates),
+            vec![command_2]
+        );
+
+        let predicates = parse_predicates("!compose !containers").unwrap();
+        assert_eq!(filter_commands(commands, &predicates), vec![]);
+    }
+
+    #[test]
+    fn searches_escaped_exclamation_marks_literally() {
+        let (predicates, pattern) = split_query("\\!! sudo \\path");
+        assert_eq!(predicates, vec![]);
+        assert_eq!(pattern, "!! sudo \\path");
+    }
+
+    #[test]
+    fn excludes_commands_by_tag() {
+        let command = CrowCommand::test("1", "docker ps").with_tags(&["containers"]);
+
+        let (predicates, _) = split_query("docker !containers");
+        assert_eq!(filter_commands(vec![command], &predicates), vec![]);
+    }
+}
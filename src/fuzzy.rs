@@ -1,6 +1,7 @@
 use std::cmp::Reverse;
 
-use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use serde::Deserialize;
 
 use crate::{
     command_scores::{CommandScore, CommandScores},
@@ -33,26 +34,117 @@ impl FuzzResult {
     }
 }
 
+/// Weights of the separately scored fields of a [CrowCommand], which are used to combine the
+/// scores to the final score of a command (e.g. so that matching the actual command ranks above
+/// matching the description).
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchWeights {
+    /// Weight of the command text
+    pub command: f64,
+    /// Weight of the description (including keywords)
+    pub description: f64,
+}
+
+impl Default for SearchWeights {
+    fn default() -> Self {
+        Self {
+            command: 1.0,
+            description: 0.5,
+        }
+    }
+}
+
+impl SearchWeights {
+    /// Scores the command and its description separately and combines both scores according to
+    /// the weights. If the pattern only matches across both fields (e.g. `docker list` for
+    /// `docker ps: list containers`), the combined match is weighted with the lower weight.
+    fn score(&self, matcher: &SkimMatcherV2, command: &CrowCommand, pattern: &str) -> i64 {
+        let description = command.description_match_str();
+
+        let command_score = matcher.fuzzy_match(&command.command, pattern);
+        let description_score = matcher.fuzzy_match(&description, pattern);
+
+        let score = match (command_score, description_score) {
+            (None, None) => {
+                let combined_score = matcher
+                    .fuzzy_match(&command.match_str(), pattern)
+                    .unwrap_or(0);
+                self.command.min(self.description) * combined_score as f64
+            }
+            (command_score, description_score) => {
+                self.command * command_score.unwrap_or(0) as f64
+                    + self.description * description_score.unwrap_or(0) as f64
+            }
+        };
+
+        score.round() as i64
+    }
+}
+
+/// Score which is added to commands whose word initials match the search pattern
+const ACRONYM_BOOST: i64 = 50;
+
+/// Checks if the pattern matches the initials of consecutive words of the command, e.g. `gcp`
+/// matches `git cherry-pick`. Words are separated by whitespace, `-` and `_`.
+fn matches_acronym(command: &str, pattern: &str) -> bool {
+    let pattern: String = pattern
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+
+    if pattern.chars().count() < 2 {
+        return false;
+    }
+
+    let initials: String = command
+        .split(|c: char| c.is_whitespace() || c == '-' || c == '_')
+        .filter_map(|word| word.chars().next())
+        .flat_map(char::to_lowercase)
+        .collect();
+
+    initials.contains(&pattern)
+}
+
 /// Given a list of [CrowCommand] this filters all commands by a given pattern.
-/// Commands stay inside the list as long as they reach a certain score.
+/// Commands stay inside the list as long as the combined command and description reach a certain
+/// score. They are ranked by the weighted scores of the command and description
+/// (see [SearchWeights]).
+/// Commands whose word initials match the pattern (see [matches_acronym]) get an additional
+/// score boost.
 /// NOTE: the score is still being fine tuned - this is just a first draft
-/// Results are also sorted according to their score
-pub fn fuzzy_search_commands(commands: Vec<CrowCommand>, pattern: &str) -> Vec<CommandScore> {
+/// Results are also sorted according to their score and contain the matching commands themselves
+/// (see [CommandScore::command])
+pub fn fuzzy_search_commands(
+    commands: Vec<CrowCommand>,
+    pattern: &str,
+    weights: &SearchWeights,
+) -> Vec<CommandScore> {
     if pattern.is_empty() {
         return commands
             .into_iter()
-            .map(|c| CommandScore::new(1, vec![], c.id))
+            .map(|c| CommandScore::new(1, vec![], c.id.clone()).with_command(c))
             .collect();
     }
 
-    let matcher = fuzzy_matcher::skim::SkimMatcherV2::default();
+    let matcher = SkimMatcherV2::default();
     let mut scores: Vec<CommandScore> = commands
         .into_iter()
-        .map(|c| match matcher.fuzzy_indices(&c.match_str(), pattern) {
-            Some((score, indices)) => CommandScore::new(score, indices, c.id),
-            None => CommandScore::new(0, vec![], c.id),
+        .filter_map(|c| match matcher.fuzzy_indices(&c.match_str(), pattern) {
+            // The indices of the combined match are used for highlighting
+            Some((combined_score, indices)) if combined_score > 50 => {
+                let score = weights.score(&matcher, &c, pattern);
+                let score = if matches_acronym(&c.command, pattern) {
+                    score + ACRONYM_BOOST
+                } else {
+                    score
+                };
+
+                Some(CommandScore::new(score, indices, c.id.clone()).with_command(c))
+            }
+            _ => None,
         })
-        .filter(|c| c.score() > 50)
         .collect();
 
     scores.sort_by_key(|c| Reverse(c.score()));
@@ -63,11 +155,42 @@ pub fn fuzzy_search_commands(commands: Vec<CrowCommand>, pattern: &str) -> Vec<C
 mod tests {
     use crate::{command_scores::CommandScore, crow_commands::CrowCommand};
 
-    use super::fuzzy_search_commands;
+    use super::{fuzzy_search_commands, matches_acronym, SearchWeights};
+
+    #[test]
+    fn detects_acronyms() {
+        assert!(matches_acronym("git cherry-pick", "gcp"));
+        assert!(matches_acronym("git cherry-pick", "GCP"));
+        assert!(matches_acronym("git cherry-pick", "cp"));
+        assert!(matches_acronym("docker compose up -d", "dcu"));
+        assert!(!matches_acronym("git status", "gcp"));
+        assert!(!matches_acronym("git cherry-pick", "g"));
+    }
+
+    #[test]
+    fn boosts_acronym_matches() {
+        let command1 = CrowCommand {
+            id: "test1".to_string(),
+            command: "go clean ./pkg".to_string(),
+            ..Default::default()
+        };
+
+        let command2 = CrowCommand {
+            id: "test2".to_string(),
+            command: "git cherry-pick".to_string(),
+            ..Default::default()
+        };
+
+        let result =
+            fuzzy_search_commands(vec![command1, command2], "gcp", &SearchWeights::default());
+
+        let ids: Vec<&String> = result.iter().map(|c| c.command_id()).collect();
+        assert_eq!(ids, vec!["test2", "test1"]);
+    }
 
     #[test]
     fn dont_error_on_empty_command_list() {
-        let result = fuzzy_search_commands(vec![], "test");
+        let result = fuzzy_search_commands(vec![], "test", &SearchWeights::default());
         let expected: Vec<CommandScore> = vec![];
         assert_eq!(expected, result);
     }
@@ -78,11 +201,12 @@ mod tests {
             id: "test1".to_string(),
             command: "echo 'hi'".to_string(),
             description: "test command".to_string(),
+            ..Default::default()
         };
 
-        let result = fuzzy_search_commands(vec![command.clone()], "");
+        let result = fuzzy_search_commands(vec![command.clone()], "", &SearchWeights::default());
 
-        let score = CommandScore::new(1, vec![], command.id);
+        let score = CommandScore::new(1, vec![], command.id.clone()).with_command(command);
         let expected: Vec<CommandScore> = vec![score];
         assert_eq!(expected, result);
     }
@@ -93,27 +217,96 @@ mod tests {
             id: "test1".to_string(),
             command: "echo 'hi'".to_string(),
             description: "test command".to_string(),
+            ..Default::default()
         };
 
         let command2 = CrowCommand {
             id: "test2".to_string(),
             command: "e c something o".to_string(),
             description: "test command".to_string(),
+            ..Default::default()
         };
 
         let command3 = CrowCommand {
             id: "test3".to_string(),
             command: "find".to_string(),
             description: "test command".to_string(),
+            ..Default::default()
         };
 
-        let result =
-            fuzzy_search_commands(vec![command1.clone(), command2.clone(), command3], "echo");
+        let result = fuzzy_search_commands(
+            vec![command1.clone(), command2.clone(), command3],
+            "echo",
+            &SearchWeights::default(),
+        );
 
-        let score_1 = CommandScore::new(91, vec![0, 1, 2, 3], command1.id);
-        let score_2 = CommandScore::new(75, vec![0, 2, 9, 14], command2.id);
+        let score_1 =
+            CommandScore::new(91, vec![0, 1, 2, 3], command1.id.clone()).with_command(command1);
+        let score_2 =
+            CommandScore::new(75, vec![0, 2, 9, 14], command2.id.clone()).with_command(command2);
 
         let expected: Vec<CommandScore> = vec![score_1, score_2];
         assert_eq!(expected, result);
     }
+
+    #[test]
+    fn resolves_the_matching_commands() {
+        let commands = vec![
+            CrowCommand::test("test1", "find").with_description("test command"),
+            CrowCommand::test("test2", "e c something o").with_description("test command"),
+            CrowCommand::test("test3", "echo 'hi'").with_description("test command"),
+        ];
+
+        for pattern in ["", "echo"] {
+            let scores =
+                fuzzy_search_commands(commands.clone(), pattern, &SearchWeights::default());
+
+            assert!(!scores.is_empty());
+            assert!(scores
+                .iter()
+                .all(|score| score.command().map(|c| &c.id) == Some(score.command_id())));
+        }
+
+        let best_match = fuzzy_search_commands(commands, "echo", &SearchWeights::default())
+            .into_iter()
+            .next()
+            .and_then(CommandScore::into_command);
+        assert_eq!(best_match.map(|c| c.command), Some("echo 'hi'".to_string()));
+    }
+
+    #[test]
+    fn ranks_command_matches_above_description_matches() {
+        let command1 = CrowCommand {
+            id: "test1".to_string(),
+            command: "ls".to_string(),
+            description: "docker ps is not this command".to_string(),
+            ..Default::default()
+        };
+
+        let command2 = CrowCommand {
+            id: "test2".to_string(),
+            command: "docker ps".to_string(),
+            description: "list containers".to_string(),
+            ..Default::default()
+        };
+
+        let result = fuzzy_search_commands(
+            vec![command1.clone(), command2.clone()],
+            "docker ps",
+            &SearchWeights::default(),
+        );
+
+        let ids: Vec<&String> = result.iter().map(|c| c.command_id()).collect();
+        assert_eq!(ids, vec!["test2", "test1"]);
+
+        // Ranks the other way around if descriptions are weighted higher
+        let weights = SearchWeights {
+            command: 0.5,
+            description: 1.0,
+        };
+        let result = fuzzy_search_commands(vec![command1, command2], "docker ps", &weights);
+
+        let ids: Vec<&String> = result.iter().map(|c| c.command_id()).collect();
+        assert_eq!(ids, vec!["test1", "test2"]);
+    }
 }
@@ -0,0 +1,142 @@
+//! A lightweight shell tokenizer which is used for the (optional) syntax highlighting of commands
+//! inside the command detail. It only needs to be good enough for coloring and never alters the
+//! command itself.
+
+use tui::style::Color;
+
+/// The kind of a shell token
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TokenKind {
+    /// The program which is invoked (first word of a pipeline segment)
+    Program,
+    /// Short or long flags like `-a` or `--all`
+    Flag,
+    /// Single or double quoted strings including the quotes
+    String,
+    /// Pipes, redirects and command separators
+    Operator,
+    /// Variables like `$HOME`
+    Variable,
+    /// Whitespace and all other words (e.g. arguments)
+    Text,
+}
+
+impl TokenKind {
+    /// Returns the color which is used to render this kind of token
+    pub fn color(&self) -> Color {
+        match self {
+            Self::Program => Color::Cyan,
+            Self::Flag => Color::LightMagenta,
+            Self::String => Color::Green,
+            Self::Operator => Color::LightRed,
+            Self::Variable => Color::LightBlue,
+            Self::Text => Color::White,
+        }
+    }
+}
+
+fn is_operator(c: char) -> bool {
+    matches!(c, '|' | '&' | ';' | '<' | '>')
+}
+
+/// Returns the [TokenKind] of every char (not byte) of the given command.
+pub fn token_kinds(command: &str) -> Vec<TokenKind> {
+    let chars: Vec<char> = command.chars().collect();
+    let mut kinds = Vec::with_capacity(chars.len());
+    let mut expects_program = true;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == '\'' || c == '"' {
+            // Quoted strings end at the next unescaped quote (or the end of the command)
+            let mut end = i + 1;
+            while end < chars.len() && chars[end] != c {
+                if c == '"' && chars[end] == '\\' {
+                    end += 1;
+                }
+                end += 1;
+            }
+            let end = (end + 1).min(chars.len());
+
+            kinds.extend((i..end).map(|_| TokenKind::String));
+            expects_program = false;
+            i = end;
+        } else if is_operator(c) {
+            kinds.push(TokenKind::Operator);
+            expects_program = expects_program || matches!(c, '|' | '&' | ';');
+            i += 1;
+        } else if c.is_whitespace() {
+            kinds.push(TokenKind::Text);
+            expects_program = expects_program || c == '\n';
+            i += 1;
+        } else {
+            let mut end = i;
+            while end < chars.len()
+                && !chars[end].is_whitespace()
+                && !is_operator(chars[end])
+                && chars[end] != '\''
+                && chars[end] != '"'
+            {
+                end += 1;
+            }
+
+            let word: String = chars[i..end].iter().collect();
+            let kind = if word.starts_with('-') {
+                TokenKind::Flag
+            } else if word.starts_with('$') {
+                TokenKind::Variable
+            } else if expects_program && !word.contains('=') {
+                // Environment variable assignments (`FOO=bar cmd`) precede the program
+                expects_program = false;
+                TokenKind::Program
+            } else {
+                TokenKind::Text
+            };
+
+            kinds.extend((i..end).map(|_| kind));
+            i = end;
+        }
+    }
+
+    kinds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{token_kinds, TokenKind};
+
+    #[test]
+    fn tokenizes_shell_commands() {
+        use TokenKind::*;
+
+        let kinds = token_kinds("ls -la \"a b\"|grep $X");
+
+        assert_eq!(
+            kinds,
+            vec![
+                Program, Program, Text, Flag, Flag, Flag, Text, String, String, String, String,
+                String, Operator, Program, Program, Program, Program, Text, Variable, Variable,
+            ]
+        );
+        assert_eq!(kinds.len(), "ls -la \"a b\"|grep $X".chars().count());
+    }
+
+    #[test]
+    fn does_not_fail_on_unterminated_strings() {
+        assert_eq!(
+            token_kinds("echo 'hi"),
+            vec![
+                TokenKind::Program,
+                TokenKind::Program,
+                TokenKind::Program,
+                TokenKind::Program,
+                TokenKind::Text,
+                TokenKind::String,
+                TokenKind::String,
+                TokenKind::String,
+            ]
+        );
+    }
+}
@@ -1,19 +1,49 @@
 use crate::eject;
 
+use log::debug;
+use once_cell::sync::Lazy;
 use regex::Regex;
-use std::{fs::File, io::BufRead, io::BufReader, path::PathBuf};
+use std::{
+    env,
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+/// Number of bytes which are read from the end of a history file at first. History files can grow
+/// to hundreds of megabytes, but the last command is almost always within the last few lines.
+const TAIL_WINDOW: u64 = 64 * 1024;
+
+// The patterns are compiled only once, because the lines are searched again every time the tail
+// window is doubled.
+
+/// Timestamp in front of every command of a .zsh_history with extended history (`: 1612:0;ls`)
+static ZSH_TIMESTAMP: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r": [0-9]*:[0-9];").expect("Valid regex"));
+
+/// Lines which mention crow as a separate word
+static CROW_INVOCATION: Lazy<Regex> = Lazy::new(|| Regex::new(r"\bcrow\b").expect("Valid regex"));
+
+/// Lines with the `add:last` subcommand, which is how crow shows up when it is run through an alias
+static ALIAS_INVOCATION: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(^|\s)add:last(\s|$)").expect("Valid regex"));
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Shell {
     Zsh,
     Bash,
+    Fish,
 }
 
 impl Shell {
     /// Tries to determine the users default shell by checking if the SHELL environment
-    /// variable contains an identifier (e.g. "zsh" or "bash").
+    /// variable contains an identifier (e.g. "zsh", "bash" or "fish").
     pub fn from_path(shell_path: String) -> Option<Self> {
-        const SHELL_MATCHES: &[(&str, Shell)] = &[("zsh", Shell::Zsh), ("bash", Shell::Bash)];
+        const SHELL_MATCHES: &[(&str, Shell)] = &[
+            ("zsh", Shell::Zsh),
+            ("bash", Shell::Bash),
+            ("fish", Shell::Fish),
+        ];
 
         for (text, sh) in SHELL_MATCHES {
             if shell_path.contains(text) {
@@ -24,59 +54,176 @@ impl Shell {
         None
     }
 
-    /// Returns the typical history file location [PathBuf] for the history type.
-    ///
-    /// # Panics
-    /// This function will terminate if the users home directory can't be determined.
+    /// Returns the path of the users history file, which is the file crow reads the history
+    /// from. Uses `$XDG_DATA_HOME` for the fish history if it is set.
+    pub fn user_history_path(&self, home_dir: &Path) -> PathBuf {
+        self.history_path(home_dir, env::var("XDG_DATA_HOME").ok())
+    }
+
+    /// Returns the path of the history file inside the given base directory (usually the users
+    /// home directory). Fish keeps its history in the data directory instead, which is
+    /// `xdg_data_home` if it is set and not empty and `.local/share` in the base directory
+    /// otherwise.
     ///
     /// # Example
     ///
     /// ```ignore
     /// use crow::history::Shell;
-    /// let zsh= Shell::Zsh;
-    /// let hist_file_path = zsh.history_path(); // => "~/.zsh_history"
+    /// let zsh = Shell::Zsh;
+    /// let hist_file_path = zsh.history_path(&home_dir, None); // => "~/.zsh_history"
     /// ```
-    fn history_file_name(&self) -> &str {
+    pub fn history_path(&self, base_dir: &Path, xdg_data_home: Option<String>) -> PathBuf {
         match self {
-            Self::Zsh => ".zsh_history",
-            Self::Bash => ".bash_history",
+            Self::Zsh => base_dir.join(".zsh_history"),
+            Self::Bash => base_dir.join(".bash_history"),
+            Self::Fish => xdg_data_home
+                .filter(|path| !path.is_empty())
+                .map(PathBuf::from)
+                .unwrap_or_else(|| base_dir.join(".local/share"))
+                .join("fish/fish_history"),
         }
     }
 
-    /// Reads the users history file from the determined default shell and returns
-    /// its content as lines.
-    fn read_history_file(&self, mut base_dir: PathBuf) -> Vec<String> {
-        let file_name = self.history_file_name();
+    /// Reads out the last entered command from the given history file of the shell.
+    /// Returns `None` if the history does not contain any command besides crow invocations
+    /// (e.g. in a brand-new shell).
+    pub fn read_last_history_command(&self, path: &Path) -> Option<String> {
+        debug!("Reading {:?} history file: {:?}", self, path);
 
-        base_dir.push(file_name);
+        match self {
+            Self::Fish => find_in_tail(path, last_fish_command),
+            Self::Zsh | Self::Bash => find_in_tail(path, last_command),
+        }
+    }
+}
 
-        let file = File::open(&base_dir).unwrap_or_else(|_| {
-            eject(&format!(
-                "Unable to open detected history file: {:?}",
-                base_dir
-            ));
-        });
+/// Reads out the last entered command from the given history file.
+/// Zsh timestamps are removed unless `plain` is set, in which case every line is taken as is
+/// (e.g. for shells like nushell or tcsh which are not covered by [Shell]).
+pub fn read_last_command_from_file(path: &Path, plain: bool) -> Option<String> {
+    debug!("Reading history file: {:?} (plain: {})", path, plain);
+
+    if plain {
+        find_in_tail(path, last_plain_command)
+    } else {
+        find_in_tail(path, last_command)
+    }
+}
 
-        let file = BufReader::new(file);
+/// Searches the lines at the end of the given history file with `find`, so that huge history
+/// files don't have to be loaded into memory completely. The window at the end of the file is
+/// doubled until `find` returns a command or the whole file has been searched.
+fn find_in_tail(path: &Path, find: fn(&[String]) -> Option<String>) -> Option<String> {
+    let mut file = File::open(path).unwrap_or_else(|_| {
+        eject(&format!("Unable to open history file: {:?}", path));
+    });
+    let len = file.metadata().map(|m| m.len()).unwrap_or(0);
 
-        let lines: Vec<String> = file.lines().filter_map(|line| line.ok()).collect();
-        lines
+    let mut window = TAIL_WINDOW;
+
+    loop {
+        let start = len.saturating_sub(window);
+        let lines = read_lines_from(&mut file, start)
+            .unwrap_or_else(|e| eject(&format!("Unable to read history file {:?}. {}", path, e)));
+
+        if let Some(command) = find(&lines) {
+            return Some(command);
+        }
+
+        if start == 0 {
+            return None;
+        }
+
+        debug!("No command in the last {} bytes of {:?}", window, path);
+        window *= 2;
     }
+}
+
+/// Returns the lines of the file starting at the given byte offset. If the offset is not the
+/// start of the file, the first (probably partial) line is dropped. Lines which are not valid
+/// UTF-8 are skipped.
+fn read_lines_from(file: &mut File, start: u64) -> std::io::Result<Vec<String>> {
+    let mut bytes = vec![];
+    file.seek(SeekFrom::Start(start))?;
+    file.read_to_end(&mut bytes)?;
+
+    Ok(bytes
+        .split(|b| *b == b'\n')
+        .skip(if start > 0 { 1 } else { 0 })
+        .filter_map(|line| String::from_utf8(line.to_vec()).ok())
+        .map(|line| line.trim_end_matches('\r').to_string())
+        .collect())
+}
+
+/// Returns the most recent command of the given history lines which does not invoke crow.
+///
+/// Usually the last line is the current command (e.g. `crow add:last`), but depending on how crow
+/// was invoked (e.g. with an absolute path or env variables) and on whether the shell has already
+/// flushed the current command to the history file, it might also be the command the user wants to
+/// save. Therefore we scan backwards and skip every line containing `crow` as a separate word.
+fn last_command(lines: &[String]) -> Option<String> {
+    // Because we might encounter a .zsh_history we need to make sure that we remove
+    // timestamps in front of the actual command.
+    let commands: Vec<String> = lines
+        .iter()
+        .map(|line| ZSH_TIMESTAMP.replace(line, "").to_string())
+        .collect();
+
+    last_plain_command(&commands)
+}
+
+/// Returns the most recent command of the given fish history lines which does not invoke crow.
+///
+/// Fish stores every command as YAML-like entry (`- cmd: echo hi`) followed by indented fields
+/// like `when:` and `paths:`, which are ignored. Line breaks and backslashes of commands are
+/// escaped by fish.
+fn last_fish_command(lines: &[String]) -> Option<String> {
+    let commands: Vec<String> = lines
+        .iter()
+        .filter_map(|line| line.strip_prefix("- cmd: "))
+        .map(unescape_fish_command)
+        .collect();
 
-    /// Reads out the last entered command from the history file of the users determined
-    /// default shell.
-    pub fn read_last_history_command(&self, base_dir: PathBuf) -> String {
-        let lines = self.read_history_file(base_dir);
+    last_plain_command(&commands)
+}
 
-        // Get the penultimate line because we would otherwise retrieve the current
-        // command (crow add:last).
-        let last_command = &lines[lines.len() - 2];
+/// Replaces the escaped line breaks (`\n`) and backslashes (`\\`) of a fish history command
+fn unescape_fish_command(command: &str) -> String {
+    let mut unescaped = String::with_capacity(command.len());
+    let mut chars = command.chars();
 
-        // Because we might encounter a .zsh_history we need to make sure that we remove
-        // timestamps in front of the actual command.
-        let re = Regex::new(r": [0-9]*:[0-9];").unwrap();
-        re.replace(last_command, "").to_string()
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                unescaped.push('\n');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                unescaped.push('\\');
+                chars.next();
+            }
+            _ => unescaped.push(c),
+        }
     }
+
+    unescaped
+}
+
+/// Returns the most recent line which is neither empty nor invokes crow, without any cleanup of
+/// the lines (see [last_command]).
+/// Besides lines which mention crow, lines with the `add:last` subcommand count as crow
+/// invocations too, so that crow is also skipped if it has been run through an alias (e.g.
+/// `c add:last` with `alias c=crow`).
+fn last_plain_command(lines: &[String]) -> Option<String> {
+    lines
+        .iter()
+        .rev()
+        .find(|command| {
+            !command.trim().is_empty()
+                && !CROW_INVOCATION.is_match(command)
+                && !ALIAS_INVOCATION.is_match(command)
+        })
+        .cloned()
 }
 
 #[cfg(test)]
@@ -94,9 +241,54 @@ mod tests {
             assert_eq!(Shell::from_path("/bin/zsh".to_string()), Some(Shell::Zsh));
         }
 
+        #[test]
+        fn detects_fish() {
+            assert_eq!(
+                Shell::from_path("/usr/local/bin/fish".to_string()),
+                Some(Shell::Fish)
+            );
+        }
+
         #[test]
         fn does_not_detect_others() {
-            assert_eq!(Shell::from_path("/bin/fish".to_string()), None);
+            assert_eq!(Shell::from_path("/bin/tcsh".to_string()), None);
+        }
+    }
+
+    mod history_path {
+        use std::path::Path;
+
+        use crate::history::Shell;
+
+        #[test]
+        fn joins_base_dir_and_file_name() {
+            assert_eq!(
+                Shell::Zsh.history_path(Path::new("/home/crow"), None),
+                Path::new("/home/crow/.zsh_history")
+            );
+            assert!(Shell::Bash
+                .history_path(Path::new("./testdata"), None)
+                .exists());
+            assert_eq!(
+                Shell::Fish.history_path(Path::new("/home/crow"), None),
+                Path::new("/home/crow/.local/share/fish/fish_history")
+            );
+        }
+
+        #[test]
+        fn uses_xdg_data_home_for_fish() {
+            assert_eq!(
+                Shell::Fish.history_path(Path::new("/home/crow"), Some("/data".to_string())),
+                Path::new("/data/fish/fish_history")
+            );
+            assert_eq!(
+                Shell::Fish.history_path(Path::new("/home/crow"), Some("".to_string())),
+                Path::new("/home/crow/.local/share/fish/fish_history")
+            );
+            assert_eq!(
+                Shell::Zsh.history_path(Path::new("/home/crow"), Some("/data".to_string())),
+                Path::new("/home/crow/.zsh_history")
+            );
         }
     }
 
@@ -113,9 +305,20 @@ mod tests {
             // this is where the cargo test command is invoked from!
             let path = PathBuf::from("./testdata/");
 
-            let result = shell.read_last_history_command(path);
+            let result = shell.read_last_history_command(&shell.history_path(&path, None));
+
+            assert_eq!(result, Some("echo \"Hi from test history\"".to_string()));
+        }
+
+        #[test]
+        fn returns_none_if_history_only_contains_crow_command() {
+            let shell = Shell::from_path("/bin/bash".to_string()).unwrap();
+
+            let path = PathBuf::from("./testdata/only_crow_history/");
 
-            assert_eq!(result, "echo \"Hi from test history\"");
+            let result = shell.read_last_history_command(&shell.history_path(&path, None));
+
+            assert_eq!(result, None);
         }
 
         #[test]
@@ -126,9 +329,158 @@ mod tests {
             // this is where the cargo test command is invoked from!
             let path = PathBuf::from("./testdata/");
 
-            let result = shell.read_last_history_command(path);
+            let result = shell.read_last_history_command(&shell.history_path(&path, None));
+
+            assert_eq!(result, Some("echo 'Hi from test zsh_history'".to_string()));
+        }
+
+        #[test]
+        fn reads_cmd_of_fish_entries() {
+            let shell = Shell::from_path("/usr/bin/fish".to_string()).unwrap();
+
+            // Note: the path is relative to the root dir of the repository, because
+            // this is where the cargo test command is invoked from!
+            let path = PathBuf::from("./testdata/");
+
+            let result = shell.read_last_history_command(&shell.history_path(&path, None));
+
+            assert_eq!(result, Some("echo 'Hi from test fish_history'".to_string()));
+        }
+    }
+
+    mod read_last_command_from_file {
+        use std::path::Path;
+
+        use crate::history::read_last_command_from_file;
+
+        #[test]
+        fn reads_plain_history_file() {
+            let path = Path::new("./testdata/.bash_history");
+
+            assert_eq!(
+                read_last_command_from_file(path, true),
+                Some("echo \"Hi from test history\"".to_string())
+            );
+        }
+
+        #[test]
+        fn keeps_zsh_timestamps_in_plain_mode() {
+            let path = Path::new("./testdata/.zsh_history");
+
+            assert_eq!(
+                read_last_command_from_file(path, false),
+                Some("echo 'Hi from test zsh_history'".to_string())
+            );
+            assert_eq!(
+                read_last_command_from_file(path, true),
+                Some(": 1542378865:0;echo 'Hi from test zsh_history'".to_string())
+            );
+        }
+    }
+
+    mod find_in_tail {
+        use std::path::Path;
+
+        use crate::history::{find_in_tail, last_command, TAIL_WINDOW};
+
+        #[test]
+        fn finds_last_command_in_large_history_file() {
+            let dir = format!("./testdata/tmp/{}", nanoid::nanoid!());
+            std::fs::create_dir_all(&dir).unwrap();
+            let path = Path::new(&dir).join(".bash_history");
+
+            let mut history = String::from("echo first\n");
+            while (history.len() as u64) < 4 * TAIL_WINDOW {
+                history.push_str("crow add:last\n");
+            }
+            std::fs::write(&path, &history).unwrap();
+
+            // The only command is far outside of the first window
+            assert_eq!(
+                find_in_tail(&path, last_command),
+                Some("echo first".to_string())
+            );
+
+            history.push_str("echo last\ncrow add:last\n");
+            std::fs::write(&path, &history).unwrap();
+
+            assert_eq!(
+                find_in_tail(&path, last_command),
+                Some("echo last".to_string())
+            );
+
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+
+    mod last_command {
+        use crate::history::{last_command, last_fish_command, last_plain_command};
+
+        fn lines(lines: &[&str]) -> Vec<String> {
+            lines.iter().map(|l| l.to_string()).collect()
+        }
+
+        #[test]
+        fn skips_crow_invocations() {
+            let history = lines(&[
+                "git status",
+                "docker ps -a",
+                "crow list docker",
+                "command crow add:last",
+                "RUST_LOG=debug /usr/local/bin/crow add:last",
+            ]);
+
+            assert_eq!(last_command(&history), Some("docker ps -a".to_string()));
+        }
+
+        #[test]
+        fn skips_crow_invocations_through_an_alias() {
+            let history = lines(&[
+                "alias c=crow",
+                "docker ps -a",
+                "c add:last",
+                ": 1542378865:0;c add:last --plain",
+            ]);
+
+            assert_eq!(last_command(&history), Some("docker ps -a".to_string()));
+        }
+
+        #[test]
+        fn returns_last_line_if_current_command_is_not_written_yet() {
+            let history = lines(&["git status", ": 1542378865:0;docker ps -a"]);
+
+            assert_eq!(last_command(&history), Some("docker ps -a".to_string()));
+        }
+
+        #[test]
+        fn returns_none_without_other_commands() {
+            assert_eq!(last_command(&lines(&["crow add:last", ""])), None);
+            assert_eq!(last_command(&[]), None);
+        }
+
+        #[test]
+        fn unescapes_multiline_fish_commands() {
+            let history = lines(&[
+                "- cmd: for f in *.txt\\n  echo \\\\$f\\nend",
+                "  when: 1542378865",
+                "- cmd: crow add:last",
+                "  when: 1542378870",
+            ]);
+
+            assert_eq!(
+                last_fish_command(&history),
+                Some("for f in *.txt\n  echo \\$f\nend".to_string())
+            );
+        }
+
+        #[test]
+        fn does_not_clean_up_plain_commands() {
+            let history = lines(&["ls -la", "> 1542378865 > echo hi", "crow add:last"]);
 
-            assert_eq!(result, "echo 'Hi from test zsh_history'");
+            assert_eq!(
+                last_plain_command(&history),
+                Some("> 1542378865 > echo hi".to_string())
+            );
         }
     }
 }
@@ -0,0 +1,156 @@
+//! Generation of ids for new commands according to the configured [IdScheme]
+
+use nanoid::nanoid;
+use serde::Deserialize;
+
+use crate::crow_commands::{CrowCommand, Id};
+
+/// Maximum length of a slug (without the suffix which is added on collisions)
+const MAX_SLUG_LENGTH: usize = 40;
+
+/// The scheme by which ids of new commands are generated
+#[derive(Deserialize, Debug, Default, Copy, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum IdScheme {
+    /// Short random ids like `V1StGXR8_Z5jdHi6B-myT` (default)
+    #[default]
+    Nanoid,
+    /// Human readable ids derived from the description (or the command if there is no
+    /// description) like `show-repo-status`. Colliding slugs get a numeric suffix (`-2`, `-3`, ...).
+    Slug,
+    /// Random (version 4) UUIDs like `6ba7b810-9dad-41d1-80b4-00c04fd430c8`
+    Uuid,
+}
+
+impl IdScheme {
+    /// Generates an id for the given command which is not used by any of the existing commands
+    pub fn generate(&self, command: &CrowCommand, existing: &[CrowCommand]) -> Id {
+        let is_taken = |id: &str| existing.iter().any(|c| c.id == id);
+
+        let id = match self {
+            Self::Nanoid => nanoid!(),
+            Self::Uuid => uuid(),
+            Self::Slug => {
+                let source = command
+                    .description
+                    .lines()
+                    .map(str::trim)
+                    .find(|l| !l.is_empty())
+                    .unwrap_or(&command.command);
+
+                match slug(source) {
+                    Some(slug) => (1..)
+                        .map(|n| match n {
+                            1 => slug.clone(),
+                            _ => format!("{}-{}", slug, n),
+                        })
+                        .find(|id| !is_taken(id))
+                        .expect("Infinite iterator"),
+                    // Fall back to random ids for e.g. commands which only consist of symbols
+                    None => nanoid!(),
+                }
+            }
+        };
+
+        if is_taken(&id) {
+            return self.generate(command, existing);
+        }
+
+        id
+    }
+}
+
+/// Creates a lowercase slug of the alphanumeric words of the given text.
+/// Returns `None` if the text does not contain any alphanumeric chars.
+fn slug(text: &str) -> Option<String> {
+    let mut slug = String::new();
+
+    for word in text
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+    {
+        if !slug.is_empty() && slug.len() + word.len() >= MAX_SLUG_LENGTH {
+            break;
+        }
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.push_str(&word.to_ascii_lowercase());
+    }
+
+    slug.truncate(MAX_SLUG_LENGTH);
+
+    Some(slug).filter(|s| !s.is_empty())
+}
+
+/// Creates a random (version 4) UUID
+fn uuid() -> String {
+    let mut bytes = nanoid::rngs::default(16);
+    // Set the version (4) and the variant (RFC 4122)
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crow_commands::CrowCommand;
+
+    use super::{slug, IdScheme};
+
+    #[test]
+    fn creates_slugs() {
+        assert_eq!(
+            slug("Show repo status!"),
+            Some("show-repo-status".to_string())
+        );
+        assert_eq!(
+            slug("git log --oneline"),
+            Some("git-log-oneline".to_string())
+        );
+        assert_eq!(slug("|| &&"), None);
+        assert!(slug(&"word ".repeat(20)).unwrap().len() <= 40);
+    }
+
+    #[test]
+    fn generates_unique_slugs() {
+        let new_command =
+            CrowCommand::test("", "git status").with_description("Show repo status\nand more");
+        let mut existing = vec![];
+
+        for expected in &[
+            "show-repo-status",
+            "show-repo-status-2",
+            "show-repo-status-3",
+        ] {
+            let id = IdScheme::Slug.generate(&new_command, &existing);
+            assert_eq!(&id, expected);
+
+            existing.push(CrowCommand::test(&id, "git status"));
+        }
+
+        assert_eq!(
+            IdScheme::Slug.generate(&CrowCommand::test("", "ls -la"), &existing),
+            "ls-la"
+        );
+    }
+
+    #[test]
+    fn generates_uuids() {
+        let id = IdScheme::Uuid.generate(&CrowCommand::test("", "ls"), &[]);
+        let groups: Vec<usize> = id.split('-').map(str::len).collect();
+
+        assert_eq!(groups, vec![8, 4, 4, 4, 12]);
+        assert_eq!(&id[14..15], "4");
+    }
+}
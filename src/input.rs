@@ -1,24 +1,25 @@
+use crate::clipboard::CopyFormat;
+use crate::color::paint;
 use crate::commands::default::InputWorkerEvent;
 use crate::crow_commands::Commands;
-use crate::crow_db::CrowDBConnection;
+use crate::editor::{edit, edit_file};
 use crate::eject;
 use crate::events::{CliEvent, InputEvent};
-use crate::fuzzy::fuzzy_search_commands;
-use crate::state::{MenuItem, State};
-use copypasta::{ClipboardContext, ClipboardProvider};
+use crate::palette::{search_actions, Action};
+use crate::state::{EditField, MenuItem, PendingEdit, State};
 use crossterm::event::{
     DisableMouseCapture, Event as CEvent, KeyCode, KeyEvent, KeyModifiers, MouseEvent,
     MouseEventKind,
 };
 use crossterm::execute;
-use crossterm::style::Stylize;
+use crossterm::style::Color;
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
-use dialoguer::Editor;
 
 use std::sync::mpsc::Sender;
 use std::{
     io::{Error, Stdout},
     sync::mpsc::Receiver,
+    time::Instant,
 };
 
 use tui::{backend::CrosstermBackend, Terminal};
@@ -34,15 +35,46 @@ pub fn handle_input(
 ) -> Result<InputEvent, Error> {
     match input_worker_rx.recv().expect("Open input channel") {
         CliEvent::Input(event) => {
+            // Raw mode turns ctrl+c into a key event instead of SIGINT, which has to quit crow
+            // in every situation (even while an error is shown)
+            if let CEvent::Key(KeyEvent {
+                code: KeyCode::Char('c'),
+                modifiers: KeyModifiers::CONTROL,
+            }) = event
+            {
+                quit(terminal, None)?;
+                return Ok(InputEvent::Cancel);
+            }
+
+            // Another program might have changed the commands since the last tick
+            reload_changed_commands(state);
+
+            // An error popup has to be acknowledged before any other input is handled
+            if state.error().is_some() {
+                if let CEvent::Key(KeyEvent {
+                    code: KeyCode::Enter | KeyCode::Esc,
+                    ..
+                }) = event
+                {
+                    state.acknowledge_error();
+                }
+
+                return Ok(InputEvent::Continue);
+            }
+
             // TODO feels like I am doing the work twice
-            if let InputEvent::Quit = handle_general(event, terminal, state)? {
-                return Ok(InputEvent::Quit);
+            if let quit @ (InputEvent::Quit | InputEvent::Cancel) =
+                handle_general(event, terminal, state)?
+            {
+                return Ok(quit);
             };
 
             match state.active_menu_item() {
                 MenuItem::Find => {
-                    if let InputEvent::Quit = handle_find(event, terminal, state)? {
-                        return Ok(InputEvent::Quit);
+                    if let quit @ (InputEvent::Quit | InputEvent::Cancel) =
+                        handle_find(main_tx, event, terminal, state)?
+                    {
+                        return Ok(quit);
                     };
                 }
                 &MenuItem::Edit => {
@@ -51,14 +83,43 @@ pub fn handle_input(
                 MenuItem::Delete => {
                     handle_delete(event, state)?;
                 }
+                MenuItem::Tags => {
+                    handle_tags(main_tx, event, state);
+                }
+                MenuItem::Palette => {
+                    return handle_palette(main_tx, event, terminal, state);
+                }
+                MenuItem::ConfirmEdit => {
+                    handle_confirm_edit(event, state);
+                }
+            }
+        }
+        CliEvent::Tick => {
+            reload_changed_commands(state);
+            state.clear_expired_flash(Instant::now());
+
+            if let Err(e) = state.copy_settled_selection(Instant::now()) {
+                state.set_flash(format!("Could not copy the selected command. {}", e));
             }
         }
-        CliEvent::Tick => {}
     }
 
     Ok(InputEvent::Continue)
 }
 
+/// Reloads the commands if the database file has been changed by another program (see
+/// [State::reload_if_changed]) and tells the user about it
+fn reload_changed_commands(state: &mut State) {
+    match state.reload_if_changed() {
+        Ok(true) => state.set_flash("Reloaded commands changed by another program".to_string()),
+        Ok(false) => {}
+        Err(e) => state.set_error(format!(
+            "Could not reload the changed database file, changes are not saved until it has been fixed. {}",
+            e
+        )),
+    }
+}
+
 /// Handles input which is specific to [MenuItem::Delete]
 fn handle_delete(event: CEvent, state: &mut State) -> Result<(), Error> {
     if let CEvent::Key(key_event) = event {
@@ -68,7 +129,7 @@ fn handle_delete(event: CEvent, state: &mut State) -> Result<(), Error> {
                 modifiers: KeyModifiers::NONE,
             } => {
                 if let Some(c) = state.selected_crow_command() {
-                    let mut connection = CrowDBConnection::new(state.db_file_path().clone());
+                    let mut connection = state.connection();
                     connection.remove_command(c).write();
 
                     let commands = connection.commands();
@@ -79,9 +140,8 @@ fn handle_delete(event: CEvent, state: &mut State) -> Result<(), Error> {
                     state
                         .crow_commands_mut()
                         .set_commands(Commands::normalize(&commands));
-                    state.set_fuzz_result(vec![]);
                     state.set_input("".to_string());
-                    state.set_active_menu_item(MenuItem::Find);
+                    state.return_to_find();
                 }
             }
 
@@ -93,7 +153,7 @@ fn handle_delete(event: CEvent, state: &mut State) -> Result<(), Error> {
                 code: KeyCode::Char('n'),
                 modifiers: KeyModifiers::NONE,
             } => {
-                state.set_active_menu_item(MenuItem::Find);
+                state.return_to_find();
             }
             _ => {}
         }
@@ -102,69 +162,378 @@ fn handle_delete(event: CEvent, state: &mut State) -> Result<(), Error> {
     Ok(())
 }
 
+/// Handles input which is specific to [MenuItem::Tags]
+fn handle_tags(main_tx: &Sender<InputWorkerEvent>, event: CEvent, state: &mut State) {
+    if let CEvent::Key(key_event) = event {
+        match key_event {
+            // Edits all tags at once as comma separated list, e.g. to rename a tag
+            KeyEvent {
+                code: KeyCode::Char('o'),
+                modifiers: KeyModifiers::CONTROL,
+            } => {
+                edit_selected_field(main_tx, EditField::Tags, state);
+            }
+
+            KeyEvent {
+                code: KeyCode::Enter,
+                modifiers: KeyModifiers::NONE,
+            } => {
+                state.toggle_tag_of_selected_command();
+            }
+
+            KeyEvent {
+                code: KeyCode::Char(c),
+                modifiers: KeyModifiers::NONE,
+            }
+            | KeyEvent {
+                code: KeyCode::Char(c),
+                modifiers: KeyModifiers::SHIFT,
+            } => {
+                state.mut_tag_input().push(c);
+            }
+
+            KeyEvent {
+                code: KeyCode::Backspace,
+                modifiers: KeyModifiers::NONE,
+            } => {
+                state.mut_tag_input().pop();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Handles input which is specific to [MenuItem::ConfirmEdit]. ENTER saves the edit, unless the
+/// edited text is empty, in which case the original is kept.
+fn handle_confirm_edit(event: CEvent, state: &mut State) {
+    if let CEvent::Key(key_event) = event {
+        match key_event {
+            KeyEvent {
+                code: KeyCode::Char('y'),
+                modifiers: KeyModifiers::NONE,
+            } => state.confirm_pending_edit(),
+
+            KeyEvent {
+                code: KeyCode::Char('n'),
+                modifiers: KeyModifiers::NONE,
+            } => state.discard_pending_edit(),
+
+            KeyEvent {
+                code: KeyCode::Char('t'),
+                modifiers: KeyModifiers::NONE,
+            } => state.truncate_pending_edit(),
+
+            KeyEvent {
+                code: KeyCode::Enter,
+                modifiers: KeyModifiers::NONE,
+            } => match state.pending_edit() {
+                Some(edit) if !edit.is_empty() => state.confirm_pending_edit(),
+                _ => state.discard_pending_edit(),
+            },
+            _ => {}
+        }
+    }
+}
+
 /// Handles input which is specific to [MenuItem::Edit]
 fn handle_edit(
     main_tx: &Sender<InputWorkerEvent>,
     event: CEvent,
     state: &mut State,
 ) -> Result<InputEvent, Error> {
-    if let Some(c) = state.selected_crow_command() {
-        if let CEvent::Key(key_event) = event {
-            match key_event {
-                KeyEvent {
-                    code: KeyCode::Char('d'),
-                    modifiers: KeyModifiers::NONE,
-                } => {
-                    suspend_input_thread(main_tx);
-
-                    let command = c.clone();
-                    let edited_description = Editor::new()
-                        .edit(&command.description)
-                        .unwrap_or_else(|e| eject(&format!("Could not edit description. {}", e)));
-                    state.crow_commands_mut().commands_mut().update_description(
-                        command.id,
-                        &edited_description.unwrap_or(command.description),
-                    );
-                    state.write_commands_to_db();
-
-                    resume_input_thread(main_tx);
-                }
-                KeyEvent {
-                    code: KeyCode::Char('c'),
-                    modifiers: KeyModifiers::NONE,
-                } => {
-                    suspend_input_thread(main_tx);
+    if state.selected_crow_command().is_none() {
+        return Ok(InputEvent::Continue);
+    }
 
-                    let command = c.clone();
-                    let edited_command = Editor::new()
-                        .edit(&command.command)
-                        .unwrap_or_else(|e| eject(&format!("Could not edit command. {}", e)));
+    if let CEvent::Key(key_event) = event {
+        match key_event {
+            KeyEvent {
+                code: KeyCode::Char('d'),
+                modifiers: KeyModifiers::NONE,
+            } => edit_selected_field(main_tx, EditField::Description, state),
+            KeyEvent {
+                code: KeyCode::Char('c'),
+                modifiers: KeyModifiers::NONE,
+            } => edit_selected_field(main_tx, EditField::Command, state),
+            KeyEvent {
+                code: KeyCode::Char('k'),
+                modifiers: KeyModifiers::NONE,
+            } => edit_selected_field(main_tx, EditField::Keywords, state),
+            KeyEvent {
+                code: KeyCode::Char('t'),
+                modifiers: KeyModifiers::NONE,
+            } => state.open_tags(),
+            KeyEvent {
+                code: KeyCode::Char('y'),
+                modifiers: KeyModifiers::NONE,
+            } => duplicate_and_edit_selected_command(main_tx, state),
+            _ => {}
+        }
+    }
 
-                    state
-                        .crow_commands_mut()
-                        .commands_mut()
-                        .update_command(command.id, &edited_command.unwrap_or(command.command));
+    Ok(InputEvent::Continue)
+}
 
-                    state.write_commands_to_db();
-                    state.set_active_menu_item(MenuItem::Find);
+/// Opens the given field of the selected command in the editor and proposes the edited text (see
+/// [State::propose_edit]). Keywords and tags are edited as comma separated list.
+fn edit_selected_field(main_tx: &Sender<InputWorkerEvent>, field: EditField, state: &mut State) {
+    let command = match state.selected_crow_command() {
+        Some(c) => c.clone(),
+        None => return,
+    };
+    let original = match field {
+        EditField::Command => command.command,
+        EditField::Description => command.description,
+        EditField::Keywords => command.keywords.join(", "),
+        EditField::Tags => command.tags.join(", "),
+    };
+
+    suspend_input_thread(main_tx);
+
+    match edit(&original) {
+        Ok(Some(edited)) => {
+            // The commands might have been changed while the editor was open
+            reload_changed_commands(state);
+            state.propose_edit(PendingEdit {
+                command_id: command.id,
+                field,
+                original,
+                edited,
+            });
+        }
+        Ok(None) => {}
+        Err(e) => state.set_error(format!("Could not edit {}. {}", field.label(), e)),
+    }
+
+    resume_input_thread(main_tx);
+}
+
+/// Duplicates the selected command and opens the duplicate in the editor right away
+fn duplicate_and_edit_selected_command(main_tx: &Sender<InputWorkerEvent>, state: &mut State) {
+    if state.selected_crow_command().is_none() {
+        return;
+    }
+
+    // The duplicate is selected afterwards
+    state.duplicate_selected_command();
+    state.set_flash("Duplicated command".to_string());
+
+    edit_selected_field(main_tx, EditField::Command, state);
+}
+
+/// Handles input which is specific to [MenuItem::Palette]
+fn handle_palette(
+    main_tx: &Sender<InputWorkerEvent>,
+    event: CEvent,
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    state: &mut State,
+) -> Result<InputEvent, Error> {
+    let actions = search_actions(state.palette_input());
 
-                    resume_input_thread(main_tx);
+    if let CEvent::Key(key_event) = event {
+        match key_event {
+            KeyEvent {
+                code: KeyCode::Enter,
+                modifiers: KeyModifiers::NONE,
+            } => {
+                if let Some(action) = actions.get(state.palette_selection()) {
+                    state.return_to_find();
+                    return run_action(*action, main_tx, terminal, state);
                 }
-                _ => {}
             }
+
+            KeyEvent {
+                code: KeyCode::Down,
+                ..
+            } if !actions.is_empty() => {
+                state.set_palette_selection((state.palette_selection() + 1) % actions.len());
+            }
+
+            KeyEvent {
+                code: KeyCode::Up, ..
+            } if !actions.is_empty() => {
+                let selection = state.palette_selection();
+                state.set_palette_selection(selection.checked_sub(1).unwrap_or(actions.len() - 1));
+            }
+
+            KeyEvent {
+                code: KeyCode::Char(c),
+                modifiers: KeyModifiers::NONE,
+            }
+            | KeyEvent {
+                code: KeyCode::Char(c),
+                modifiers: KeyModifiers::SHIFT,
+            } => {
+                let input = format!("{}{}", state.palette_input(), c);
+                state.set_palette_input(input);
+            }
+
+            KeyEvent {
+                code: KeyCode::Backspace,
+                modifiers: KeyModifiers::NONE,
+            } => {
+                let mut input = state.palette_input().clone();
+                input.pop();
+                state.set_palette_input(input);
+            }
+            _ => {}
         }
     }
 
     Ok(InputEvent::Continue)
 }
 
+/// Runs an action of the command palette on the selected command, which does the same as the key
+/// binding of the action
+fn run_action(
+    action: Action,
+    main_tx: &Sender<InputWorkerEvent>,
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    state: &mut State,
+) -> Result<InputEvent, Error> {
+    match action {
+        Action::Copy => return copy_selected_command(terminal, state),
+        Action::CopyAsCodeBlock => return copy_selected_command_as_code_block(terminal, state),
+        Action::CopyWithDescription => state.copy_selected_command_with_description(),
+        Action::CopyId => match state.copy_selected_command(CopyFormat::Id) {
+            Ok(Some(id)) => {
+                return quit(
+                    terminal,
+                    Some(&format!(
+                        "\nId:\n  {}\ncopied to clipboard!\n",
+                        paint(id, Color::Cyan, state.color())
+                    )),
+                )
+            }
+            Ok(None) => {}
+            Err(e) => state.set_error(format!("Could not add id to clipboard. {}", e)),
+        },
+        Action::EditCommand => {
+            state.edit_selected_command();
+            edit_selected_field(main_tx, EditField::Command, state);
+        }
+        Action::Duplicate => {
+            state.edit_selected_command();
+            duplicate_and_edit_selected_command(main_tx, state);
+        }
+        // Editing the description or keywords doesn't stay in the edit mode, unlike their key
+        // bindings inside the edit mode
+        Action::EditDescription => edit_selected_field(main_tx, EditField::Description, state),
+        Action::EditKeywords => edit_selected_field(main_tx, EditField::Keywords, state),
+        Action::EditTags => state.open_tags(),
+        Action::Delete => {
+            if state.selected_crow_command().is_some() {
+                state.set_active_menu_item(MenuItem::Delete);
+            }
+        }
+        Action::EditDatabase => edit_database(main_tx, terminal, state)?,
+        Action::ToggleScores => state.toggle_show_scores(),
+        Action::ToggleTaggedOnly => state.toggle_tagged_only(),
+        Action::ToggleInlineDescriptions => state.toggle_inline_descriptions(),
+        Action::ClearSearch => state.clear_input(),
+        Action::Quit => {
+            quit(terminal, None)?;
+            return Ok(InputEvent::Cancel);
+        }
+    }
+
+    Ok(InputEvent::Continue)
+}
+
+/// Copies the selected command and quits
+fn copy_selected_command(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    state: &mut State,
+) -> Result<InputEvent, Error> {
+    match state.copy_selected_command(CopyFormat::Command) {
+        Ok(Some(command)) => {
+            state.mark_selected_command_used();
+
+            let color = state.color();
+            let message = match state.verify_clipboard(&command) {
+                Some(warning) => format!(
+                    "\nCommand:\n  {}\n{}\n",
+                    paint(&command, Color::Cyan, color),
+                    paint(warning, Color::Yellow, color)
+                ),
+                None => format!(
+                    "\nCommand:\n  {}\ncopied to clipboard!\n",
+                    paint(&command, Color::Cyan, color)
+                ),
+            };
+
+            quit(terminal, Some(&message))
+        }
+        Ok(None) => Ok(InputEvent::Continue),
+        Err(e) => {
+            state.set_error(format!("Could not add command to clipboard. {}", e));
+            Ok(InputEvent::Continue)
+        }
+    }
+}
+
+/// Copies the selected command as markdown code block and quits
+fn copy_selected_command_as_code_block(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    state: &mut State,
+) -> Result<InputEvent, Error> {
+    match state.copy_selected_command(state.code_block_format()) {
+        Ok(Some(block)) => {
+            state.mark_selected_command_used();
+
+            let color = state.color();
+            let message = match state.verify_clipboard(&block) {
+                Some(warning) => format!(
+                    "\n{}\n{}\n",
+                    paint(&block, Color::Cyan, color),
+                    paint(warning, Color::Yellow, color)
+                ),
+                None => format!(
+                    "\n{}\ncopied to clipboard as code block!\n",
+                    paint(&block, Color::Cyan, color)
+                ),
+            };
+
+            quit(terminal, Some(&message))
+        }
+        Ok(None) => Ok(InputEvent::Continue),
+        Err(e) => {
+            state.set_error(format!("Could not add command to clipboard. {}", e));
+            Ok(InputEvent::Continue)
+        }
+    }
+}
+
+/// Opens the database file to edit it by hand, the changes are reloaded afterwards
+fn edit_database(
+    main_tx: &Sender<InputWorkerEvent>,
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    state: &mut State,
+) -> Result<(), Error> {
+    suspend_input_thread(main_tx);
+    let result = edit_file(state.db_file_path().as_path());
+    resume_input_thread(main_tx);
+
+    // The editor might have left its content on the screen
+    terminal.clear()?;
+
+    match result {
+        Ok(()) => reload_changed_commands(state),
+        Err(e) => state.set_error(format!("Could not edit database file. {}", e)),
+    }
+
+    Ok(())
+}
+
 /// Handles input which is specific to [MenuItem::Find]
 fn handle_find(
+    main_tx: &Sender<InputWorkerEvent>,
     event: CEvent,
     terminal: &mut Terminal<CrosstermBackend<Stdout>>,
     state: &mut State,
 ) -> Result<InputEvent, Error> {
-    let fuzz_result_count = state.fuzz_result_or_all().len();
+    // NOTE: only visible results can be selected (see `max_results` config)
+    let fuzz_result_count = state.visible_fuzz_result().len();
 
     match event {
         CEvent::Key(key_event) => {
@@ -173,31 +542,93 @@ fn handle_find(
                 // List handling //
                 ///////////////////
                 KeyEvent {
+                    code: KeyCode::Up,
+                    modifiers: KeyModifiers::CONTROL,
+                } if state.input().is_empty() && state.can_move_commands() => {
+                    state.move_selected_command_up();
+                }
+
+                KeyEvent {
+                    code: KeyCode::Down,
+                    modifiers: KeyModifiers::CONTROL,
+                } if state.input().is_empty() && state.can_move_commands() => {
+                    state.move_selected_command_down();
+                }
+
+                KeyEvent {
+                    code: KeyCode::Up,
+                    modifiers: KeyModifiers::CONTROL,
+                }
+                | KeyEvent {
                     code: KeyCode::Down,
+                    modifiers: KeyModifiers::CONTROL,
+                } if state.input().is_empty() => {
+                    state.set_flash("Commands can only be moved in the manual order of all commands without sort_db".to_string());
+                }
+
+                // Ctrl+Shift+G can't be distinguished from Ctrl+G by most terminals, that is why
+                // Home/End are bound as well
+                KeyEvent {
+                    code: KeyCode::Char('g'),
+                    modifiers: KeyModifiers::CONTROL,
+                }
+                | KeyEvent {
+                    code: KeyCode::Home,
                     ..
-                } => {
+                } if fuzz_result_count > 0 => {
+                    state.select_command(0);
+                    state.remember_selection();
+                }
+
+                KeyEvent {
+                    code: KeyCode::Char('G'),
+                    modifiers,
+                } if modifiers.contains(KeyModifiers::CONTROL) && fuzz_result_count > 0 => {
+                    state.select_command(fuzz_result_count - 1);
+                    state.remember_selection();
+                }
+
+                KeyEvent {
+                    code: KeyCode::End, ..
+                } if fuzz_result_count > 0 => {
+                    state.select_command(fuzz_result_count - 1);
+                    state.remember_selection();
+                }
+
+                KeyEvent {
+                    code: KeyCode::Down,
+                    ..
+                } if fuzz_result_count > 0 => {
                     if let Some(selected) = state.command_list_state().selected() {
                         let selected_index = if selected >= fuzz_result_count - 1 {
-                            0
+                            if state.wrap_navigation() {
+                                0
+                            } else {
+                                selected
+                            }
                         } else {
                             selected + 1
                         };
 
                         state.select_command(selected_index);
+                        state.remember_selection();
                     }
                 }
 
                 KeyEvent {
                     code: KeyCode::Up, ..
-                } => {
+                } if fuzz_result_count > 0 => {
                     if let Some(selected) = state.command_list_state().selected() {
                         let selected_index = if selected > 0 {
                             selected - 1
-                        } else {
+                        } else if state.wrap_navigation() {
                             fuzz_result_count - 1
+                        } else {
+                            selected
                         };
 
                         state.select_command(selected_index);
+                        state.remember_selection();
                     }
                 }
 
@@ -207,23 +638,44 @@ fn handle_find(
                 KeyEvent {
                     code: KeyCode::Enter,
                     modifiers: KeyModifiers::NONE,
+                } => return copy_selected_command(terminal, state),
+
+                KeyEvent {
+                    code: KeyCode::Char('b'),
+                    modifiers: KeyModifiers::CONTROL,
+                } => return copy_selected_command_as_code_block(terminal, state),
+
+                KeyEvent {
+                    code: KeyCode::Char('y'),
+                    modifiers: KeyModifiers::CONTROL,
+                } => state.copy_selected_command_with_description(),
+
+                KeyEvent {
+                    code: KeyCode::Tab,
+                    modifiers: KeyModifiers::NONE,
                 } => {
-                    if let Some(c) = state.selected_crow_command() {
-                        let mut ctx = ClipboardContext::new().unwrap_or_else(|e| {
-                            eject(&format!("Could not create clipboard context. {}", e))
-                        });
-                        ctx.set_contents(c.command.clone()).unwrap_or_else(|e| {
-                            eject(&format!("Could not add command to clipboard. {}", e))
-                        });
-
-                        return quit(
-                            terminal,
-                            Some(&format!(
-                                "\nCommand:\n  {}\ncopied to clipboard!\n",
-                                c.command.clone().cyan()
-                            )),
-                        );
-                    }
+                    state.push_search_term();
+                    state.select_command(0);
+                }
+
+                KeyEvent {
+                    code: KeyCode::BackTab,
+                    ..
+                } => {
+                    state.pop_search_term();
+                    state.select_command(0);
+                }
+
+                KeyEvent {
+                    code: KeyCode::Char('o'),
+                    modifiers: KeyModifiers::CONTROL,
+                } => edit_database(main_tx, terminal, state)?,
+
+                KeyEvent {
+                    code: KeyCode::Char(':'),
+                    ..
+                } if state.input().is_empty() => {
+                    state.open_palette();
                 }
 
                 KeyEvent {
@@ -231,40 +683,45 @@ fn handle_find(
                     modifiers: KeyModifiers::NONE,
                 } => {
                     state.mut_input().push(c);
-                    state.set_fuzz_result(fuzzy_search_commands(
-                        state
-                            .crow_commands()
-                            .commands()
-                            .denormalize()
-                            .cloned()
-                            .collect(),
-                        state.input(),
-                    ));
-
-                    // We always want to select the first list element, when a new fuzzy search is being
-                    // triggered
-                    state.select_command(0);
+
+                    // Usually the first list element is selected, when a new fuzzy search is being
+                    // triggered (see [State::search_and_select])
+                    state.search_and_select();
+                }
+
+                KeyEvent {
+                    code: KeyCode::Char('s'),
+                    modifiers: KeyModifiers::CONTROL,
+                } => state.toggle_show_scores(),
+
+                KeyEvent {
+                    code: KeyCode::Char('t'),
+                    modifiers: KeyModifiers::CONTROL,
+                } => {
+                    state.toggle_tagged_only();
+                }
+
+                KeyEvent {
+                    code: KeyCode::Char('l'),
+                    modifiers: KeyModifiers::CONTROL,
+                } => {
+                    state.toggle_inline_descriptions();
                 }
 
+                KeyEvent {
+                    code: KeyCode::Char('u'),
+                    modifiers: KeyModifiers::CONTROL,
+                } => state.clear_input(),
+
                 KeyEvent {
                     code: KeyCode::Backspace,
                     modifiers: KeyModifiers::NONE,
                 } => {
                     state.mut_input().pop();
 
-                    state.set_fuzz_result(fuzzy_search_commands(
-                        state
-                            .crow_commands()
-                            .commands()
-                            .denormalize()
-                            .cloned()
-                            .collect(),
-                        state.input(),
-                    ));
-
-                    // We always want to select the first list element, when a new fuzzy search is being
-                    // triggered
-                    state.select_command(0);
+                    // Usually the first list element is selected, when a new fuzzy search is being
+                    // triggered (see [State::search_and_select])
+                    state.search_and_select();
                 }
 
                 _ => {}
@@ -321,6 +778,9 @@ fn handle_general(
     state: &mut State,
 ) -> Result<InputEvent, Error> {
     if let CEvent::Key(key_event) = event {
+        let escape_pressed = state.escape_pressed();
+        state.set_escape_pressed(false);
+
         match key_event {
             ///////////////////
             // Menu handling //
@@ -329,14 +789,28 @@ fn handle_general(
                 code: KeyCode::Char('q'),
                 modifiers: KeyModifiers::CONTROL,
             } => {
-                return quit(terminal, None);
+                quit(terminal, None)?;
+                return Ok(InputEvent::Cancel);
+            }
+
+            // Pressing ESC once closes any open popup, pressing it twice quits without copying
+            KeyEvent {
+                code: KeyCode::Esc, ..
+            } => {
+                if escape_pressed {
+                    quit(terminal, None)?;
+                    return Ok(InputEvent::Cancel);
+                }
+
+                state.set_escape_pressed(true);
+                state.return_to_find();
             }
 
             KeyEvent {
                 code: KeyCode::Char('f'),
                 modifiers: KeyModifiers::CONTROL,
             } => {
-                state.set_active_menu_item(MenuItem::Find);
+                state.return_to_find();
             }
 
             KeyEvent {
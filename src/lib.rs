@@ -2,21 +2,44 @@
 
 //! This library provides the [run] and [eject] functions which are used by the crow binary crate
 
+mod clipboard;
+mod color;
 mod command_scores;
 mod commands;
+mod config;
 mod crow_commands;
 mod crow_db;
+mod date;
+mod editor;
 mod events;
+mod filter;
 mod fuzzy;
+mod highlight;
 mod history;
+mod ids;
 mod input;
+mod palette;
 mod rendering;
+mod sanitize;
 mod state;
 
+/// Internals which are only exposed for the benchmarks in `benches/` and are not part of a stable
+/// API.
+#[doc(hidden)]
+pub mod bench {
+    pub use crate::command_scores::CommandScore;
+    pub use crate::crow_commands::CrowCommand;
+    pub use crate::fuzzy::{fuzzy_search_commands, SearchWeights};
+}
+
+use config::{BrowseSort, Config};
 use crossterm::{event::DisableMouseCapture, execute, terminal::disable_raw_mode};
-use std::io::Error;
+use state::MenuItem;
+use std::{env, io::Error};
 
-use clap::{crate_authors, crate_description, crate_name, crate_version, App, Arg, SubCommand};
+use clap::{
+    crate_authors, crate_description, crate_name, crate_version, App, AppSettings, Arg, SubCommand,
+};
 
 fn initialize_arg_parser() -> App<'static, 'static> {
     let db_path_arg = Arg::with_name("db_path")
@@ -31,17 +54,106 @@ fn initialize_arg_parser() -> App<'static, 'static> {
         .long("file")
         .takes_value(true);
 
+    let query_arg = Arg::with_name("query")
+        .help("fuzzy query to filter commands by")
+        .index(1);
+
+    let limit_arg = Arg::with_name("limit")
+        .help("Print at most this number of commands")
+        .long("limit")
+        .takes_value(true);
+
+    let count_arg = Arg::with_name("count")
+        .help("Only print the number of matching commands.\nExits with a non-zero code if nothing matched")
+        .long("count");
+
+    let filter_arg = Arg::with_name("filter")
+        .help("Only include commands matching all given filters, e.g. 'has:description keyword:git'.\nAvailable filters: has:description, has:keywords, has:tags, keyword:<keyword>, tag:<tag>, id:<id>, used:>5, used:<5, used:5, !<term>")
+        .long("filter")
+        .takes_value(true)
+        .multiple(true)
+        .number_of_values(1);
+
     App::new(crate_name!())
         .version(crate_version!())
         .author(crate_authors!("\n"))
         .about(crate_description!())
+        .arg(
+            Arg::with_name("verbose")
+                .help("Print diagnostic output (e.g. resolved file paths) to stderr.\nThe log level can also be set with the RUST_LOG environment variable")
+                .short("v")
+                .long("verbose")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("no_color")
+                .help("Print output without colors (also disabled by the NO_COLOR environment variable)")
+                .long("no-color")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("config")
+                .help("Path to the config file.\nDefaults to '$CROW_CONFIG', '$XDG_CONFIG_HOME/crow/config.toml' or '~/.config/crow/config.toml'")
+                .long("config")
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("first")
+                .help("Copy the best matching command for the given query without starting the interactive mode.\nExits with a non-zero code if nothing matched")
+                .long("first")
+                .value_name("query")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("print")
+                .help("Print the command of '--first' instead of copying it")
+                .long("print")
+                .requires("first"),
+        )
+        .arg(&db_path_arg)
+        .arg(&db_file_arg)
         .subcommand(
             SubCommand::with_name("search")
                 .about("Search through saved commands.\nThis subcommand can be omitted if only default arguments are used, because it is crow default behavior when run without a subcommand.")
                 .version("0.1.0")
                 .author(crate_authors!("\n"))
+                .arg(&query_arg)
+                .arg(
+                    Arg::with_name("print")
+                        .help("Print the matching commands (ranked like in the interactive mode) instead of starting the interactive mode")
+                        .long("print"),
+                )
+                .arg(limit_arg.clone().requires("print"))
+                .arg(count_arg.clone().requires("print"))
+                .arg(
+                    Arg::with_name("sort")
+                        .help("Order of the commands without a query and of equally ranked matches (overrides the 'browse_sort' config).\nTakes the values of 'browse_sort': 'manual', 'recent' (most recently used first), 'alpha' or 'usage'/'frequency' (most used first)")
+                        .long("sort")
+                        .takes_value(true)
+                        .possible_values(BrowseSort::VALUES),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .help("Output format of '--print'.\n'plain' (default) prints the command text, 'json' one json object per command and line")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["plain", "json"])
+                        .requires("print"),
+                )
                 .arg(&db_path_arg)
-                .arg(&db_file_arg),
+                .arg(&db_file_arg)
+                .arg(&filter_arg),
+        )
+        .subcommand(
+            SubCommand::with_name("edit-search")
+                .about("Start the interactive mode in edit mode on the best match of the query, e.g. to quickly fix a typo in a saved command")
+                .version("0.1.0")
+                .author(crate_authors!("\n"))
+                .arg(query_arg.clone().required(true))
+                .arg(&db_path_arg)
+                .arg(&db_file_arg)
+                .arg(&filter_arg),
         )
         .subcommand(
             SubCommand::with_name("add")
@@ -52,7 +164,47 @@ fn initialize_arg_parser() -> App<'static, 'static> {
                     Arg::with_name("command")
                         .help("command to add")
                         .index(1)
-                        .required(true),
+                        .required_unless("from_clipboard"),
+                )
+                .arg(
+                    Arg::with_name("from_clipboard")
+                        .help("Add the current clipboard contents as command")
+                        .long("from-clipboard")
+                        .conflicts_with("command"),
+                )
+                .arg(
+                    Arg::with_name("yes")
+                        .help("Save the command without asking for confirmation")
+                        .short("y")
+                        .long("yes"),
+                )
+                .arg(
+                    Arg::with_name("description")
+                        .help("Description of the command.\nSkips the description and keywords prompts")
+                        .short("d")
+                        .long("description")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("source")
+                        .help("Where the command has been found (e.g. a URL).\nSkips the source prompt")
+                        .long("source")
+                        .takes_value(true)
+                        .value_name("URL"),
+                )
+                .arg(
+                    Arg::with_name("tag")
+                        .help("Tag of the command, can be given multiple times")
+                        .long("tag")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .value_name("TAG"),
+                )
+                .arg(
+                    Arg::with_name("capture_cwd")
+                        .help("Save the current working directory along with the command")
+                        .long("capture-cwd"),
                 )
                 .arg(&db_path_arg)
                 .arg(&db_file_arg),
@@ -62,9 +214,201 @@ fn initialize_arg_parser() -> App<'static, 'static> {
                 .about("add last used CLI command to crow")
                 .version("0.1.0")
                 .author(crate_authors!("\n"))
+                .arg(
+                    Arg::with_name("history_file")
+                        .help("Read the history from this file instead of the history file of the shell in $SHELL")
+                        .long("history-file")
+                        .takes_value(true)
+                        .value_name("PATH"),
+                )
+                .arg(
+                    Arg::with_name("plain")
+                        .help("Read the history file as plain commands, one per line, without removing timestamps\n(e.g. for shells which are not supported like nushell or tcsh)")
+                        .long("plain")
+                        .requires("history_file"),
+                )
+                .arg(&db_path_arg)
+                .arg(&db_file_arg),
+        )
+        .subcommand(
+            SubCommand::with_name("list")
+                .about("Print saved commands (optionally filtered by a fuzzy query) without starting the interactive mode")
+                .version("0.1.0")
+                .author(crate_authors!("\n"))
+                .arg(&query_arg)
+                .arg(&limit_arg)
+                .arg(&count_arg)
+                .arg(
+                    Arg::with_name("since")
+                        .help("Only print commands which have been saved at or after this date (YYYY-MM-DD or RFC 3339)")
+                        .long("since")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("before")
+                        .help("Only print commands which have been saved before this date (YYYY-MM-DD or RFC 3339)")
+                        .long("before")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("include_undated")
+                        .help("Also print commands without a saving date (saved by older versions of crow) when using --since or --before")
+                        .long("include-undated"),
+                )
+                .arg(&db_path_arg)
+                .arg(&db_file_arg)
+                .arg(&filter_arg),
+        )
+        .subcommand(
+            SubCommand::with_name("pick")
+                .about("Pick a command with an alternative frontend instead of the interactive mode and copy it")
+                .version("0.1.0")
+                .author(crate_authors!("\n"))
+                .arg(&query_arg)
+                .arg(
+                    Arg::with_name("fzf")
+                        .help("Pick the command with fzf (which has to be installed)")
+                        .long("fzf")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("print")
+                        .help("Print the picked command instead of copying it")
+                        .long("print"),
+                )
+                .arg(&db_path_arg)
+                .arg(&db_file_arg)
+                .arg(&filter_arg),
+        )
+        .subcommand(
+            SubCommand::with_name("stale")
+                .about("Print commands which have not been copied from the fuzzy mode within the last days, e.g. to clean them up")
+                .version("0.1.0")
+                .author(crate_authors!("\n"))
+                .arg(
+                    Arg::with_name("days")
+                        .help("Number of days without usage after which a command is stale")
+                        .long("days")
+                        .takes_value(true)
+                        .default_value("30"),
+                )
+                .arg(&db_path_arg)
+                .arg(&db_file_arg),
+        )
+        .subcommand(
+            SubCommand::with_name("tag")
+                .about("Add tags to (or remove tags from) the command with the given id")
+                .version("0.1.0")
+                .author(crate_authors!("\n"))
+                .arg(
+                    Arg::with_name("id")
+                        .help("id of the command")
+                        .index(1)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("tags")
+                        .help("tags to add or remove")
+                        .index(2)
+                        .multiple(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("remove")
+                        .help("Remove the given tags instead of adding them")
+                        .long("remove"),
+                )
                 .arg(&db_path_arg)
                 .arg(&db_file_arg),
         )
+        .subcommand(
+            SubCommand::with_name("export")
+                .about("Print all saved commands, e.g. to share them")
+                .version("0.1.0")
+                .author(crate_authors!("\n"))
+                .arg(
+                    Arg::with_name("format")
+                        .help("Output format.\n'markdown' creates a cheat sheet grouped by tags")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["json", "markdown"])
+                        .default_value("json"),
+                )
+                .arg(&db_path_arg)
+                .arg(&db_file_arg),
+        )
+        .subcommand(
+            SubCommand::with_name("import")
+                .about("Import commands from a file created by 'crow export' or from another crow database file")
+                .version("0.1.0")
+                .author(crate_authors!("\n"))
+                .arg(
+                    Arg::with_name("file")
+                        .help("file to import")
+                        .index(1)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("merge_strategy")
+                        .help("How to handle imported commands with the same id or command text as an existing command.\nskip: keep the existing command\noverwrite: replace the existing command\nkeep-both: keep both commands (with a new id for the imported command if necessary)")
+                        .long("merge-strategy")
+                        .takes_value(true)
+                        .possible_values(&["skip", "overwrite", "keep-both"])
+                        .default_value("skip"),
+                )
+                .arg(&db_path_arg)
+                .arg(&db_file_arg),
+        )
+        .subcommand(
+            SubCommand::with_name("clear")
+                .about("Remove all saved commands. The database file is backed up first (e.g. to 'crow_db.json.bak')")
+                .version("0.1.0")
+                .author(crate_authors!("\n"))
+                .arg(
+                    Arg::with_name("yes")
+                        .help("Remove all commands without asking for confirmation")
+                        .short("y")
+                        .long("yes"),
+                )
+                .arg(&db_path_arg)
+                .arg(&db_file_arg),
+        )
+        .subcommand(
+            SubCommand::with_name("repair")
+                .about("Give commands with an empty or duplicate id a new id. The database file is backed up first")
+                .version("0.1.0")
+                .author(crate_authors!("\n"))
+                .arg(&db_path_arg)
+                .arg(&db_file_arg),
+        )
+        .subcommand(
+            SubCommand::with_name("doctor")
+                .about("Print diagnostic information, e.g. which config and database files are used")
+                .version("0.1.0")
+                .author(crate_authors!("\n"))
+                .arg(&db_path_arg)
+                .arg(&db_file_arg),
+        )
+        .subcommand(
+            SubCommand::with_name("which")
+                .about("Print which database and config file are used and whether they exist")
+                .version("0.1.0")
+                .author(crate_authors!("\n"))
+                .arg(&db_path_arg)
+                .arg(&db_file_arg),
+        )
+        .subcommand(
+            SubCommand::with_name("self-test-history")
+                .about("Developer command which verifies history parsing against the bundled history fixtures")
+                .setting(AppSettings::Hidden)
+                .arg(
+                    Arg::with_name("fixtures")
+                        .help("Directory of the history fixtures")
+                        .long("fixtures")
+                        .takes_value(true)
+                        .default_value("./testdata"),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("add:pick")
                 .about("NOTE: THIS COMMAND IS NOT YET IMPLEMENTED!\nAllows the user to add a command by picking from the last history commands")
@@ -73,26 +417,132 @@ fn initialize_arg_parser() -> App<'static, 'static> {
         )
 }
 
+/// Initializes logging to stderr. Normal runs only log warnings and errors, `--verbose` also logs
+/// debug output. `RUST_LOG` takes precedence over both.
+fn initialize_logger(verbose: bool) {
+    let default_level = if verbose { "debug" } else { "warn" };
+
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(default_level))
+        .format_timestamp(None)
+        .init();
+}
+
+/// Exit codes of crow which scripts can rely on
+pub mod exit_code {
+    use std::{
+        fmt,
+        io::{Error, ErrorKind},
+    };
+
+    /// The command succeeded
+    pub const SUCCESS: i32 = 0;
+    /// The command failed
+    pub const ERROR: i32 = 1;
+    /// There is no command with the given id
+    pub const NOT_FOUND: i32 = 2;
+    /// No command has been selected, either because crow was quit without copying a command
+    /// or because nothing matched the query (e.g. `--first` or `list --count`)
+    pub const NO_SELECTION: i32 = 3;
+
+    /// Returns the exit code for an error which has been returned by [crate::run].
+    /// Only errors created by [unknown_id] exit with [NOT_FOUND], other errors of the kind
+    /// [ErrorKind::NotFound] (e.g. a missing file) are generic errors.
+    pub fn from_error(error: &Error) -> i32 {
+        match error.get_ref() {
+            Some(e) if e.is::<UnknownId>() => NOT_FOUND,
+            _ => ERROR,
+        }
+    }
+
+    /// Creates the error for a command id which does not exist (see [NOT_FOUND])
+    pub fn unknown_id(id: &str) -> Error {
+        Error::new(ErrorKind::NotFound, UnknownId(id.to_string()))
+    }
+
+    /// Marks an error as caused by an unknown command id
+    #[derive(Debug)]
+    struct UnknownId(String);
+
+    impl fmt::Display for UnknownId {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "No command with id '{}'", self.0)
+        }
+    }
+
+    impl std::error::Error for UnknownId {}
+}
+
 /// Starts crow, parses command line arguments and runs the chosen command.
+///
+/// # Exit codes
+/// Errors returned by this function should exit with the code of [exit_code::from_error] (e.g.
+/// [exit_code::NOT_FOUND] for an unknown command id), some commands exit on their own with
+/// [exit_code::NO_SELECTION] if nothing was selected or matched. See [exit_code] for all codes.
 pub fn run() -> Result<(), Error> {
     let arg_parser = initialize_arg_parser();
     let matches = arg_parser.get_matches();
 
+    initialize_logger(matches.is_present("verbose"));
+
+    // Global arguments are only available on the matches of the (sub)command they were given to
+    let config_arg = matches
+        .value_of("config")
+        .or_else(|| matches.subcommand().1.and_then(|m| m.value_of("config")));
+    let mut config = match matches.subcommand_name() {
+        // `doctor` and `which` report a missing or invalid config file instead of failing
+        Some("doctor") | Some("which") => Config::path(config_arg)
+            .and_then(|path| Config::try_from_file(&path).ok())
+            .unwrap_or_default(),
+        _ => Config::load(config_arg),
+    };
+
+    let no_color = matches.is_present("no_color")
+        || matches!(matches.subcommand().1, Some(m) if m.is_present("no_color"))
+        || matches!(env::var_os("NO_COLOR"), Some(value) if !value.is_empty());
+    if no_color {
+        config.color = false;
+    }
+
+    if matches.is_present("first") {
+        return commands::first::run(&matches, config);
+    }
+
     match matches.subcommand() {
-        ("add", Some(sub_matches)) => commands::add::run(sub_matches),
-        ("add:last", Some(sub_matches)) => commands::add_last::run(sub_matches),
+        ("add", Some(sub_matches)) => commands::add::run(sub_matches, config),
+        ("add:last", Some(sub_matches)) => commands::add_last::run(sub_matches, config),
+        ("list", Some(sub_matches)) => commands::list::run(sub_matches, config),
+        ("pick", Some(sub_matches)) => commands::pick::run(sub_matches, config),
+        ("stale", Some(sub_matches)) => commands::stale::run(sub_matches, config),
+        ("tag", Some(sub_matches)) => commands::tag::run(sub_matches, config),
+        ("export", Some(sub_matches)) => commands::export::run(sub_matches, config),
+        ("import", Some(sub_matches)) => commands::import::run(sub_matches, config),
+        ("self-test-history", Some(sub_matches)) => {
+            commands::self_test_history::run(sub_matches, config)
+        }
+        ("clear", Some(sub_matches)) => commands::clear::run(sub_matches, config),
+        ("repair", Some(sub_matches)) => commands::repair::run(sub_matches, config),
+        ("doctor", Some(sub_matches)) => commands::doctor::run(sub_matches, config_arg, config),
+        ("which", Some(sub_matches)) => commands::which::run(sub_matches, config_arg, config),
         ("add:pick", Some(_sub_matches)) => {
             // TODO
             println!("Sorry, this command is not yet implemented!");
             Ok(())
         }
-        ("search", sub_matches) => commands::default::run(sub_matches),
-        (_, sub_matches) => commands::default::run(sub_matches),
+        ("search", Some(sub_matches)) if sub_matches.is_present("print") => {
+            commands::list::run(sub_matches, config)
+        }
+        ("edit-search", sub_matches) => commands::default::run(sub_matches, config, MenuItem::Edit),
+        ("search", sub_matches) => commands::default::run(sub_matches, config, MenuItem::Find),
+        (_, sub_matches) => {
+            commands::default::run(sub_matches.or(Some(&matches)), config, MenuItem::Find)
+        }
     }
 }
 
 /// Disables the terminals raw mode, prints a message to stderr and exits the currently running
 /// program.
+/// This is meant for fatal errors only, recoverable errors inside the TUI should be shown with
+/// `State::set_error` instead.
 pub fn eject(reason: &str) -> ! {
     disable_raw_mode().unwrap();
     execute!(std::io::stdout(), DisableMouseCapture).unwrap();
@@ -100,3 +550,76 @@ pub fn eject(reason: &str) -> ! {
     eprintln!("{}", reason);
     std::process::exit(-1);
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Error, ErrorKind};
+
+    use crate::{config::Config, crow_db::FilePath};
+
+    use super::{exit_code, initialize_arg_parser};
+
+    #[test]
+    fn exits_with_not_found_only_for_unknown_ids() {
+        assert_eq!(
+            exit_code::from_error(&exit_code::unknown_id("abc")),
+            exit_code::NOT_FOUND
+        );
+        assert_eq!(
+            exit_code::unknown_id("abc").to_string(),
+            "No command with id 'abc'"
+        );
+
+        // e.g. a missing import file
+        let missing_file = Error::new(ErrorKind::NotFound, "No such file or directory");
+        assert_eq!(exit_code::from_error(&missing_file), exit_code::ERROR);
+    }
+
+    #[test]
+    fn uses_database_file_arguments_in_interactive_mode() {
+        let matches = initialize_arg_parser().get_matches_from(vec![
+            "crow",
+            "search",
+            "--path",
+            "./testdata",
+            "--file",
+            "crow.json",
+            "docker",
+        ]);
+        let (name, sub_matches) = matches.subcommand();
+
+        assert_eq!(name, "search");
+        assert_eq!(
+            &*FilePath::from_args(sub_matches, &Config::default()),
+            "./testdata/crow.json"
+        );
+
+        // Without a subcommand the arguments of crow itself are used
+        let matches = initialize_arg_parser().get_matches_from(vec![
+            "crow",
+            "--path",
+            "./testdata",
+            "--file",
+            "crow.json",
+        ]);
+
+        assert_eq!(
+            &*FilePath::from_args(Some(&matches), &Config::default()),
+            "./testdata/crow.json"
+        );
+    }
+
+    #[test]
+    fn accepts_count_only_when_printing_search_results() {
+        let matches = initialize_arg_parser()
+            .get_matches_from(vec!["crow", "search", "docker", "--print", "--count"]);
+        let (name, sub_matches) = matches.subcommand();
+
+        assert_eq!(name, "search");
+        assert!(sub_matches.unwrap().is_present("count"));
+
+        assert!(initialize_arg_parser()
+            .get_matches_from_safe(vec!["crow", "search", "docker", "--count"])
+            .is_err());
+    }
+}
@@ -10,6 +10,6 @@ fn main() {
     if let Err(e) = crow::run() {
         println!("Application error: {}", e);
 
-        process::exit(1);
+        process::exit(crow::exit_code::from_error(&e));
     };
 }
@@ -0,0 +1,135 @@
+//! The command palette, a fuzzy searchable list of all actions which can be run on the selected
+//! command (opened with `:` inside the fuzzy mode).
+
+use crate::{
+    crow_commands::CrowCommand,
+    fuzzy::{fuzzy_search_commands, SearchWeights},
+};
+
+/// An action of the command palette
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Action {
+    Copy,
+    CopyAsCodeBlock,
+    CopyWithDescription,
+    CopyId,
+    EditCommand,
+    EditDescription,
+    EditKeywords,
+    EditTags,
+    Duplicate,
+    EditDatabase,
+    Delete,
+    ClearSearch,
+    ToggleScores,
+    ToggleTaggedOnly,
+    ToggleInlineDescriptions,
+    Quit,
+}
+
+/// All actions in the order in which they are listed without a query
+pub const ACTIONS: &[Action] = &[
+    Action::Copy,
+    Action::CopyAsCodeBlock,
+    Action::CopyWithDescription,
+    Action::CopyId,
+    Action::EditCommand,
+    Action::EditDescription,
+    Action::EditKeywords,
+    Action::EditTags,
+    Action::Duplicate,
+    Action::EditDatabase,
+    Action::Delete,
+    Action::ClearSearch,
+    Action::ToggleScores,
+    Action::ToggleTaggedOnly,
+    Action::ToggleInlineDescriptions,
+    Action::Quit,
+];
+
+impl Action {
+    /// The name which is shown inside the palette
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Copy => "Copy",
+            Self::CopyAsCodeBlock => "Copy as code block",
+            Self::CopyWithDescription => "Copy with description",
+            Self::CopyId => "Copy id",
+            Self::EditCommand => "Edit command",
+            Self::EditDescription => "Edit description",
+            Self::EditKeywords => "Edit keywords",
+            Self::EditTags => "Edit tags",
+            Self::Duplicate => "Duplicate",
+            Self::EditDatabase => "Edit database file",
+            Self::Delete => "Delete",
+            Self::ClearSearch => "Clear search",
+            Self::ToggleScores => "Toggle scores",
+            Self::ToggleTaggedOnly => "Tagged only",
+            Self::ToggleInlineDescriptions => "Toggle inline descriptions",
+            Self::Quit => "Quit",
+        }
+    }
+
+    /// A short explanation which can be searched as well
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Copy => "copy the selected command to the clipboard and quit",
+            Self::CopyAsCodeBlock => "copy the selected command as markdown code block and quit",
+            Self::CopyWithDescription => {
+                "copy the selected command below its description as comment for a script"
+            }
+            Self::CopyId => "copy the id of the selected command and quit",
+            Self::EditCommand => "change the selected command in your editor",
+            Self::EditDescription => "change the description in your editor",
+            Self::EditKeywords => "change the search keywords in your editor",
+            Self::EditTags => "add or remove tags",
+            Self::Duplicate => "copy the selected command as new command and edit it",
+            Self::EditDatabase => "open the json file of all commands in your editor",
+            Self::Delete => "remove the selected command",
+            Self::ClearSearch => "empty the search input",
+            Self::ToggleScores => "show or hide the search score of every command",
+            Self::ToggleTaggedOnly => "restrict the search to commands with a tag or undo it",
+            Self::ToggleInlineDescriptions => "show or hide descriptions inside the command list",
+            Self::Quit => "quit without copying",
+        }
+    }
+}
+
+/// Returns the actions which match the given query, best matches first
+pub fn search_actions(query: &str) -> Vec<Action> {
+    // The actions are searched like commands, the index inside [ACTIONS] serves as id
+    let descriptors = ACTIONS
+        .iter()
+        .enumerate()
+        .map(|(index, action)| CrowCommand {
+            id: index.to_string(),
+            command: action.label().to_string(),
+            description: action.description().to_string(),
+            ..Default::default()
+        })
+        .collect();
+
+    fuzzy_search_commands(descriptors, query, &SearchWeights::default())
+        .iter()
+        .filter_map(|score| score.command_id().parse::<usize>().ok())
+        .map(|index| ACTIONS[index])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{search_actions, Action, ACTIONS};
+
+    #[test]
+    fn lists_all_actions_without_query() {
+        assert_eq!(search_actions(""), ACTIONS.to_vec());
+    }
+
+    #[test]
+    fn searches_actions_by_label_and_description() {
+        assert_eq!(search_actions("copy id").first(), Some(&Action::CopyId));
+        assert_eq!(search_actions("tags").first(), Some(&Action::EditTags));
+        assert!(search_actions("markdown").contains(&Action::CopyAsCodeBlock));
+        assert_eq!(search_actions("xyz"), vec![]);
+    }
+}
@@ -1,5 +1,6 @@
-use std::io::Stdout;
+use std::{collections::HashMap, io::Stdout};
 
+use serde::Deserialize;
 use tui::backend::CrosstermBackend;
 use tui::text::Text;
 use tui::widgets::{Clear, Widget, Wrap};
@@ -14,10 +15,69 @@ use tui::{
     widgets::{Block, Borders, List, ListItem},
 };
 use tui::{text::Span, widgets::Tabs};
-use unicode_width::UnicodeWidthStr;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 use crate::crow_commands::CrowCommand;
-use crate::state::MenuItem;
+use crate::highlight::{token_kinds, TokenKind};
+use crate::palette::Action;
+use crate::sanitize::{expand_tabs, printable, TabExpander};
+use crate::state::{EditField, MenuItem, PendingEdit};
+
+/// The border type of all blocks
+#[derive(Deserialize, Debug, Default, Copy, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BorderStyle {
+    /// Single lines with square corners (default)
+    #[default]
+    Plain,
+    /// Single lines with rounded corners
+    Rounded,
+    /// Double lines
+    Double,
+    /// Thick single lines
+    Thick,
+}
+
+impl From<BorderStyle> for BorderType {
+    fn from(style: BorderStyle) -> Self {
+        match style {
+            BorderStyle::Plain => BorderType::Plain,
+            BorderStyle::Rounded => BorderType::Rounded,
+            BorderStyle::Double => BorderType::Double,
+            BorderStyle::Thick => BorderType::Thick,
+        }
+    }
+}
+
+/// The configured appearance of the bordered blocks around all widgets
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BlockStyle {
+    pub border_style: BorderStyle,
+    pub show_titles: bool,
+}
+
+impl Default for BlockStyle {
+    fn default() -> Self {
+        Self {
+            border_style: BorderStyle::default(),
+            show_titles: true,
+        }
+    }
+}
+
+impl BlockStyle {
+    /// Creates a bordered block with the given title, which is omitted if titles are disabled
+    pub fn block<'a>(&self, title: Option<String>) -> Block<'a> {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(self.border_style.into());
+
+        match title {
+            Some(title) if self.show_titles => block.title(title),
+            _ => block,
+        }
+    }
+}
 
 // TODO most (but not all) of the Paragraphs which are annotated with 'static lifetime
 // should probably use a proper lifetime as their parameters aren't actually static.
@@ -32,6 +92,7 @@ pub fn layout(rect: Rect) -> Vec<Rect> {
                 Constraint::Length(3),
                 Constraint::Min(2),
                 Constraint::Length(3),
+                Constraint::Length(1),
             ]
             .as_ref(),
         )
@@ -80,27 +141,75 @@ pub fn popup(frame: &mut Frame<CrosstermBackend<Stdout>>, widget: impl Widget) {
 }
 
 /// Renders the deletion prompt for the currently selected command
-pub fn delete_command(selected_command: &CrowCommand) -> Paragraph {
+pub fn delete_command(selected_command: &CrowCommand, style: BlockStyle) -> Paragraph {
     Paragraph::new(Spans::from(vec![
         Span::styled("Do you really want to ", Style::default().fg(Color::White)),
         Span::styled("delete ", Style::default().fg(Color::Red)),
         Span::styled("command: ", Style::default().fg(Color::White)),
-        Span::styled(&selected_command.command, Style::default().fg(Color::Cyan)),
+        Span::styled(
+            printable(&selected_command.command),
+            Style::default().fg(Color::Cyan),
+        ),
         Span::styled("? (y/N)", Style::default().fg(Color::White)),
     ]))
     .style(Style::default().fg(Color::White))
     .alignment(Alignment::Center)
     .wrap(Wrap { trim: true })
-    .block(
-        Block::default()
-            .borders(Borders::ALL)
-            .style(Style::default().fg(Color::White))
-            .border_type(BorderType::Plain),
-    )
+    .block(style.block(None).style(Style::default().fg(Color::White)))
+}
+
+/// Renders the comparison of the original and the edited text of a command, which has to be
+/// confirmed before the edit is saved. If the edited text is empty, keeping the original is the
+/// default. A description which is longer than `max_description_len` can be truncated.
+pub fn confirm_edit(
+    edit: &PendingEdit,
+    max_description_len: Option<usize>,
+    style: BlockStyle,
+) -> Paragraph<'static> {
+    let mut text = Text::styled("Before:", Style::default().fg(Color::Red));
+    text.extend(Text::styled(
+        printable(edit.original.trim_end()),
+        Style::default().fg(Color::White),
+    ));
+    text.extend(Text::raw(""));
+    text.extend(Text::styled("After:", Style::default().fg(Color::Green)));
+    text.extend(Text::styled(
+        printable(edit.edited.trim_end()),
+        Style::default().fg(Color::White),
+    ));
+    text.extend(Text::raw(""));
+    if let (Some(_), Some(max_len)) = (edit.truncated(max_description_len), max_description_len) {
+        text.extend(Text::styled(
+            format!(
+                "The description is longer than {} characters. Press t to truncate it",
+                max_len
+            ),
+            Style::default().fg(Color::Red),
+        ));
+    }
+    text.extend(Text::styled(
+        if edit.is_empty() && edit.field == EditField::Command {
+            "The edited command is empty and can't be saved. Press ENTER to keep the original"
+                .to_string()
+        } else if edit.is_empty() {
+            format!(
+                "The edited {} is empty. Save it anyway? (y/N)",
+                edit.field.label()
+            )
+        } else {
+            format!("Save the changed {}? (Y/n)", edit.field.label())
+        },
+        Style::default().fg(Color::Yellow),
+    ));
+
+    Paragraph::new(text)
+        .style(Style::default().fg(Color::White))
+        .wrap(Wrap { trim: false })
+        .block(style.block(None).style(Style::default().fg(Color::White)))
 }
 
 /// Renders the edit prompt for the currently selected command
-pub fn edit_command() -> Paragraph<'static> {
+pub fn edit_command(style: BlockStyle) -> Paragraph<'static> {
     Paragraph::new(Spans::from(vec![
         Span::styled(
             "C",
@@ -117,20 +226,138 @@ pub fn edit_command() -> Paragraph<'static> {
                 .add_modifier(Modifier::UNDERLINED),
         ),
         Span::styled("escription", Style::default().fg(Color::White)),
+        Span::styled(" / ", Style::default().fg(Color::White)),
+        Span::styled(
+            "K",
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::UNDERLINED),
+        ),
+        Span::styled("eywords", Style::default().fg(Color::White)),
+        Span::styled(" / ", Style::default().fg(Color::White)),
+        Span::styled(
+            "T",
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::UNDERLINED),
+        ),
+        Span::styled("ags", Style::default().fg(Color::White)),
+        Span::styled(" / Duplicate (", Style::default().fg(Color::White)),
+        Span::styled(
+            "Y",
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::UNDERLINED),
+        ),
+        Span::styled(")", Style::default().fg(Color::White)),
     ]))
     .style(Style::default().fg(Color::White))
     .alignment(Alignment::Center)
     .wrap(Wrap { trim: true })
+    .block(style.block(None).style(Style::default().fg(Color::White)))
+}
+
+/// Renders the tag popup for the currently selected command, which lists its tags and the input
+/// of the tag to add or remove
+pub fn edit_tags<'a>(
+    selected_command: &CrowCommand,
+    tag_input: &'a str,
+    style: BlockStyle,
+) -> Paragraph<'a> {
+    let tags = if selected_command.tags.is_empty() {
+        Span::styled("no tags", Style::default().fg(Color::DarkGray))
+    } else {
+        Span::styled(
+            selected_command.tags.join(", "),
+            Style::default().fg(Color::Cyan),
+        )
+    };
+
+    Paragraph::new(vec![
+        Spans::from(vec![
+            Span::styled("Tags: ", Style::default().fg(Color::White)),
+            tags,
+        ]),
+        Spans::from(""),
+        Spans::from(vec![
+            Span::styled("> ", Style::default().fg(Color::Cyan)),
+            Span::styled(tag_input, Style::default().fg(Color::White)),
+        ]),
+    ])
+    .style(Style::default().fg(Color::White))
+    .alignment(Alignment::Center)
+    .wrap(Wrap { trim: true })
     .block(
-        Block::default()
-            .borders(Borders::ALL)
-            .style(Style::default().fg(Color::White))
-            .border_type(BorderType::Plain),
+        style
+            .block(Some(
+                "ENTER to add a tag or remove an existing one, ctrl+o to edit all tags in your editor, ESC to close".to_string(),
+            ))
+            .style(Style::default().fg(Color::White)),
+    )
+}
+
+/// Renders the popup of a recoverable error which needs to be acknowledged
+pub fn error(message: &str, style: BlockStyle) -> Paragraph<'static> {
+    Paragraph::new(vec![
+        Spans::from(Span::styled(
+            printable(message),
+            Style::default().fg(Color::White),
+        )),
+        Spans::from(""),
+        Spans::from(Span::styled(
+            "Press ENTER to continue",
+            Style::default().fg(Color::Yellow),
+        )),
+    ])
+    .alignment(Alignment::Center)
+    .wrap(Wrap { trim: true })
+    .block(
+        style
+            .block(Some("Error".to_string()))
+            .style(Style::default().fg(Color::Red)),
+    )
+}
+
+/// Renders the command palette with the actions which match the palette input
+pub fn palette<'a>(
+    actions: &[Action],
+    selection: usize,
+    palette_input: &'a str,
+    style: BlockStyle,
+) -> Paragraph<'a> {
+    let mut lines = vec![
+        Spans::from(vec![
+            Span::styled(": ", Style::default().fg(Color::Cyan)),
+            Span::styled(palette_input, Style::default().fg(Color::White)),
+        ]),
+        Spans::from(""),
+    ];
+
+    lines.extend(actions.iter().enumerate().map(|(index, action)| {
+        let (symbol, label_style) = if index == selection {
+            (">> ", Style::default().fg(Color::Yellow))
+        } else {
+            ("   ", Style::default().fg(Color::White))
+        };
+
+        Spans::from(vec![
+            Span::styled(format!("{}{}", symbol, action.label()), label_style),
+            Span::styled(
+                format!(" - {}", action.description()),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ])
+    }));
+
+    Paragraph::new(lines).alignment(Alignment::Left).block(
+        style
+            .block(Some("Actions (ENTER to run, ESC to close)".to_string()))
+            .style(Style::default().fg(Color::White)),
     )
 }
 
 /// Renders a list of keybindings to the top of the terminal output
-pub fn keybindings(active_menu_item: &MenuItem) -> Tabs<'static> {
+pub fn keybindings(active_menu_item: &MenuItem, style: BlockStyle) -> Tabs<'static> {
     // TODO find a way to better couple these with [MenutItem]
     // TODO add arrows for list navigation and <C-J>/<C-K> for scrolling
     let label_list = vec!["Find", "Edit", "Delete", "Quit"];
@@ -153,9 +380,10 @@ pub fn keybindings(active_menu_item: &MenuItem) -> Tabs<'static> {
     Tabs::new(labels)
         .select(active_menu_item.clone().into())
         .block(
-            Block::default()
-                .title("Keys (press CTRL+<KEY> or ENTER to copy command and quit)")
-                .borders(Borders::ALL),
+            style.block(Some(
+                "Keys (press CTRL+<KEY>, ENTER to copy command and quit or ESC twice to quit without copying)"
+                    .to_string(),
+            )),
         )
         .style(Style::default().fg(Color::LightYellow))
         .highlight_style(Style::default().fg(Color::Green))
@@ -163,92 +391,339 @@ pub fn keybindings(active_menu_item: &MenuItem) -> Tabs<'static> {
 }
 
 /// Renders a list of commands with teh currently selected item being highlighted.
+/// If some results are hidden (see `max_results` config) a last, non-selectable item hints at the
+/// number of hidden results. Commands with identical command text get a suffix to tell them
+/// apart (see [label_suffixes]). Tabs are shown as spaces up to the next tab stop.
+/// If `scores` are given, the fuzzy search score of every command is appended to its label.
+/// With `inline_descriptions` every list item has a second, dimmed line with the first line of the
+/// description (see [description_label]), so that the selection highlights both lines.
 /// For selection to work this needs to be rendered inside a stateful_widget
 /// NOTE: Selection input is handled inside [crate::input]
 /// NOTE: The stateful_widget binding happens in [crate::commands::default::render]
-pub fn command_list<'a>(commands: Vec<CrowCommand>, frame_size: Rect) -> List<'a> {
-    let list_items: Vec<ListItem> = commands
+pub fn command_list<'a>(
+    commands: Vec<CrowCommand>,
+    hidden_result_count: usize,
+    scores: Option<&[i64]>,
+    inline_descriptions: bool,
+    frame_size: Rect,
+    tab_width: usize,
+    style: BlockStyle,
+) -> List<'a> {
+    let width = usize::from(frame_size.width);
+    let mut list_items: Vec<ListItem> = commands
         .iter()
-        .map(|c| {
-            let command = c.command.clone();
-            let available_width = usize::from(frame_size.width);
-            let command_width = UnicodeWidthStr::width(command.as_str());
-
-            if available_width > command_width {
-                Text::from(command)
-            } else {
-                Text::from(format!(
-                    "{}...",
-                    command[..available_width - 10].to_string()
-                ))
+        .zip(label_suffixes(&commands))
+        .enumerate()
+        .map(|(index, (c, suffix))| {
+            let mut suffix = suffix.unwrap_or_default();
+            if let Some(score) = scores.and_then(|scores| scores.get(index)) {
+                suffix.push_str(&format!(" (score: {})", score));
             }
+
+            let available_width = width.saturating_sub(UnicodeWidthStr::width(suffix.as_str()));
+            let label = format!(
+                "{}{}",
+                list_label(&c.command, available_width, tab_width),
+                suffix
+            );
+
+            if !inline_descriptions {
+                return ListItem::new(Text::from(label));
+            }
+
+            ListItem::new(Text::from(vec![
+                Spans::from(label),
+                Spans::from(Span::styled(
+                    description_label(&c.description, width, tab_width),
+                    Style::default().fg(Color::DarkGray),
+                )),
+            ]))
         })
-        .map(ListItem::new)
         .collect();
 
+    if hidden_result_count > 0 {
+        list_items.push(ListItem::new(Text::styled(
+            format!("… {} more (refine search)", hidden_result_count),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+
     List::new(list_items)
-        .block(Block::default().title("Commands").borders(Borders::ALL))
+        .block(style.block(Some("Commands".to_string())))
         // .style(Style::default().fg(Color::White))
         .highlight_style(Style::default().add_modifier(Modifier::ITALIC))
         .highlight_symbol(">> ")
 }
 
+/// Returns a label suffix for every command which has the same command text as another of the
+/// given commands, so that identical looking list items can be told apart. The suffix is the
+/// first description line if it is unique among these commands, otherwise the shortest unique
+/// tail (at least 6 chars) of the id. Tails keep the differing end of ids like `status` and
+/// `status-2`, which only differ after the end of the shorter id.
+fn label_suffixes(commands: &[CrowCommand]) -> Vec<Option<String>> {
+    let mut duplicates: HashMap<&str, Vec<&CrowCommand>> = HashMap::new();
+    for command in commands {
+        duplicates
+            .entry(command.command.as_str())
+            .or_default()
+            .push(command);
+    }
+
+    let first_line = |c: &CrowCommand| {
+        c.description
+            .lines()
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_string()
+    };
+
+    commands
+        .iter()
+        .map(|command| {
+            let duplicates = &duplicates[command.command.as_str()];
+            if duplicates.len() < 2 {
+                return None;
+            }
+
+            let description = first_line(command);
+            let is_unique_description = !description.is_empty()
+                && duplicates
+                    .iter()
+                    .filter(|c| first_line(c) == description)
+                    .count()
+                    == 1;
+
+            if is_unique_description {
+                return Some(format!(" ({})", printable(&description)));
+            }
+
+            let id_tail = |c: &CrowCommand, length: usize| {
+                let skipped = c.id.chars().count().saturating_sub(length);
+                c.id.chars().skip(skipped).collect::<String>()
+            };
+            let id_length = command.id.chars().count();
+            let length = (6..id_length).find(|&length| {
+                duplicates
+                    .iter()
+                    .filter(|c| id_tail(c, length) == id_tail(command, length))
+                    .count()
+                    == 1
+            });
+
+            let id = match length {
+                Some(length) => format!("…{}", id_tail(command, length)),
+                None => command.id.clone(),
+            };
+            Some(format!(" [{}]", printable(&id)))
+        })
+        .collect()
+}
+
+/// Creates the single line label of a command inside the command list.
+/// Line breaks of multiline commands are shown as `↵`, control chars as visible symbols and
+/// commands which are too wide for the available width are truncated.
+fn list_label(command: &str, available_width: usize, tab_width: usize) -> String {
+    let label = printable(&expand_tabs(
+        &command.replace("\r\n", "\n").replace('\n', " ↵ "),
+        tab_width,
+    ));
+
+    if available_width > UnicodeWidthStr::width(label.as_str()) {
+        return label;
+    }
+
+    let max_width = available_width.saturating_sub(10);
+    let mut width = 0;
+    let truncated: String = label
+        .chars()
+        .take_while(|c| {
+            width += UnicodeWidthChar::width(*c).unwrap_or(0);
+            width <= max_width
+        })
+        .collect();
+
+    format!("{}...", truncated)
+}
+
+/// Creates the indented label of the first description line, which is shown below the command
+/// inside the command list. Like the command label it is truncated to the available width.
+fn description_label(description: &str, available_width: usize, tab_width: usize) -> String {
+    let first_line = description.lines().next().unwrap_or("").trim();
+
+    format!(
+        "  {}",
+        list_label(first_line, available_width.saturating_sub(2), tab_width)
+    )
+}
+
 /// Handles the display of the command details (command + description) for the currently
 /// selected command. Character matches of the fuzzy search are being highlighted.
+/// Multiline commands are rendered left-aligned line by line, all other commands are centered.
+/// With `syntax_highlight` the command is colored according to its shell tokens (see
+/// [crate::highlight]), fuzzy matches are still highlighted on top of that.
+/// With `line_numbers` every line of multiline commands is prefixed with its (dimmed) number.
 pub fn command_detail<'a>(
     selected_command: &CrowCommand,
     scroll_position: u16,
     highlight_indices: &[usize],
+    syntax_highlight: bool,
+    line_numbers: bool,
+    tab_width: usize,
+    style: BlockStyle,
 ) -> Paragraph<'a> {
-    let mut detail = Text::from(Spans::from(
-        selected_command
-            .command
-            .char_indices()
-            .map(|(index, char)| {
-                if highlight_indices.contains(&index) {
-                    Span::styled(char.to_string(), Style::default().fg(Color::Yellow))
+    let is_multiline = selected_command.command.contains('\n');
+    let token_kinds = if syntax_highlight {
+        token_kinds(&selected_command.command)
+    } else {
+        vec![]
+    };
+
+    let mut command_lines = vec![vec![]];
+    let mut tab_expander = TabExpander::new(tab_width);
+
+    for (char_position, (index, char)) in selected_command.command.char_indices().enumerate() {
+        match char {
+            '\n' => {
+                command_lines.push(vec![]);
+                tab_expander = TabExpander::new(tab_width);
+            }
+            '\r' => {}
+            _ => {
+                let color = if highlight_indices.contains(&index) {
+                    Color::Yellow
                 } else {
-                    Span::styled(char.to_string(), Style::default().fg(Color::Cyan))
+                    token_kinds
+                        .get(char_position)
+                        .map_or(Color::Cyan, TokenKind::color)
+                };
+
+                let text = printable(&tab_expander.expand(char));
+
+                if let Some(line) = command_lines.last_mut() {
+                    line.push(Span::styled(text, Style::default().fg(color)));
                 }
-            })
-            .collect::<Vec<Span>>(),
-    ));
+            }
+        }
+    }
+
+    // Line numbers are only shown for multiline commands (e.g. scripts)
+    if line_numbers && is_multiline {
+        let width = command_lines.len().to_string().len();
+
+        for (number, line) in command_lines.iter_mut().enumerate() {
+            line.insert(
+                0,
+                Span::styled(
+                    format!("{:>width$} ", number + 1, width = width),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            );
+        }
+    }
+
+    let mut detail = Text::from(
+        command_lines
+            .into_iter()
+            .map(Spans::from)
+            .collect::<Vec<Spans>>(),
+    );
 
     detail.extend(Text::raw("\n"));
 
+    let mut tab_expander = TabExpander::new(tab_width);
     detail.extend(Text::from(Spans::from(
         selected_command
             .description
             .char_indices()
             .map(|(index, char)| {
+                let text = printable(&tab_expander.expand(char));
+
                 // Because our fuzzy search combines command + description we have to take the
                 // length of the command into account when we check if the current chars index is
                 // part of the matching indices. We also need to add two more characters because of
                 // the "\n" newline above!
                 if highlight_indices.contains(&{ index + selected_command.command.len() + 2 }) {
-                    Span::styled(char.to_string(), Style::default().fg(Color::Yellow))
+                    Span::styled(text, Style::default().fg(Color::Yellow))
                 } else {
-                    Span::styled(char.to_string(), Style::default().fg(Color::White))
+                    Span::styled(text, Style::default().fg(Color::White))
                 }
             })
             .collect::<Vec<Span>>(),
     )));
 
+    if !selected_command.tags.is_empty() {
+        detail.extend(Text::raw("\n"));
+        detail.extend(Text::styled(
+            format!("Tags: {}", printable(&selected_command.tags.join(", "))),
+            Style::default().fg(Color::Cyan),
+        ));
+    }
+
+    if let Some(usage_hint) = selected_command
+        .usage_hint
+        .as_deref()
+        .map(str::trim)
+        .filter(|hint| !hint.is_empty())
+    {
+        detail.extend(Text::raw("\n"));
+        detail.extend(Text::styled(
+            "When to use",
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        ));
+        detail.extend(Text::styled(
+            printable(usage_hint),
+            Style::default().fg(Color::White),
+        ));
+    }
+
+    if let Some(cwd) = selected_command.cwd.as_deref() {
+        detail.extend(Text::raw("\n"));
+        detail.extend(Text::styled(
+            "Working directory",
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        ));
+        detail.extend(Text::styled(
+            printable(cwd),
+            Style::default().fg(Color::White),
+        ));
+    }
+
+    if let Some(source) = selected_command
+        .source
+        .as_deref()
+        .map(str::trim)
+        .filter(|source| !source.is_empty())
+    {
+        detail.extend(Text::raw("\n"));
+        detail.extend(Text::styled(
+            format!("Source: {}", printable(source)),
+            Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::DIM),
+        ));
+    }
+
     Paragraph::new(detail)
         .style(Style::default().fg(Color::White))
-        .alignment(Alignment::Center)
-        .wrap(Wrap { trim: true })
+        .alignment(if is_multiline {
+            Alignment::Left
+        } else {
+            Alignment::Center
+        })
+        .wrap(Wrap {
+            trim: !is_multiline,
+        })
         .scroll((scroll_position, 0))
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .style(Style::default().fg(Color::White))
-                .border_type(BorderType::Plain),
-        )
+        .block(style.block(None).style(Style::default().fg(Color::White)))
 }
 
 /// Renders the empty command list hint
-pub fn empty_command_list() -> Paragraph<'static> {
+pub fn empty_command_list(style: BlockStyle) -> Paragraph<'static> {
     let mut text = Text::styled(
         "There are no saved commands!\nPlease quit and run one of the following crow commands first:\n\n",
         Style::default().fg(Color::White),
@@ -272,16 +747,36 @@ pub fn empty_command_list() -> Paragraph<'static> {
         .style(Style::default().fg(Color::White))
         .alignment(Alignment::Center)
         .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .style(Style::default().fg(Color::LightCyan))
-                .border_type(BorderType::Plain),
+            style
+                .block(None)
+                .style(Style::default().fg(Color::LightCyan)),
+        )
+}
+
+/// Renders a message inside the command list area when the search does not match any command,
+/// so that it can be distinguished from an empty database.
+pub fn no_matches(query: &str, style: BlockStyle) -> Paragraph<'static> {
+    let message = if query.trim().is_empty() {
+        "No commands match the given filters".to_string()
+    } else {
+        format!("No matches for '{}'", query)
+    };
+
+    Paragraph::new(Text::styled(message, Style::default().fg(Color::White)))
+        .alignment(Alignment::Center)
+        .block(
+            style
+                .block(Some("Commands".to_string()))
+                .style(Style::default().fg(Color::LightCyan)),
         )
 }
 
 /// Renders the input prompt which is used for fuzzy searching.
 /// The actual input handling is located in [crate::input].
-pub fn input(input: &str) -> Paragraph {
+/// Fixed search terms are shown inside the status bar (see [status_bar]).
+pub fn input<'a>(input: &'a str, style: BlockStyle) -> Paragraph<'a> {
+    let title = "TAB to fix the search term".to_string();
+
     Paragraph::new(Spans::from(vec![
         Span::styled("> ", Style::default().fg(Color::Cyan)),
         Span::styled(input, Style::default().fg(Color::White)),
@@ -289,9 +784,222 @@ pub fn input(input: &str) -> Paragraph {
     .style(Style::default().fg(Color::White))
     .alignment(Alignment::Left)
     .block(
-        Block::default()
-            .borders(Borders::ALL)
-            .style(Style::default().fg(Color::LightCyan))
-            .border_type(BorderType::Plain),
+        style
+            .block(Some(title))
+            .style(Style::default().fg(Color::LightCyan)),
     )
 }
+
+/// Renders the status bar below the input, which shows the flash message (if any) or otherwise
+/// the fixed search terms and whether the search is restricted to tagged commands
+pub fn status_bar(
+    flash: Option<&str>,
+    search_terms: &[String],
+    tagged_only: bool,
+) -> Paragraph<'static> {
+    let status = match flash {
+        Some(flash) => Span::styled(printable(flash), Style::default().fg(Color::Yellow)),
+        None => Span::styled(
+            search_status(search_terms, tagged_only),
+            Style::default().fg(Color::Cyan),
+        ),
+    };
+
+    Paragraph::new(status).alignment(Alignment::Left)
+}
+
+/// Describes the restrictions of the current search for the status bar
+fn search_status(search_terms: &[String], tagged_only: bool) -> String {
+    let mut status = vec![];
+
+    if !search_terms.is_empty() {
+        status.push(format!(
+            "Search terms: {} (SHIFT+TAB to remove the last term)",
+            printable(&search_terms.join(" & "))
+        ));
+    }
+    if tagged_only {
+        status.push("Searching only tagged commands (ctrl+t to search all)".to_string());
+    }
+
+    status.join(" | ")
+}
+
+#[cfg(test)]
+mod tests {
+    use tui::{backend::TestBackend, layout::Rect, widgets::Widget, Terminal};
+
+    use crate::crow_commands::CrowCommand;
+
+    use super::{
+        command_detail, command_list, description_label, label_suffixes, list_label, search_status,
+        BlockStyle,
+    };
+
+    /// Renders the widget into a terminal of the given size and returns the trimmed lines
+    fn rendered_lines<W: Widget>(widget: W, width: u16, height: u16) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal
+            .draw(|frame| frame.render_widget(widget, frame.size()))
+            .unwrap();
+
+        let buffer = terminal.backend().buffer();
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| buffer.get(x, y).symbol.as_str())
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn shows_scores_in_command_list_only_if_given() {
+        let commands = vec![
+            CrowCommand::test("1", "echo 'hi'"),
+            CrowCommand::test("2", "e c something o"),
+        ];
+        let style = BlockStyle {
+            show_titles: false,
+            ..Default::default()
+        };
+        let size = Rect::new(0, 0, 40, 4);
+
+        let lines = rendered_lines(
+            command_list(commands.clone(), 0, Some(&[91, 75]), false, size, 4, style),
+            40,
+            4,
+        );
+        assert!(lines[1].contains("echo 'hi' (score: 91)"));
+        assert!(lines[2].contains("e c something o (score: 75)"));
+
+        let lines = rendered_lines(
+            command_list(commands, 0, None, false, size, 4, style),
+            40,
+            4,
+        );
+        assert!(lines[1].contains("echo 'hi'"));
+        assert!(!lines.iter().any(|line| line.contains("score")));
+    }
+
+    #[test]
+    fn shows_usage_hint_in_command_detail() {
+        let command = CrowCommand {
+            usage_hint: Some("  after a big merge ".to_string()),
+            ..CrowCommand::test("1", "cargo clean").with_description("remove build artifacts")
+        };
+        let style = BlockStyle {
+            show_titles: false,
+            ..Default::default()
+        };
+
+        let lines = rendered_lines(
+            command_detail(&command, 0, &[], false, false, 4, style),
+            40,
+            10,
+        );
+
+        let hint_title = lines
+            .iter()
+            .position(|line| line.contains("When to use"))
+            .expect("Shows the usage hint title");
+        assert!(lines[hint_title + 1].contains("after a big merge"));
+        assert!(lines[..hint_title]
+            .iter()
+            .any(|line| line.contains("remove build artifacts")));
+
+        let command = CrowCommand {
+            usage_hint: Some(" ".to_string()),
+            ..command
+        };
+        let lines = rendered_lines(
+            command_detail(&command, 0, &[], false, false, 4, style),
+            40,
+            10,
+        );
+        assert!(!lines.iter().any(|line| line.contains("When to use")));
+    }
+
+    #[test]
+    fn shows_line_breaks_in_list_label() {
+        assert_eq!(list_label("ls", 20, 4), "ls");
+        assert_eq!(
+            list_label("cat <<EOF\nhello\nEOF", 40, 4),
+            "cat <<EOF ↵ hello ↵ EOF"
+        );
+        assert_eq!(
+            list_label("echo ↵ ünïcödé and a long tail", 20, 4),
+            "echo ↵ ünï..."
+        );
+        assert_eq!(list_label("echo hello", 5, 4), "...");
+    }
+
+    #[test]
+    fn expands_tabs_in_list_label() {
+        assert_eq!(list_label("printf 'a\tb'", 40, 4), "printf 'a   b'");
+        assert_eq!(list_label("a\tb", 40, 2), "a b");
+    }
+
+    #[test]
+    fn shows_first_description_line_in_description_label() {
+        assert_eq!(description_label("", 20, 4), "  ");
+        assert_eq!(
+            description_label(" show status\nmore details", 20, 4),
+            "  show status"
+        );
+        assert_eq!(
+            description_label("a rather long description", 20, 4),
+            "  a rather..."
+        );
+    }
+
+    #[test]
+    fn distinguishes_identical_commands() {
+        let commands = vec![
+            CrowCommand::test("show-repo-status", "git status"),
+            CrowCommand::test("show-repo-status-2", "git status"),
+            CrowCommand::test("3", "git status").with_description("short status\nmore details"),
+            CrowCommand::test("4", "ls"),
+        ];
+
+        assert_eq!(
+            label_suffixes(&commands),
+            vec![
+                Some(" […status]".to_string()),
+                Some(" […atus-2]".to_string()),
+                Some(" (short status)".to_string()),
+                None
+            ]
+        );
+
+        let commands = vec![
+            CrowCommand::test("1", "ls"),
+            CrowCommand::test("Xy3kQ9ab", "ls"),
+            CrowCommand::test("Zz3kQ9ab", "ls"),
+        ];
+
+        assert_eq!(
+            label_suffixes(&commands),
+            vec![
+                Some(" [1]".to_string()),
+                Some(" […y3kQ9ab]".to_string()),
+                Some(" […z3kQ9ab]".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn shows_search_terms_in_status_bar() {
+        assert_eq!(search_status(&[], false), "");
+        assert_eq!(
+            search_status(&["git".to_string(), "log".to_string()], false),
+            "Search terms: git & log (SHIFT+TAB to remove the last term)"
+        );
+        assert_eq!(
+            search_status(&["git".to_string()], true),
+            "Search terms: git (SHIFT+TAB to remove the last term) | Searching only tagged commands (ctrl+t to search all)"
+        );
+    }
+}
@@ -0,0 +1,135 @@
+//! Sanitizing of control characters like ANSI escape sequences (e.g. inside descriptions which
+//! were edited with `$EDITOR`), which would otherwise corrupt the rendering of the TUI.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use unicode_width::UnicodeWidthChar;
+
+/// Replaces a control char with a visible symbol (e.g. `ESC` with `␛`), so that it can't be
+/// interpreted by the terminal. Line breaks are kept and tabs are replaced with a space.
+pub fn printable_char(c: char) -> char {
+    match c {
+        '\n' => c,
+        '\t' | '\r' => ' ',
+        '\u{0}'..='\u{1f}' => char::from_u32(0x2400 + c as u32).unwrap_or('\u{fffd}'),
+        '\u{7f}' => '␡',
+        '\u{80}'..='\u{9f}' => '\u{fffd}',
+        _ => c,
+    }
+}
+
+/// Replaces all control chars of the given text with visible symbols (see [printable_char]).
+/// The number of chars is not changed, so that fuzzy highlight indices stay valid.
+pub fn printable(text: &str) -> String {
+    text.chars().map(printable_char).collect()
+}
+
+/// Returns the number of spaces a tab at the given column is displayed as, so that the following
+/// text starts at the next tab stop.
+fn tab_stop_distance(column: usize, tab_width: usize) -> usize {
+    let tab_width = tab_width.max(1);
+
+    tab_width - column % tab_width
+}
+
+/// Expands the tabs of a text char by char (see [expand_tabs]), e.g. to style every char of the
+/// text differently. The column is measured in display width, so that wide chars (e.g. `日`)
+/// move the following tab stops as far as they are displayed.
+pub struct TabExpander {
+    tab_width: usize,
+    column: usize,
+}
+
+impl TabExpander {
+    pub fn new(tab_width: usize) -> Self {
+        Self {
+            tab_width,
+            column: 0,
+        }
+    }
+
+    /// Returns the text the given char is displayed as, which is the char itself except for tabs
+    pub fn expand(&mut self, c: char) -> String {
+        match c {
+            '\t' => {
+                let distance = tab_stop_distance(self.column, self.tab_width);
+                self.column += distance;
+                " ".repeat(distance)
+            }
+            '\n' => {
+                self.column = 0;
+                c.to_string()
+            }
+            _ => {
+                self.column += printable_char(c).width().unwrap_or(0);
+                c.to_string()
+            }
+        }
+    }
+}
+
+/// Replaces tabs with spaces up to the next tab stop (see [TabExpander]), because terminals
+/// render tabs inconsistently. This is only meant for displaying text, copied commands keep their
+/// tabs.
+pub fn expand_tabs(text: &str, tab_width: usize) -> String {
+    let mut expander = TabExpander::new(tab_width);
+
+    text.chars().map(|c| expander.expand(c)).collect()
+}
+
+/// Removes ANSI escape sequences and all other control chars except line breaks and tabs from
+/// the given text.
+pub fn strip_control_sequences(text: &str) -> String {
+    // Compiled only once, because every command is sanitized on each render
+    static ESCAPE_SEQUENCES: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"\x1b(\[[0-?]*[ -/]*[@-~]|\][^\x07\x1b]*(\x07|\x1b\\)?|[@-_])")
+            .expect("Valid regex")
+    });
+
+    ESCAPE_SEQUENCES
+        .replace_all(text, "")
+        .chars()
+        .filter(|c| matches!(c, '\n' | '\t') || !c.is_control())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expand_tabs, printable, strip_control_sequences};
+
+    #[test]
+    fn replaces_control_chars_with_visible_symbols() {
+        let description = "\u{1b}[31mred\u{1b}[0m\nnext line\u{7}";
+
+        assert_eq!(printable(description), "␛[31mred␛[0m\nnext line␇");
+        assert_eq!(
+            printable(description).chars().count(),
+            description.chars().count()
+        );
+        assert_eq!(printable("ls -la"), "ls -la");
+    }
+
+    #[test]
+    fn expands_tabs_to_next_tab_stop() {
+        assert_eq!(expand_tabs("a\tbc\td", 4), "a   bc  d");
+        assert_eq!(expand_tabs("\tx\n\ty", 2), "  x\n  y");
+        assert_eq!(expand_tabs("abcd\te", 4), "abcd    e");
+        assert_eq!(expand_tabs("a\tb", 0), "a b");
+
+        // Wide chars take two columns and combining chars none
+        assert_eq!(expand_tabs("日\tx", 4), "日  x");
+        assert_eq!(expand_tabs("e\u{301}\tx", 4), "e\u{301}   x");
+    }
+
+    #[test]
+    fn strips_escape_sequences() {
+        assert_eq!(
+            strip_control_sequences("\u{1b}[1;31mls\u{1b}[0m -la\u{8}"),
+            "ls -la"
+        );
+        assert_eq!(
+            strip_control_sequences("\u{1b}]0;title\u{7}echo\t'hi'\n"),
+            "echo\t'hi'\n"
+        );
+    }
+}
@@ -1,17 +1,55 @@
 use crate::{
+    clipboard::{apply_copy_template, strip_comments, Clipboard, CopyFormat},
     command_scores::{CommandScore, CommandScores},
-    crow_commands::{Commands, CrowCommand, CrowCommands, Id},
-    crow_db::{CrowDBConnection, FilePath},
+    config::{BrowseSort, Config},
+    crow_commands::{
+        parse_keywords, truncate_description, unix_timestamp, Commands, CrowCommand, CrowCommands,
+        Id,
+    },
+    crow_db::{CommandStore, CrowDBConnection, FilePath, FileStore},
+    filter::{filter_commands, split_query, Field, Predicate},
     fuzzy::{fuzzy_search_commands, FuzzResult},
+    rendering::BlockStyle,
+    sanitize::strip_control_sequences,
+};
+use log::warn;
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    fs,
+    time::{Duration, Instant, SystemTime},
 };
-use std::fmt::Debug;
 
 use tui::widgets::ListState;
 
+/// Maximum number of queries of which the selected command is remembered
+const REMEMBERED_SELECTIONS: usize = 50;
+
+/// How long a flash message is shown inside the status bar
+const FLASH_DURATION: Duration = Duration::from_secs(3);
+
+/// How long the selection has to stay the same before it is copied with `copy_on_select`, so that
+/// scrolling through the list does not copy every command on the way
+const COPY_ON_SELECT_DELAY: Duration = Duration::from_millis(300);
+
 #[derive(Debug, Default)]
 pub struct State {
     db_file_path: FilePath,
 
+    /// Storage of the crow_db file, which is the file at [State::db_file_path] except in tests
+    db_store: Box<dyn CommandStore>,
+
+    /// Version of the crow_db file when it has last been read or written by crow, which is used
+    /// to detect changes by other programs (see [State::reload_if_changed])
+    db_version: Option<DbFileVersion>,
+
+    /// Version of a changed crow_db file which has been reported as invalid, so that it is only
+    /// reported again once it has been changed again
+    invalid_db_version: Option<DbFileVersion>,
+
+    /// User configuration
+    config: Config,
+
     /// User input which is used for fuzzy searching
     input: String,
 
@@ -23,15 +61,82 @@ pub struct State {
     /// List of filtered commands
     fuzz_result: FuzzResult,
 
+    /// Filter predicates (e.g. from `--filter`) which are applied to all commands before
+    /// fuzzy searching
+    filters: Vec<Predicate>,
+
+    /// Previous search inputs which have been fixed by the user. Commands have to match all of
+    /// them in addition to the current input.
+    search_terms: Vec<String>,
+
     /// The currently selected command
     selected_command_id: Option<Id>,
 
+    /// The last selected command per query, most recently used first (see
+    /// [Config::remember_selection])
+    remembered_selections: VecDeque<(String, Id)>,
+
     /// The currently selected menu item which determines in what mode
     /// crow is in
     active_menu_item: MenuItem,
 
+    /// User input of the tag popup (see [MenuItem::Tags])
+    tag_input: String,
+
+    /// User input of the command palette (see [MenuItem::Palette])
+    palette_input: String,
+
+    /// Index of the selected action inside the command palette
+    palette_selection: usize,
+
     /// The vertical scroll position of the detail view for commands
     detail_scroll_position: u16,
+
+    /// Whether the last key press was ESC, so that pressing ESC twice quits crow
+    escape_pressed: bool,
+
+    /// Message of a recoverable error (e.g. a failed edit) which is shown in a popup until it is
+    /// acknowledged by the user
+    error: Option<String>,
+
+    /// Short feedback message (e.g. after duplicating a command) which is shown inside the status
+    /// bar until it expires, together with the time it has been set
+    flash: Option<(String, Instant)>,
+
+    /// Change of the selected command in the editor which has to be confirmed before it is saved
+    /// (see [MenuItem::ConfirmEdit])
+    pending_edit: Option<PendingEdit>,
+
+    /// Whether the fuzzy search score of every visible command is shown inside the command list,
+    /// which helps to understand the order of the results
+    show_scores: bool,
+
+    /// Whether only commands with at least one tag are searched (see [State::toggle_tagged_only])
+    tagged_only: bool,
+
+    /// Whether descriptions are shown inside the command list, initially
+    /// [Config::inline_descriptions]
+    inline_descriptions: bool,
+
+    /// Order of the commands while the input is empty, which also breaks ties between equally
+    /// scored search results. Initially [Config::browse_sort], but can be overridden (e.g. by
+    /// `crow search --sort`).
+    browse_sort: BrowseSort,
+
+    /// Clipboard which selected commands are copied to
+    clipboard: Box<dyn Clipboard>,
+
+    /// Time of the last selection change which has not been copied yet (see
+    /// [State::copy_settled_selection])
+    selection_changed_at: Option<Instant>,
+}
+
+/// Modification time and length of the crow_db file. The length detects changes which happen
+/// within the timestamp resolution of the file system, e.g. two writes within the same second.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct DbFileVersion {
+    modified_at: SystemTime,
+    len: u64,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -39,6 +144,13 @@ pub enum MenuItem {
     Find,
     Edit,
     Delete,
+    /// Popup to add and remove tags of the selected command, which is opened from [MenuItem::Edit]
+    Tags,
+    /// Fuzzy searchable list of all actions (see [crate::palette])
+    Palette,
+    /// Popup which compares the original and the edited text after editing a command in the
+    /// editor, so that the change can be confirmed or rejected (see [State::pending_edit])
+    ConfirmEdit,
     // NOTE: Quit is only a shortcut not an actual menu item
 }
 
@@ -46,13 +158,62 @@ pub enum MenuItem {
 impl From<MenuItem> for usize {
     fn from(input: MenuItem) -> usize {
         match input {
-            MenuItem::Find => 0,
-            MenuItem::Edit => 1,
+            MenuItem::Find | MenuItem::Palette => 0,
+            MenuItem::Edit | MenuItem::Tags | MenuItem::ConfirmEdit => 1,
             MenuItem::Delete => 2,
         }
     }
 }
 
+/// Field of a command which can be changed in the editor
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum EditField {
+    Command,
+    Description,
+    Keywords,
+    Tags,
+}
+
+impl EditField {
+    /// Name of the field which is shown to the user
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Command => "command",
+            Self::Description => "description",
+            Self::Keywords => "keywords",
+            Self::Tags => "tags",
+        }
+    }
+}
+
+/// A change of a command which has been made in the editor, but has not been saved yet
+#[derive(Clone, Debug, PartialEq)]
+pub struct PendingEdit {
+    pub command_id: Id,
+    pub field: EditField,
+    /// The text before editing (keywords and tags are comma separated)
+    pub original: String,
+    /// The text after editing
+    pub edited: String,
+}
+
+impl PendingEdit {
+    /// Whether the edited text is empty (e.g. because the editor buffer has been cleared by
+    /// accident), in which case the original is kept by default
+    pub fn is_empty(&self) -> bool {
+        self.edited.trim().is_empty()
+    }
+
+    /// Returns the truncated description if an edited description is longer than `max_len`
+    /// characters (see [truncate_description])
+    pub fn truncated(&self, max_len: Option<usize>) -> Option<String> {
+        match (self.field, max_len) {
+            (EditField::Description, Some(max_len)) => truncate_description(&self.edited, max_len),
+            _ => None,
+        }
+    }
+}
+
 impl Default for MenuItem {
     fn default() -> MenuItem {
         MenuItem::Find
@@ -62,37 +223,136 @@ impl Default for MenuItem {
 impl State {
     /// Initializes the default state by filling most of the state with default
     /// values, but also reading and normalizing all commands from the crow_db file.
-    pub fn new(db_file_path: Option<FilePath>) -> Self {
+    pub fn new(db_file_path: FilePath) -> Self {
         let mut state: State = Self::default();
 
-        if let Some(path) = db_file_path {
-            state.set_db_file_path(path);
-        }
-
-        // Retrieve commands from db
-        let commands = CrowDBConnection::new(state.db_file_path.clone())
-            .commands()
-            .to_vec();
+        // Reads the commands and selects the first one
+        state.reload(db_file_path);
 
-        // Initialize command_ids on state
         state
-            .crow_commands
-            .set_command_ids(commands.iter().map(|c| c.id.clone()).collect());
+    }
 
-        // Initialize commands on state
-        state
-            .crow_commands_mut()
+    /// Reads and normalizes all commands from the crow_db file
+    fn read_commands_from_db(&mut self) {
+        let commands = self.connection().commands().to_vec();
+
+        self.crow_commands
+            .set_command_ids(commands.iter().map(|c| c.id.clone()).collect());
+        self.crow_commands
             .set_commands(Commands::normalize(&commands));
 
-        // Select first command
-        state.select_command(0);
+        self.db_version = self.db_file_version();
+    }
 
-        state
+    /// Returns the current version of the crow_db file
+    fn db_file_version(&self) -> Option<DbFileVersion> {
+        let metadata = fs::metadata(self.db_file_path.as_path()).ok()?;
+
+        Some(DbFileVersion {
+            modified_at: metadata.modified().ok()?,
+            len: metadata.len(),
+        })
+    }
+
+    /// Reads all commands again if the crow_db file has been changed by another program (e.g.
+    /// `crow add` in another terminal or an editor) since crow has read or written it, so that
+    /// the next write does not overwrite these changes. Changes are always written right away,
+    /// which is why there is nothing to lose by reloading.
+    /// The search is re-run and the selected command stays selected if it still matches.
+    /// Returns whether the commands have been reloaded or an error if the changed file is
+    /// invalid, in which case the current commands are kept. The invalid file is not overwritten
+    /// (see [State::write_commands_to_db]) until it has been fixed.
+    pub fn reload_if_changed(&mut self) -> Result<bool, String> {
+        let version = self.db_file_version();
+        if version == self.db_version || version == self.invalid_db_version {
+            return Ok(false);
+        }
+
+        // An invalid file is only reported once and not until it is changed again
+        if let Err(e) = CrowDBConnection::validate(&self.db_file_path, self.db_store.as_ref()) {
+            self.invalid_db_version = version;
+            return Err(e);
+        }
+        self.invalid_db_version = None;
+
+        let selected_id = self.selected_command_id.clone();
+
+        self.read_commands_from_db();
+        self.search();
+        self.reselect_command(selected_id);
+
+        Ok(true)
+    }
+
+    /// Switches to another crow_db file (e.g. of another profile) and reads its commands.
+    /// Everything which belongs to the previous commands is reset: the input, fixed search terms,
+    /// remembered selections, the fuzzy search result and the selection.
+    /// Filters (e.g. from `--filter`) still apply to the new commands.
+    pub fn reload(&mut self, db_file_path: FilePath) {
+        let store = Box::new(FileStore::new(db_file_path.clone()));
+
+        self.reload_from_store(db_file_path, store);
     }
 
-    /// Writes the current command state to the crow_db file
-    pub fn write_commands_to_db(&self) {
-        CrowDBConnection::new(self.db_file_path.clone())
+    /// Like [State::reload], but reads the commands from the given store (e.g. a
+    /// [crate::crow_db::MemoryStore] in tests) instead of the file at the given path
+    pub fn reload_from_store(&mut self, db_file_path: FilePath, store: Box<dyn CommandStore>) {
+        self.db_file_path = db_file_path;
+        self.db_store = store;
+        self.read_commands_from_db();
+
+        self.input.clear();
+        self.search_terms.clear();
+        self.remembered_selections.clear();
+        self.detail_scroll_position = 0;
+        self.set_fuzz_result(vec![]);
+        self.select_command(0);
+    }
+
+    /// Writes the current command state to the crow_db file.
+    /// An empty command state is never written over a database file which still contains
+    /// commands, because that would most likely be caused by a bug and wipe all saved commands.
+    /// The write is skipped with a warning instead, unless [State::force_write_commands_to_db] is
+    /// used. Returns whether the file has been written.
+    /// A database file which has been changed by another program and is invalid now (e.g. a
+    /// broken edit by hand) is never overwritten, so that the changes are not lost.
+    pub fn write_commands_to_db(&mut self) -> bool {
+        let version = self.db_file_version();
+        if version.is_some()
+            && version != self.db_version
+            && CrowDBConnection::validate(&self.db_file_path, self.db_store.as_ref()).is_err()
+        {
+            warn!("Not overwriting the invalid file {}", self.db_file_path);
+            self.set_error(format!(
+                "Did not save the changes, because {} has been changed by another program and is not a valid database file. Fix the file to save changes again.",
+                self.db_file_path
+            ));
+            return false;
+        }
+
+        if self.crow_commands.commands().is_empty()
+            && CrowDBConnection::contains_commands(&self.db_file_path, self.db_store.as_ref())
+        {
+            warn!(
+                "Not overwriting {} with an empty list of commands",
+                self.db_file_path
+            );
+            self.set_error(format!(
+                "Did not save the changes, because all commands would have been removed from {}",
+                self.db_file_path
+            ));
+            return false;
+        }
+
+        self.force_write_commands_to_db();
+        true
+    }
+
+    /// Writes the current command state to the crow_db file, even if this removes all commands
+    /// from the file (see [State::write_commands_to_db])
+    pub fn force_write_commands_to_db(&mut self) {
+        self.connection()
+            .set_sort_order(self.config.sort_db)
             .set_commands(
                 self.crow_commands()
                     .commands()
@@ -101,6 +361,8 @@ impl State {
                     .collect(),
             )
             .write();
+
+        self.db_version = self.db_file_version();
     }
 
     /// Gets the current fuzzy_search user input value
@@ -133,8 +395,37 @@ impl State {
         self.active_menu_item = item;
     }
 
+    /// Activates [MenuItem::Find] and re-runs the search for the preserved input, so that the
+    /// command list reflects changes which were made in other modes (e.g. an edited command).
+    /// The selected command stays selected if it still matches the input.
+    pub fn return_to_find(&mut self) {
+        let selected_id = self.selected_command_id.clone();
+
+        // Leaving the confirmation popup (e.g. with ESC) keeps the original
+        self.pending_edit = None;
+
+        self.set_active_menu_item(MenuItem::Find);
+        self.search();
+        self.reselect_command(selected_id);
+    }
+
+    /// Selects the command with the given id if it is part of the search result, otherwise the
+    /// first command
+    fn reselect_command(&mut self, id: Option<Id>) {
+        let index = id.and_then(|id| self.visible_index_of(&id));
+        self.select_command(index.unwrap_or(0));
+    }
+
     /// Set the state's fuzz result.
+    /// Only the ids of the scored commands are kept, the commands are always looked up in
+    /// [State::crow_commands] so that they are never shown outdated.
+    /// The selection is clamped to the new result, so that it always points at a list item.
     pub fn set_fuzz_result(&mut self, command_scores: Vec<CommandScore>) {
+        let command_scores: Vec<CommandScore> = command_scores
+            .into_iter()
+            .map(CommandScore::without_command)
+            .collect();
+
         self.fuzz_result = FuzzResult::new(
             CommandScores::normalize(&command_scores),
             command_scores
@@ -142,6 +433,32 @@ impl State {
                 .map(|c| c.command_id().clone())
                 .collect(),
         );
+
+        self.clamp_selection();
+    }
+
+    /// Moves the selection to the last list item if the list shrank below the selected index.
+    fn clamp_selection(&mut self) {
+        // NOTE: an empty fuzz result without input is replaced by the full list
+        // (see [State::fuzz_result_or_all])
+        let mut result_count = if self.fuzz_result.scores().is_empty() && self.input.is_empty() {
+            self.candidate_commands(&[]).len()
+        } else {
+            self.fuzz_result.scores().len()
+        };
+
+        if let Some(max_results) = self.config.max_results {
+            result_count = result_count.min(max_results.get());
+        }
+
+        match self.command_list_state.selected() {
+            Some(_) if result_count == 0 => {
+                self.command_list_state.select(None);
+                self.set_selected_command_id(None);
+            }
+            Some(selected) if selected >= result_count => self.select_command(result_count - 1),
+            _ => {}
+        }
     }
 
     /// Get a reference to the state's fuzz result.
@@ -149,35 +466,145 @@ impl State {
         if !self.fuzz_result().scores().is_empty() || !self.input.is_empty() {
             self.fuzz_result().scores().denormalize().cloned().collect()
         } else {
-            let fuzz_result = fuzzy_search_commands(
-                self.crow_commands()
-                    .commands()
-                    .denormalize()
-                    .cloned()
-                    .collect(),
-                "",
-            );
-            self.set_fuzz_result(fuzz_result.clone());
-            fuzz_result
+            let fuzz_result =
+                fuzzy_search_commands(self.candidate_commands(&[]), "", &self.config.weights);
+            self.set_fuzz_result(fuzz_result);
+            self.fuzz_result().scores().denormalize().cloned().collect()
+        }
+    }
+
+    /// Returns the part of the fuzz result which is actually rendered inside the command list,
+    /// which might be limited by the `max_results` config.
+    pub fn visible_fuzz_result(&mut self) -> Vec<CommandScore> {
+        let mut fuzz_result = self.fuzz_result_or_all();
+
+        if let Some(max_results) = self.config.max_results {
+            fuzz_result.truncate(max_results.get());
+        }
+
+        fuzz_result
+    }
+
+    /// Returns the number of results which are not rendered because of the `max_results` config.
+    pub fn hidden_result_count(&mut self) -> usize {
+        let total = self.fuzz_result_or_all().len();
+
+        total - self.visible_fuzz_result().len()
+    }
+
+    /// Runs the fuzzy search for the current input and updates the fuzz result accordingly.
+    /// Filter predicates inside the input (e.g. `has:description`) are applied before fuzzy
+    /// searching with the remaining input.
+    pub fn search(&mut self) {
+        let (predicates, pattern) = split_query(&self.input);
+
+        let fuzz_result = fuzzy_search_commands(
+            self.candidate_commands(&predicates),
+            &pattern,
+            &self.config.weights,
+        );
+        self.set_fuzz_result(fuzz_result);
+    }
+
+    /// Returns all commands which satisfy the state's filters and search terms as well as the
+    /// given predicates.
+    fn candidate_commands(&self, predicates: &[Predicate]) -> Vec<CrowCommand> {
+        let commands = filter_commands(
+            self.crow_commands()
+                .commands()
+                .denormalize()
+                .cloned()
+                .collect(),
+            &self.filters,
+        );
+
+        let mut commands = filter_commands(commands, predicates);
+        if self.tagged_only {
+            commands = filter_commands(commands, &[Predicate::Has(Field::Tags)]);
+        }
+
+        // Results of a search pattern are ranked by their score, the browse order only breaks ties
+        self.browse_sort.sort(&mut commands);
+
+        for term in &self.search_terms {
+            let (term_predicates, pattern) = split_query(term);
+            let term_commands = filter_commands(commands, &term_predicates);
+
+            let matching_ids: Vec<Id> =
+                fuzzy_search_commands(term_commands.clone(), &pattern, &self.config.weights)
+                    .iter()
+                    .map(|c| c.command_id().clone())
+                    .collect();
+
+            commands = term_commands
+                .into_iter()
+                .filter(|c| matching_ids.contains(&c.id))
+                .collect();
+        }
+
+        commands
+    }
+
+    /// Fixes the current input as search term and clears the input for the next term.
+    /// Empty inputs are ignored.
+    pub fn push_search_term(&mut self) {
+        if self.input.trim().is_empty() {
+            return;
+        }
+
+        let term = std::mem::take(&mut self.input);
+        self.search_terms.push(term.trim().to_string());
+        self.search();
+    }
+
+    /// Removes the last fixed search term
+    pub fn pop_search_term(&mut self) {
+        if self.search_terms.pop().is_some() {
+            self.search();
         }
     }
 
+    /// Get a reference to the state's fixed search terms.
+    pub fn search_terms(&self) -> &[String] {
+        &self.search_terms
+    }
+
+    /// Set the state's filter predicates.
+    pub fn set_filters(&mut self, filters: Vec<Predicate>) {
+        self.filters = filters;
+    }
+
     /// Set the state's selected command.
     pub fn set_selected_command_id(&mut self, id: Option<Id>) {
+        if self.config.copy_on_select && id.is_some() && id != self.selected_command_id {
+            self.selection_changed_at = Some(Instant::now());
+        }
+
         self.selected_command_id = id;
     }
 
     /// Get a reference to the state's selected crow command.
     pub fn selected_crow_command(&self) -> Option<&CrowCommand> {
         match &self.selected_command_id {
-            Some(id) => self.crow_commands.commands().get(id),
+            Some(id) => self.crow_commands.commands().find_by_id(id),
             None => None,
         }
     }
 
     /// Selects the command at a certain index inside the command_list_state and
     /// also retrieves the commands id from the fuzzy search result.
+    /// If there are no commands to select, the selection is cleared instead.
+    /// The index is clamped to the visible results (see [State::visible_fuzz_result]), so that a
+    /// hidden command can never be selected.
     pub fn select_command(&mut self, index: usize) {
+        let visible_count = self.visible_fuzz_result().len();
+        if visible_count == 0 {
+            self.command_list_state.select(None);
+            self.set_selected_command_id(None);
+            return;
+        }
+
+        let index = index.min(visible_count - 1);
         self.command_list_state.select(Some(index));
 
         // WHY:
@@ -193,204 +620,1667 @@ impl State {
         self.set_selected_command_id(selected_command_id);
     }
 
-    /// Set the state's input.
-    pub fn set_input(&mut self, input: String) {
-        self.input = input;
-    }
+    /// Remembers the selected command for the current input if [Config::remember_selection] is
+    /// enabled. Only the most recently used queries are kept.
+    pub fn remember_selection(&mut self) {
+        let id = match &self.selected_command_id {
+            Some(id) if self.config.remember_selection && !self.input.is_empty() => id.clone(),
+            _ => return,
+        };
 
-    /// Set the state's detail scroll position.
-    pub fn set_detail_scroll_position(&mut self, detail_scroll_position: u16) {
-        self.detail_scroll_position = detail_scroll_position;
+        let input = self.input.clone();
+        self.remembered_selections
+            .retain(|(query, _)| query != &input);
+        self.remembered_selections.push_front((input, id));
+        self.remembered_selections.truncate(REMEMBERED_SELECTIONS);
     }
 
-    /// Get a reference to the state's detail scroll position.
-    pub fn detail_scroll_position(&self) -> u16 {
-        self.detail_scroll_position
-    }
+    /// Runs the fuzzy search for the changed input (see [State::search]) and selects the command
+    /// which has been remembered for the input (see [State::remember_selection]) if it is part of
+    /// the result. Otherwise the previously selected command stays selected if
+    /// [Config::reset_selection_on_type] is disabled and it still matches, or the first command
+    /// is selected.
+    pub fn search_and_select(&mut self) {
+        let previous_id = self.selected_command_id.clone();
+
+        self.search();
+
+        let position = self
+            .remembered_selections
+            .iter()
+            .position(|(query, _)| query == &self.input);
+
+        let remembered_index = position.and_then(|position| {
+            let (_, id) = self.remembered_selections[position].clone();
+            self.visible_index_of(&id)
+        });
+
+        // Move the query to the front, so that it is the most recently used one
+        if let (Some(position), Some(_)) = (position, remembered_index) {
+            if let Some(entry) = self.remembered_selections.remove(position) {
+                self.remembered_selections.push_front(entry);
+            }
+        }
 
-    /// Checks if there are any commands at all inside the state
-    pub fn has_crow_commands(&self) -> bool {
-        !self.crow_commands.commands().is_empty()
-    }
+        let index = match (remembered_index, previous_id) {
+            (Some(index), _) => Some(index),
+            (None, Some(id)) if !self.config.reset_selection_on_type => self.visible_index_of(&id),
+            _ => None,
+        };
 
-    /// Get a reference to the state's fuzz result.
-    pub fn fuzz_result(&self) -> &FuzzResult {
-        &self.fuzz_result
+        self.select_command(index.unwrap_or(0));
     }
 
-    /// Get a reference to the state's crow commands.
-    pub fn crow_commands(&self) -> &CrowCommands {
-        &self.crow_commands
+    /// Returns the index of the command with the given id inside the rendered command list
+    fn visible_index_of(&mut self, id: &Id) -> Option<usize> {
+        self.visible_fuzz_result()
+            .iter()
+            .position(|c| c.command_id() == id)
     }
 
-    /// Get a mutable reference to the state's crow commands.
-    pub fn crow_commands_mut(&mut self) -> &mut CrowCommands {
-        &mut self.crow_commands
+    /// Whether the command list shows the persisted command order (see [BrowseSort::Manual]) of all
+    /// commands, which is required to move commands. Filters, fixed search terms and
+    /// [State::tagged_only] hide commands, so neighbours in the list would not be neighbours in
+    /// the persisted order. With [Config::sort_db] every write sorts the commands again, which
+    /// would undo the move.
+    pub fn can_move_commands(&self) -> bool {
+        self.browse_sort == BrowseSort::Manual
+            && self.config.sort_db.is_none()
+            && self.filters.is_empty()
+            && self.search_terms.is_empty()
+            && !self.tagged_only
     }
 
-    /// Get a reference to the state's db file path.
-    pub fn db_file_path(&self) -> &FilePath {
-        &self.db_file_path
+    /// Changes the order of the commands (see [State::browse_sort]) and searches again
+    pub fn set_browse_sort(&mut self, browse_sort: BrowseSort) {
+        self.browse_sort = browse_sort;
+        self.search();
     }
 
-    /// Set the state's db file path.
-    pub fn set_db_file_path(&mut self, db_file_path: FilePath) {
-        self.db_file_path = db_file_path;
+    /// Moves the currently selected command one position up inside the persisted command order.
+    pub fn move_selected_command_up(&mut self) {
+        if let Some(selected) = self.command_list_state.selected() {
+            if selected > 0 {
+                self.swap_selected_command_with(selected - 1);
+            }
+        }
     }
 
-    /// Get a reference to the state's selected command id.
-    pub fn _selected_command_id(&self) -> Option<&String> {
-        self.selected_command_id.as_ref()
+    /// Moves the currently selected command one position down inside the persisted command order.
+    pub fn move_selected_command_down(&mut self) {
+        if let Some(selected) = self.command_list_state.selected() {
+            if selected + 1 < self.visible_fuzz_result().len() {
+                self.swap_selected_command_with(selected + 1);
+            }
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::path::Path;
+    /// Swaps the selected command with the listed command at `index`, keeps the moved command
+    /// selected and writes the new order to the crow_db file.
+    /// The commands are swapped by id inside the persisted order, because list indices only
+    /// correspond to positions inside the persisted order if no command is hidden.
+    fn swap_selected_command_with(&mut self, index: usize) {
+        let selected_id = match self.selected_command_id.clone() {
+            Some(id) => id,
+            None => return,
+        };
+        let target_id = match self.visible_fuzz_result().get(index) {
+            Some(score) => score.command_id().clone(),
+            None => return,
+        };
 
-    use nanoid::nanoid;
+        let commands = self.crow_commands.commands();
+        if let (Some(selected), Some(target)) = (
+            commands.get_index_of(&selected_id),
+            commands.get_index_of(&target_id),
+        ) {
+            self.crow_commands.swap_commands(selected, target);
+
+            // Reset the fuzz result so that the list is recreated in the new order
+            self.set_fuzz_result(vec![]);
+            let index = self.visible_index_of(&selected_id).unwrap_or(index);
+            self.select_command(index);
+            self.write_commands_to_db();
+        }
+    }
 
-    use crate::{
-        command_scores::{CommandScore, CommandScores},
-        crow_commands::{Commands, CrowCommand, CrowCommands, Id},
-        crow_db::FilePath,
-    };
+    /// Copies the currently selected command in the given format to the clipboard and returns the
+    /// copied text. Comments and control chars are stripped from the copied command if
+    /// `strip_comments_on_copy` or `sanitize_copies` are enabled in the config.
+    /// [CopyFormat::Command] copies the configured `copy_template`.
+    /// Returns `Ok(None)` if no command is selected.
+    pub fn copy_selected_command(&mut self, format: CopyFormat) -> Result<Option<String>, String> {
+        let mut command = match self.selected_crow_command() {
+            Some(c) => c.clone(),
+            None => return Ok(None),
+        };
 
-    use super::State;
+        if self.config.strip_comments_on_copy {
+            command.command = strip_comments(&command.command);
+        }
+        if self.config.sanitize_copies {
+            command.command = strip_control_sequences(&command.command);
+            command.description = strip_control_sequences(&command.description);
+        }
 
-    #[test]
-    fn initializes_with_correct_data() {
-        let file_path = FilePath::new(Some("./testdata"), Some("crow.json"));
+        let text = match format {
+            CopyFormat::Command => apply_copy_template(&self.config.copy_template, &command),
+            _ => format.format(&command),
+        };
 
-        let state = State::new(Some(file_path));
+        self.clipboard.set_contents(text.clone())?;
 
-        assert_eq!(state.input(), "");
-        assert_eq!(&**state.db_file_path(), "./testdata/crow.json");
+        Ok(Some(text))
+    }
 
-        assert_eq!(state.detail_scroll_position(), 0);
-        assert_eq!(state.has_crow_commands(), true);
-        assert_eq!(state.command_list_state().selected().unwrap(), 0);
+    /// Copies the selected command with its description without quitting, e.g. to collect
+    /// several commands for a script. The copied text is shown as flash message.
+    pub fn copy_selected_command_with_description(&mut self) {
+        match self.copy_selected_command(CopyFormat::WithDescription) {
+            Ok(Some(text)) => {
+                self.mark_selected_command_used();
+                // The status bar has only a single line
+                self.set_flash(format!("Copied: {}", text.replace('\n', "␊")));
+            }
+            Ok(None) => {}
+            Err(e) => self.set_error(format!("Could not add command to clipboard. {}", e)),
+        }
     }
 
-    #[test]
-    fn writes_updated_state_to_db() {
-        let file_path = FilePath::new(Some("./testdata"), Some("crow_tmp.json"));
+    /// Copies the selected command if `copy_on_select` is enabled and the selection has not
+    /// changed for [COPY_ON_SELECT_DELAY] at the given time. This is checked on every tick.
+    /// Returns the copied text or `Ok(None)` if nothing has been copied.
+    pub fn copy_settled_selection(&mut self, now: Instant) -> Result<Option<String>, String> {
+        match self.selection_changed_at {
+            Some(changed_at) if now.duration_since(changed_at) >= COPY_ON_SELECT_DELAY => {
+                self.copy_pending_selection()
+            }
+            _ => Ok(None),
+        }
+    }
 
-        let mut state = State::new(Some(file_path.clone()));
+    /// Copies the selected command right away if it still has to be copied because of
+    /// `copy_on_select` (e.g. when crow is quit before [COPY_ON_SELECT_DELAY] has passed)
+    pub fn copy_pending_selection(&mut self) -> Result<Option<String>, String> {
+        if self.selection_changed_at.take().is_none() {
+            return Ok(None);
+        }
 
-        let crow_command = CrowCommand {
-            id: "test_command_1".to_string(),
-            command: "echo 'hi from db'".to_string(),
-            description: "This is a test command".to_string(),
-        };
-        let commands = [crow_command];
-        let command_ids: Vec<Id> = vec!["test_command_1".to_string()];
-        let crow_commands = CrowCommands::_new(Commands::normalize(&commands), command_ids);
-        *state.crow_commands_mut() = crow_commands.clone();
+        self.copy_selected_command(CopyFormat::Command)
+    }
 
-        // Assert that current state holds correct commands
-        assert_eq!(state.crow_commands(), &crow_commands);
+    /// Reads the clipboard back after copying if `verify_clipboard` is enabled in the config.
+    /// Returns a warning if the clipboard does not contain the copied text (anymore), e.g. because
+    /// a clipboard manager cleared or transformed it.
+    pub fn verify_clipboard(&mut self, copied: &str) -> Option<String> {
+        if !self.config.verify_clipboard {
+            return None;
+        }
 
-        state.write_commands_to_db();
+        match self.clipboard.get_contents() {
+            Ok(contents) if contents == copied => None,
+            Ok(_) => Some("Warning: the clipboard content changed after copying!".to_string()),
+            Err(e) => Some(format!(
+                "Warning: could not verify the clipboard content. {}",
+                e
+            )),
+        }
+    }
 
-        // Assert that new state which also accesses the file holds the correct
-        // commands
-        let new_state = State::new(Some(file_path));
+    /// Records the current time as last usage of the selected command and writes it to the
+    /// crow_db file.
+    pub fn mark_selected_command_used(&mut self) {
+        if let Some(id) = self.selected_command_id.clone() {
+            self.crow_commands
+                .commands_mut()
+                .mark_used(&id, unix_timestamp());
+            self.write_commands_to_db();
+        }
+    }
 
-        assert_eq!(new_state.crow_commands(), &crow_commands);
+    /// Whether commands are syntax highlighted inside the command detail
+    pub fn syntax_highlight(&self) -> bool {
+        self.config.syntax_highlight
+    }
 
-        std::fs::remove_file("./testdata/crow_tmp.json").unwrap();
+    /// Returns the configured appearance of the bordered blocks
+    pub fn block_style(&self) -> BlockStyle {
+        BlockStyle {
+            border_style: self.config.border_style,
+            show_titles: self.config.show_titles,
+        }
     }
 
-    #[test]
-    fn correctly_selects_command() {
-        let file_path = FilePath::new(Some("./testdata"), Some("crow.json"));
+    /// Whether line numbers are shown for multiline commands inside the command detail
+    pub fn line_numbers(&self) -> bool {
+        self.config.line_numbers
+    }
 
-        let mut state = State::new(Some(file_path));
+    /// Maximum number of characters of a description (see [Config::max_description_len])
+    pub fn max_description_len(&self) -> Option<usize> {
+        self.config.max_description_len
+    }
 
-        assert_eq!(state.command_list_state().selected(), Some(0));
-        assert_eq!(
-            state._selected_command_id(),
-            Some(&"test_command_1".to_string())
-        );
+    /// Number of columns between tab stops when commands are rendered
+    pub fn tab_width(&self) -> usize {
+        self.config.tab_width
+    }
 
-        state.select_command(1);
+    /// Whether the output which is printed after quitting the fuzzy mode is colored
+    pub fn color(&self) -> bool {
+        self.config.color
+    }
 
-        assert_eq!(state.command_list_state().selected(), Some(1));
-        assert_eq!(
-            state._selected_command_id(),
-            Some(&"test_command_2".to_string())
-        );
+    /// Whether the list selection wraps around at the top and bottom of the list
+    pub fn wrap_navigation(&self) -> bool {
+        self.config.wrap_navigation
     }
 
-    #[test]
-    fn correctly_sets_crow_commands() {
-        let file_path = FilePath::new(Some("./testdata"), Some("crow.json"));
+    /// Returns the code block copy format according to the config
+    pub fn code_block_format(&self) -> CopyFormat {
+        CopyFormat::CodeBlock {
+            with_description: self.config.code_block_description,
+        }
+    }
 
-        let state = State::new(Some(file_path));
+    /// Set the state's clipboard.
+    pub fn _set_clipboard(&mut self, clipboard: Box<dyn Clipboard>) {
+        self.clipboard = clipboard;
+    }
 
-        let crow_command_1 = CrowCommand {
-            id: "test_command_1".to_string(),
-            command: "echo 'hi from db'".to_string(),
-            description: "This is a test command".to_string(),
+    /// Get a mutable reference to the state's clipboard.
+    pub fn _clipboard_mut(&mut self) -> &mut dyn Clipboard {
+        self.clipboard.as_mut()
+    }
+
+    /// Set the state's input.
+    pub fn set_input(&mut self, input: String) {
+        self.input = input;
+    }
+
+    /// Clears the whole input like ctrl+u in readline and searches again
+    pub fn clear_input(&mut self) {
+        self.input.clear();
+        self.search();
+        self.select_command(0);
+    }
+
+    /// Gets the message of the error which has not been acknowledged yet
+    pub fn error(&self) -> Option<&String> {
+        self.error.as_ref()
+    }
+
+    /// Shows the given error message in a popup instead of terminating crow
+    pub fn set_error(&mut self, error: String) {
+        self.error = Some(error);
+    }
+
+    /// Hides the error popup
+    pub fn acknowledge_error(&mut self) {
+        self.error = None;
+    }
+
+    /// Gets the flash message which is shown inside the status bar
+    pub fn flash(&self) -> Option<&str> {
+        self.flash.as_ref().map(|(message, _)| message.as_str())
+    }
+
+    /// Shows the given message inside the status bar for a few seconds (see
+    /// [State::clear_expired_flash])
+    pub fn set_flash(&mut self, message: String) {
+        self.flash = Some((message, Instant::now()));
+    }
+
+    /// Hides the flash message if it has been shown long enough at the given time.
+    /// This is checked on every tick.
+    pub fn clear_expired_flash(&mut self, now: Instant) {
+        if matches!(&self.flash, Some((_, set_at)) if now.duration_since(*set_at) >= FLASH_DURATION)
+        {
+            self.flash = None;
+        }
+    }
+
+    /// Asks the user to confirm the given edit before it is saved (see [MenuItem::ConfirmEdit]).
+    /// Edits which did not change anything (besides trailing whitespace like the final line break
+    /// which editors add) are ignored.
+    pub fn propose_edit(&mut self, edit: PendingEdit) {
+        if edit.edited.trim_end() == edit.original.trim_end() {
+            return;
+        }
+
+        self.pending_edit = Some(edit);
+        self.set_active_menu_item(MenuItem::ConfirmEdit);
+    }
+
+    /// Gets the edit which waits for confirmation
+    pub fn pending_edit(&self) -> Option<&PendingEdit> {
+        self.pending_edit.as_ref()
+    }
+
+    /// Saves the pending edit to the crow_db file. An empty command is never saved, because it
+    /// couldn't be used for anything, so the original command is kept instead.
+    /// Returns to the find mode after the command has been changed and to the edit mode otherwise.
+    pub fn confirm_pending_edit(&mut self) {
+        let edit = match self.pending_edit.take() {
+            Some(edit) => edit,
+            None => return,
         };
-        let crow_command_2 = CrowCommand {
-            id: "test_command_2".to_string(),
-            command: "".to_string(),
-            description: "".to_string(),
+
+        let commands = self.crow_commands.commands_mut();
+        match edit.field {
+            EditField::Command if edit.is_empty() => {
+                self.set_flash(
+                    "Kept the original command, because it must not be empty".to_string(),
+                );
+                self.set_active_menu_item(MenuItem::Edit);
+                return;
+            }
+            EditField::Command => commands.update_command(edit.command_id, &edit.edited),
+            EditField::Description => commands.update_description(edit.command_id, &edit.edited),
+            EditField::Keywords => {
+                commands.update_keywords(edit.command_id, parse_keywords(&edit.edited))
+            }
+            EditField::Tags => commands.update_tags(edit.command_id, parse_keywords(&edit.edited)),
+        }
+
+        self.write_commands_to_db();
+
+        if edit.field == EditField::Command {
+            self.return_to_find();
+        } else {
+            self.set_active_menu_item(MenuItem::Edit);
+        }
+    }
+
+    /// Truncates the pending edit if it is a description which is longer than
+    /// [Config::max_description_len]. The truncated description still has to be confirmed.
+    pub fn truncate_pending_edit(&mut self) {
+        let max_len = self.config.max_description_len;
+
+        if let Some(edit) = self.pending_edit.as_mut() {
+            if let Some(truncated) = edit.truncated(max_len) {
+                edit.edited = truncated;
+            }
+        }
+    }
+
+    /// Drops the pending edit, so that the command stays unchanged, and returns to the edit mode
+    pub fn discard_pending_edit(&mut self) {
+        self.pending_edit = None;
+        self.set_active_menu_item(MenuItem::Edit);
+    }
+
+    /// Whether the fuzzy search scores are shown inside the command list
+    pub fn show_scores(&self) -> bool {
+        self.show_scores
+    }
+
+    /// Shows or hides the fuzzy search scores inside the command list
+    pub fn toggle_show_scores(&mut self) {
+        self.show_scores = !self.show_scores;
+        self.set_flash(if self.show_scores {
+            "Showing search scores".to_string()
+        } else {
+            "Hiding search scores".to_string()
+        });
+    }
+
+    /// Whether the first description line of every command is shown inside the command list
+    pub fn inline_descriptions(&self) -> bool {
+        self.inline_descriptions
+    }
+
+    /// Shows or hides the descriptions inside the command list
+    pub fn toggle_inline_descriptions(&mut self) {
+        self.inline_descriptions = !self.inline_descriptions;
+    }
+
+    /// Opens the edit mode for the selected command (e.g. the best match of the query of
+    /// `crow edit-search`). Stays in the find mode and tells the user if nothing is selected.
+    pub fn edit_selected_command(&mut self) {
+        if self.selected_crow_command().is_some() {
+            self.set_active_menu_item(MenuItem::Edit);
+        } else {
+            self.set_flash("No command matches the query".to_string());
+        }
+    }
+
+    /// Whether only commands with at least one tag are searched
+    pub fn tagged_only(&self) -> bool {
+        self.tagged_only
+    }
+
+    /// Restricts the search to commands with at least one tag (like the `has:tags` filter) or
+    /// searches all commands again, which is a quick way to work with a curated subset of a large
+    /// database
+    pub fn toggle_tagged_only(&mut self) {
+        self.tagged_only = !self.tagged_only;
+        self.search_and_select();
+    }
+
+    /// Opens the popup to add and remove tags of the selected command with an empty input, if a
+    /// command is selected
+    pub fn open_tags(&mut self) {
+        if self.selected_crow_command().is_none() {
+            return;
+        }
+
+        self.tag_input.clear();
+        self.set_active_menu_item(MenuItem::Tags);
+    }
+
+    /// Opens the command palette with an empty input
+    pub fn open_palette(&mut self) {
+        self.palette_input.clear();
+        self.palette_selection = 0;
+        self.set_active_menu_item(MenuItem::Palette);
+    }
+
+    /// Gets the user input of the command palette
+    pub fn palette_input(&self) -> &String {
+        &self.palette_input
+    }
+
+    /// Sets the user input of the command palette and selects the first matching action
+    pub fn set_palette_input(&mut self, input: String) {
+        self.palette_input = input;
+        self.palette_selection = 0;
+    }
+
+    /// Gets the index of the selected action inside the command palette
+    pub fn palette_selection(&self) -> usize {
+        self.palette_selection
+    }
+
+    /// Sets the index of the selected action inside the command palette
+    pub fn set_palette_selection(&mut self, selection: usize) {
+        self.palette_selection = selection;
+    }
+
+    /// Gets the user input of the tag popup
+    pub fn tag_input(&self) -> &String {
+        &self.tag_input
+    }
+
+    /// Returns mutable reference to the user input of the tag popup
+    pub fn mut_tag_input(&mut self) -> &mut String {
+        &mut self.tag_input
+    }
+
+    /// Adds the tag of the tag input to the selected command (or removes it if the command
+    /// already has the tag), clears the tag input and writes the change to the crow_db file.
+    pub fn toggle_tag_of_selected_command(&mut self) {
+        let tag = self.tag_input.trim().to_string();
+        self.tag_input.clear();
+
+        if let (Some(id), false) = (self.selected_command_id.clone(), tag.is_empty()) {
+            self.crow_commands.commands_mut().toggle_tag(&id, &tag);
+            self.write_commands_to_db();
+        }
+    }
+
+    /// Duplicates the selected command with a newly generated id (see [Config::id_scheme]),
+    /// writes it to the crow_db file and selects the duplicate.
+    pub fn duplicate_selected_command(&mut self) {
+        let command = match self.selected_crow_command() {
+            Some(c) => c.clone(),
+            None => return,
         };
-        let crow_commands = [crow_command_1, crow_command_2];
-        let crow_command_ids: Vec<Id> =
-            vec!["test_command_1".to_string(), "test_command_2".to_string()];
-        let expected = CrowCommands::_new(Commands::normalize(&crow_commands), crow_command_ids);
 
-        assert_eq!(state.crow_commands(), &expected);
+        let existing: Vec<CrowCommand> = self.crow_commands.commands().values().cloned().collect();
+        let new_id = self.config.id_scheme.generate(&command, &existing);
+
+        if self.crow_commands.duplicate(&command.id, new_id.clone()) {
+            self.write_commands_to_db();
+            self.set_selected_command_id(Some(new_id));
+        }
+    }
+
+    /// Set the state's detail scroll position.
+    pub fn set_detail_scroll_position(&mut self, detail_scroll_position: u16) {
+        self.detail_scroll_position = detail_scroll_position;
+    }
+
+    /// Get a reference to the state's detail scroll position.
+    pub fn detail_scroll_position(&self) -> u16 {
+        self.detail_scroll_position
+    }
+
+    /// Get whether the last key press was ESC.
+    pub fn escape_pressed(&self) -> bool {
+        self.escape_pressed
+    }
+
+    /// Set whether the last key press was ESC.
+    pub fn set_escape_pressed(&mut self, escape_pressed: bool) {
+        self.escape_pressed = escape_pressed;
+    }
+
+    /// Checks if there are any commands at all inside the state
+    pub fn has_crow_commands(&self) -> bool {
+        !self.crow_commands.commands().is_empty()
+    }
+
+    /// Get a reference to the state's fuzz result.
+    pub fn fuzz_result(&self) -> &FuzzResult {
+        &self.fuzz_result
+    }
+
+    /// Get a reference to the state's crow commands.
+    pub fn crow_commands(&self) -> &CrowCommands {
+        &self.crow_commands
+    }
+
+    /// Get a mutable reference to the state's crow commands.
+    pub fn crow_commands_mut(&mut self) -> &mut CrowCommands {
+        &mut self.crow_commands
+    }
+
+    /// Set the state's config.
+    pub fn set_config(&mut self, config: Config) {
+        self.inline_descriptions = config.inline_descriptions;
+        self.browse_sort = config.browse_sort;
+        self.config = config;
+    }
+
+    /// Get a reference to the state's db file path.
+    pub fn db_file_path(&self) -> &FilePath {
+        &self.db_file_path
+    }
+
+    /// Returns a connection to the crow_db file, which reads the commands which are currently
+    /// saved
+    pub fn connection(&self) -> CrowDBConnection {
+        CrowDBConnection::with_store(self.db_file_path.clone(), self.db_store.clone())
+    }
+
+    /// Get a reference to the state's selected command id.
+    pub fn _selected_command_id(&self) -> Option<&String> {
+        self.selected_command_id.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        num::NonZeroUsize,
+        path::Path,
+        time::{Duration, Instant},
+    };
+
+    use nanoid::nanoid;
+
+    use crate::{
+        clipboard::{CopyFormat, MemoryClipboard},
+        command_scores::{CommandScore, CommandScores},
+        config::{BrowseSort, Config, SortOrder},
+        crow_commands::{Commands, CrowCommand, CrowCommands, Id},
+        crow_db::{CrowDBConnection, FilePath, MemoryStore},
+        filter::{Field, Predicate},
+    };
+
+    use super::{EditField, MenuItem, PendingEdit, State};
+
+    /// Creates a state whose commands are only saved in the returned [MemoryStore]
+    fn memory_state(commands: Vec<CrowCommand>) -> (State, MemoryStore) {
+        let file_path = FilePath::new(Some("./testdata"), Some("memory.json"));
+        let store = MemoryStore::default();
+
+        CrowDBConnection::with_store(file_path.clone(), Box::new(store.clone()))
+            .set_commands(commands)
+            .write();
+
+        let mut state = State::default();
+        state.reload_from_store(file_path, Box::new(store.clone()));
+
+        (state, store)
+    }
+
+    /// Returns the commands which are currently saved inside the store
+    fn stored_commands(store: &MemoryStore) -> Vec<CrowCommand> {
+        let file_path = FilePath::new(Some("./testdata"), Some("memory.json"));
+
+        CrowDBConnection::with_store(file_path, Box::new(store.clone()))
+            .commands()
+            .to_vec()
     }
 
     #[test]
-    fn returns_denormalized_fuzz_result_if_exists() {
+    fn initializes_with_correct_data() {
         let file_path = FilePath::new(Some("./testdata"), Some("crow.json"));
 
-        let state = State::new(Some(file_path));
+        let state = State::new(file_path);
 
-        let crow_command_1 = CrowCommand {
+        assert_eq!(state.input(), "");
+        assert_eq!(&**state.db_file_path(), "./testdata/crow.json");
+
+        assert_eq!(state.detail_scroll_position(), 0);
+        assert_eq!(state.has_crow_commands(), true);
+        assert_eq!(state.command_list_state().selected().unwrap(), 0);
+    }
+
+    #[test]
+    fn writes_updated_state_to_db() {
+        let file_path = FilePath::new(Some("./testdata"), Some("crow_tmp.json"));
+
+        let mut state = State::new(file_path.clone());
+
+        let crow_command = CrowCommand {
             id: "test_command_1".to_string(),
             command: "echo 'hi from db'".to_string(),
             description: "This is a test command".to_string(),
+            ..Default::default()
         };
-        let crow_command_2 = CrowCommand {
-            id: "test_command_2".to_string(),
-            command: "".to_string(),
-            description: "".to_string(),
-        };
+        let commands = [crow_command];
+        let command_ids: Vec<Id> = vec!["test_command_1".to_string()];
+        let crow_commands = CrowCommands::_new(Commands::normalize(&commands), command_ids);
+        *state.crow_commands_mut() = crow_commands.clone();
 
-        let command_scores = CommandScores::normalize(&[
-            CommandScore::new(1, vec![], crow_command_1.id),
-            CommandScore::new(1, vec![], crow_command_2.id),
-        ]);
+        // Assert that current state holds correct commands
+        assert_eq!(state.crow_commands(), &crow_commands);
 
-        assert_eq!(state.fuzz_result().scores(), &command_scores);
-        assert!(state
-            .fuzz_result()
-            ._command_ids()
-            .contains(&"test_command_1".to_string()));
-        assert!(state
-            .fuzz_result()
-            ._command_ids()
-            .contains(&"test_command_2".to_string()));
+        state.write_commands_to_db();
+
+        // Assert that new state which also accesses the file holds the correct
+        // commands
+        let new_state = State::new(file_path);
+
+        assert_eq!(new_state.crow_commands(), &crow_commands);
+
+        std::fs::remove_file("./testdata/crow_tmp.json").unwrap();
     }
 
     #[test]
-    fn updates_fuzz_result_and_returns_it_if_not_exists() {
+    fn does_not_write_empty_state_over_populated_db() {
+        let (mut state, store) = memory_state(vec![CrowCommand {
+            id: "test_command_1".to_string(),
+            command: "echo 'hi from db'".to_string(),
+            ..Default::default()
+        }]);
+        *state.crow_commands_mut() = CrowCommands::_new(Commands::normalize(&[]), vec![]);
+
+        assert!(!state.write_commands_to_db());
+        assert!(state.error().is_some());
+        assert_eq!(stored_commands(&store).len(), 1);
+
+        state.force_write_commands_to_db();
+
+        assert!(stored_commands(&store).is_empty());
+    }
+
+    #[test]
+    fn reloads_commands_from_another_db_file() {
         let fn_path = &format!("./testdata/tmp/{}", nanoid!());
-        let file_path = FilePath::new(Some(fn_path), Some("crow.json"));
+        let other_file_path = FilePath::new(Some(fn_path), Some("crow.json"));
+
+        CrowDBConnection::new(other_file_path.clone())
+            .add_command(CrowCommand {
+                id: "other_command".to_string(),
+                command: "kubectl get pods".to_string(),
+                ..Default::default()
+            })
+            .write();
 
-        let state = State::new(Some(file_path));
+        let file_path = FilePath::new(Some("./testdata"), Some("crow.json"));
+        let mut state = State::new(file_path.clone());
 
-        let command_scores = CommandScores::normalize(&[]);
+        state.set_input("echo".to_string());
+        state.push_search_term();
+        state.set_input("hi".to_string());
+        state.search();
+        assert_eq!(state.crow_commands().commands().len(), 2);
 
-        assert_eq!(state.fuzz_result().scores(), &command_scores);
+        state.reload(other_file_path.clone());
+
+        assert_eq!(state.db_file_path(), &other_file_path);
+        assert_eq!(
+            state
+                .crow_commands()
+                .commands()
+                .keys()
+                .collect::<Vec<&Id>>(),
+            vec!["other_command"]
+        );
+        assert_eq!(state.input(), "");
+        assert!(state.search_terms().is_empty());
+        assert_eq!(state.visible_fuzz_result().len(), 1);
+        assert_eq!(
+            state._selected_command_id(),
+            Some(&"other_command".to_string())
+        );
+
+        state.reload(file_path);
+
+        assert_eq!(state.crow_commands().commands().len(), 2);
+        assert_eq!(
+            state._selected_command_id(),
+            Some(&"test_command_1".to_string())
+        );
 
         std::fs::remove_dir_all(Path::new(fn_path)).unwrap();
     }
+
+    #[test]
+    fn saves_edits_only_after_confirmation() {
+        let (mut state, store) = memory_state(vec![CrowCommand {
+            id: "test_command_1".to_string(),
+            command: "docker ps".to_string(),
+            description: "list containers".to_string(),
+            ..Default::default()
+        }]);
+        let id = "test_command_1".to_string();
+        let saved = |store: &MemoryStore| stored_commands(store)[0].clone();
+        let edit = |field: EditField, original: &str, edited: &str| PendingEdit {
+            command_id: id.clone(),
+            field,
+            original: original.to_string(),
+            edited: edited.to_string(),
+        };
+
+        // Unchanged edits are not shown
+        state.set_active_menu_item(MenuItem::Edit);
+        state.propose_edit(edit(
+            EditField::Description,
+            "list containers",
+            "list containers\n",
+        ));
+        assert!(state.pending_edit().is_none());
+        assert!(matches!(state.active_menu_item(), MenuItem::Edit));
+
+        state.propose_edit(edit(
+            EditField::Description,
+            "list containers",
+            "show containers",
+        ));
+        assert!(matches!(state.active_menu_item(), MenuItem::ConfirmEdit));
+        assert_eq!(saved(&store).description, "list containers");
+
+        state.discard_pending_edit();
+        assert!(state.pending_edit().is_none());
+        assert_eq!(saved(&store).description, "list containers");
+
+        state.propose_edit(edit(
+            EditField::Description,
+            "list containers",
+            "show containers",
+        ));
+        state.confirm_pending_edit();
+        assert!(matches!(state.active_menu_item(), MenuItem::Edit));
+        assert_eq!(saved(&store).description, "show containers");
+
+        // An empty command is never saved
+        state.propose_edit(edit(EditField::Command, "docker ps", " \n"));
+        state.confirm_pending_edit();
+        assert_eq!(saved(&store).command, "docker ps");
+
+        state.propose_edit(edit(EditField::Tags, "", "Docker, ops, docker"));
+        state.confirm_pending_edit();
+        assert!(matches!(state.active_menu_item(), MenuItem::Edit));
+        assert_eq!(saved(&store).tags, vec!["docker", "ops"]);
+
+        state.propose_edit(edit(EditField::Command, "docker ps", "docker ps -a"));
+        state.confirm_pending_edit();
+        assert!(matches!(state.active_menu_item(), MenuItem::Find));
+        assert_eq!(saved(&store).command, "docker ps -a");
+    }
+
+    #[test]
+    fn truncates_long_descriptions_of_pending_edits() {
+        let (mut state, store) = memory_state(vec![CrowCommand {
+            id: "test_command_1".to_string(),
+            command: "docker ps".to_string(),
+            ..Default::default()
+        }]);
+        state.set_config(Config {
+            max_description_len: Some(15),
+            ..Default::default()
+        });
+        let edit = |field: EditField, edited: &str| PendingEdit {
+            command_id: "test_command_1".to_string(),
+            field,
+            original: "".to_string(),
+            edited: edited.to_string(),
+        };
+
+        // Only descriptions are truncated
+        state.propose_edit(edit(EditField::Keywords, "containers, docker, ps"));
+        state.truncate_pending_edit();
+        assert_eq!(
+            state.pending_edit().map(|e| e.edited.as_str()),
+            Some("containers, docker, ps")
+        );
+
+        state.propose_edit(edit(
+            EditField::Description,
+            "list containers of all projects",
+        ));
+        state.truncate_pending_edit();
+        assert_eq!(
+            state.pending_edit().map(|e| e.edited.as_str()),
+            Some("list containers")
+        );
+        assert_eq!(stored_commands(&store)[0].description, "");
+
+        state.confirm_pending_edit();
+        assert_eq!(stored_commands(&store)[0].description, "list containers");
+    }
+
+    #[test]
+    fn searches_only_tagged_commands() {
+        let (mut state, _) = memory_state(vec![
+            CrowCommand::test("status", "git status"),
+            CrowCommand::test("log", "git log").with_tags(&["git"]),
+            CrowCommand::test("push", "git push"),
+        ]);
+        state.set_input("git".to_string());
+        state.search_and_select();
+        assert_eq!(state.visible_fuzz_result().len(), 3);
+
+        state.toggle_tagged_only();
+
+        assert!(state.tagged_only());
+        assert_eq!(state.visible_fuzz_result().len(), 1);
+        assert_eq!(state._selected_command_id(), Some(&"log".to_string()));
+
+        state.toggle_tagged_only();
+
+        assert_eq!(state.visible_fuzz_result().len(), 3);
+    }
+
+    #[test]
+    fn orders_equally_scored_results_by_usage() {
+        let (mut state, _) = memory_state(vec![
+            CrowCommand::test("rare", "git status").with_use_count(1),
+            CrowCommand::test("frequent", "git status").with_use_count(5),
+        ]);
+        state.set_input("status".to_string());
+        state.search();
+
+        let ids = |state: &mut State| {
+            state
+                .fuzz_result_or_all()
+                .iter()
+                .map(|c| c.command_id().clone())
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(ids(&mut state), vec!["rare", "frequent"]);
+        assert!(state.can_move_commands());
+
+        state.set_browse_sort(BrowseSort::Usage);
+
+        assert_eq!(ids(&mut state), vec!["frequent", "rare"]);
+        assert!(!state.can_move_commands());
+    }
+
+    #[test]
+    fn toggles_inline_descriptions() {
+        let (mut state, _) = memory_state(vec![]);
+        assert!(!state.inline_descriptions());
+
+        state.set_config(Config {
+            inline_descriptions: true,
+            ..Default::default()
+        });
+        assert!(state.inline_descriptions());
+
+        state.toggle_inline_descriptions();
+        assert!(!state.inline_descriptions());
+    }
+
+    #[test]
+    fn edits_best_match_of_query() {
+        let (mut state, _) = memory_state(vec![
+            CrowCommand::test("1", "docker ps"),
+            CrowCommand::test("2", "git psuh origin"),
+        ]);
+
+        state.set_input("git psh".to_string());
+        state.search();
+        state.select_command(0);
+        state.edit_selected_command();
+
+        assert!(matches!(state.active_menu_item(), MenuItem::Edit));
+        assert_eq!(state._selected_command_id(), Some(&"2".to_string()));
+
+        state.return_to_find();
+        state.set_input("zzzzzz".to_string());
+        state.search();
+        state.select_command(0);
+        state.edit_selected_command();
+
+        assert!(matches!(state.active_menu_item(), MenuItem::Find));
+        assert!(state.flash().is_some());
+    }
+
+    #[test]
+    fn correctly_selects_command() {
+        let file_path = FilePath::new(Some("./testdata"), Some("crow.json"));
+
+        let mut state = State::new(file_path);
+
+        assert_eq!(state.command_list_state().selected(), Some(0));
+        assert_eq!(
+            state._selected_command_id(),
+            Some(&"test_command_1".to_string())
+        );
+
+        state.select_command(1);
+
+        assert_eq!(state.command_list_state().selected(), Some(1));
+        assert_eq!(
+            state._selected_command_id(),
+            Some(&"test_command_2".to_string())
+        );
+    }
+
+    #[test]
+    fn correctly_sets_crow_commands() {
+        let file_path = FilePath::new(Some("./testdata"), Some("crow.json"));
+
+        let state = State::new(file_path);
+
+        let crow_command_1 = CrowCommand {
+            id: "test_command_1".to_string(),
+            command: "echo 'hi from db'".to_string(),
+            description: "This is a test command".to_string(),
+            ..Default::default()
+        };
+        let crow_command_2 = CrowCommand {
+            id: "test_command_2".to_string(),
+            command: "".to_string(),
+            description: "".to_string(),
+            ..Default::default()
+        };
+        let crow_commands = [crow_command_1, crow_command_2];
+        let crow_command_ids: Vec<Id> =
+            vec!["test_command_1".to_string(), "test_command_2".to_string()];
+        let expected = CrowCommands::_new(Commands::normalize(&crow_commands), crow_command_ids);
+
+        assert_eq!(state.crow_commands(), &expected);
+    }
+
+    #[test]
+    fn returns_denormalized_fuzz_result_if_exists() {
+        let file_path = FilePath::new(Some("./testdata"), Some("crow.json"));
+
+        let state = State::new(file_path);
+
+        let crow_command_1 = CrowCommand {
+            id: "test_command_1".to_string(),
+            command: "echo 'hi from db'".to_string(),
+            description: "This is a test command".to_string(),
+            ..Default::default()
+        };
+        let crow_command_2 = CrowCommand {
+            id: "test_command_2".to_string(),
+            command: "".to_string(),
+            description: "".to_string(),
+            ..Default::default()
+        };
+
+        let command_scores = CommandScores::normalize(&[
+            CommandScore::new(1, vec![], crow_command_1.id),
+            CommandScore::new(1, vec![], crow_command_2.id),
+        ]);
+
+        assert_eq!(state.fuzz_result().scores(), &command_scores);
+        assert!(state
+            .fuzz_result()
+            ._command_ids()
+            .contains(&"test_command_1".to_string()));
+        assert!(state
+            .fuzz_result()
+            ._command_ids()
+            .contains(&"test_command_2".to_string()));
+    }
+
+    #[test]
+    fn moves_selected_command_and_persists_order() {
+        let fn_path = &format!("./testdata/tmp/{}", nanoid!());
+        let file_path = FilePath::new(Some(fn_path), Some("crow.json"));
+
+        let mut state = State::new(file_path.clone());
+
+        let crow_command_1 = CrowCommand {
+            id: "test_command_1".to_string(),
+            command: "echo 'first'".to_string(),
+            ..Default::default()
+        };
+        let crow_command_2 = CrowCommand {
+            id: "test_command_2".to_string(),
+            command: "echo 'second'".to_string(),
+            ..Default::default()
+        };
+        let command_ids: Vec<Id> = vec!["test_command_1".to_string(), "test_command_2".to_string()];
+        *state.crow_commands_mut() = CrowCommands::_new(
+            Commands::normalize(&[crow_command_1.clone(), crow_command_2.clone()]),
+            command_ids,
+        );
+        state.select_command(1);
+
+        state.move_selected_command_up();
+
+        assert_eq!(state.command_list_state().selected(), Some(0));
+        assert_eq!(
+            state._selected_command_id(),
+            Some(&"test_command_2".to_string())
+        );
+
+        // Moving the first command further up does nothing
+        state.move_selected_command_up();
+        assert_eq!(state.command_list_state().selected(), Some(0));
+
+        let new_state = State::new(file_path);
+        let persisted: Vec<CrowCommand> = new_state
+            .crow_commands()
+            .commands()
+            .denormalize()
+            .cloned()
+            .collect();
+        assert_eq!(persisted, vec![crow_command_2, crow_command_1]);
+
+        std::fs::remove_dir_all(Path::new(fn_path)).unwrap();
+    }
+
+    #[test]
+    fn does_not_move_commands_if_the_database_is_sorted() {
+        let (mut state, store) = memory_state(vec![
+            CrowCommand::test("a", "echo a"),
+            CrowCommand::test("b", "echo b"),
+        ]);
+        assert!(state.can_move_commands());
+
+        state.set_config(Config {
+            sort_db: Some(SortOrder::Command),
+            ..Default::default()
+        });
+        assert!(!state.can_move_commands());
+
+        // Every write sorts the database again, so a move would silently be undone
+        state.select_command(1);
+        state.move_selected_command_up();
+        let ids: Vec<Id> = stored_commands(&store).into_iter().map(|c| c.id).collect();
+        assert_eq!(ids, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn moves_commands_by_id_inside_filtered_list() {
+        let (mut state, store) = memory_state(vec![
+            CrowCommand::test("a", "echo a").with_tags(&["tag"]),
+            CrowCommand::test("b", "echo b"),
+            CrowCommand::test("c", "echo c").with_tags(&["tag"]),
+        ]);
+        assert!(state.can_move_commands());
+
+        state.toggle_tagged_only();
+        assert!(!state.can_move_commands());
+
+        state.select_command(1);
+        assert_eq!(state._selected_command_id(), Some(&"c".to_string()));
+
+        state.move_selected_command_up();
+
+        assert_eq!(state._selected_command_id(), Some(&"c".to_string()));
+        assert_eq!(state.command_list_state().selected(), Some(0));
+        let ids: Vec<Id> = stored_commands(&store).into_iter().map(|c| c.id).collect();
+        assert_eq!(ids, vec!["c", "b", "a"]);
+
+        // The last listed command can't be moved further down, even though it is not the last
+        // stored command
+        state.select_command(1);
+        state.move_selected_command_down();
+        assert_eq!(state._selected_command_id(), Some(&"a".to_string()));
+        let ids: Vec<Id> = stored_commands(&store).into_iter().map(|c| c.id).collect();
+        assert_eq!(ids, vec!["c", "b", "a"]);
+    }
+
+    #[test]
+    fn updates_fuzz_result_and_returns_it_if_not_exists() {
+        let fn_path = &format!("./testdata/tmp/{}", nanoid!());
+        let file_path = FilePath::new(Some(fn_path), Some("crow.json"));
+
+        let state = State::new(file_path);
+
+        let command_scores = CommandScores::normalize(&[]);
+
+        assert_eq!(state.fuzz_result().scores(), &command_scores);
+
+        std::fs::remove_dir_all(Path::new(fn_path)).unwrap();
+    }
+
+    #[test]
+    fn applies_filters_and_predicates_from_input() {
+        let file_path = FilePath::new(Some("./testdata"), Some("crow.json"));
+
+        let mut state = State::new(file_path);
+
+        state.set_filters(vec![Predicate::Has(Field::Description)]);
+        state.set_fuzz_result(vec![]);
+        state.select_command(0);
+
+        let ids: Vec<Id> = state
+            .fuzz_result_or_all()
+            .iter()
+            .map(|c| c.command_id().clone())
+            .collect();
+        assert_eq!(ids, vec!["test_command_1".to_string()]);
+
+        state.set_filters(vec![]);
+        state.set_input("id:test_command_2".to_string());
+        state.search();
+
+        let ids: Vec<Id> = state
+            .fuzz_result_or_all()
+            .iter()
+            .map(|c| c.command_id().clone())
+            .collect();
+        assert_eq!(ids, vec!["test_command_2".to_string()]);
+    }
+
+    #[test]
+    fn matches_all_search_terms() {
+        let file_path = FilePath::new(Some("./testdata"), Some("crow.json"));
+
+        let mut state = State::new(file_path);
+
+        let commands = [
+            CrowCommand::test("1", "git status"),
+            CrowCommand::test("2", "git log"),
+            CrowCommand::test("3", "docker logs"),
+        ];
+        let command_ids: Vec<Id> = commands.iter().map(|c| c.id.clone()).collect();
+        *state.crow_commands_mut() =
+            CrowCommands::_new(Commands::normalize(&commands), command_ids);
+
+        let result_ids = |state: &mut State| -> Vec<Id> {
+            state
+                .fuzz_result_or_all()
+                .iter()
+                .map(|c| c.command_id().clone())
+                .collect()
+        };
+
+        state.set_input("git".to_string());
+        state.push_search_term();
+
+        assert_eq!(state.input(), "");
+        assert_eq!(state.search_terms(), &["git".to_string()]);
+        assert_eq!(result_ids(&mut state), vec!["1", "2"]);
+
+        state.set_input("log".to_string());
+        state.search();
+
+        assert_eq!(result_ids(&mut state), vec!["2"]);
+
+        state.pop_search_term();
+
+        assert_eq!(state.search_terms().len(), 0);
+        assert_eq!(result_ids(&mut state), vec!["2", "3"]);
+    }
+
+    #[test]
+    fn limits_visible_results_to_max_results() {
+        let file_path = FilePath::new(Some("./testdata"), Some("crow.json"));
+
+        let mut state = State::new(file_path);
+
+        assert_eq!(state.visible_fuzz_result().len(), 2);
+        assert_eq!(state.hidden_result_count(), 0);
+
+        state.set_config(Config {
+            max_results: NonZeroUsize::new(1),
+            ..Default::default()
+        });
+
+        assert_eq!(state.visible_fuzz_result().len(), 1);
+        assert_eq!(state.hidden_result_count(), 1);
+
+        // The hidden result can't be selected
+        state.select_command(1);
+        let visible_id = state.visible_fuzz_result()[0].command_id().clone();
+        assert_eq!(state.command_list_state().selected(), Some(0));
+        assert_eq!(state._selected_command_id(), Some(&visible_id));
+    }
+
+    #[test]
+    fn copies_selected_command_to_clipboard() {
+        let file_path = FilePath::new(Some("./testdata"), Some("crow.json"));
+
+        let mut state = State::new(file_path);
+        state._set_clipboard(Box::new(MemoryClipboard::default()));
+
+        assert_eq!(
+            state.copy_selected_command(CopyFormat::Command),
+            Ok(Some("echo 'hi from db'".to_string()))
+        );
+        assert_eq!(
+            state._clipboard_mut().get_contents(),
+            Ok("echo 'hi from db'".to_string())
+        );
+    }
+
+    #[test]
+    fn copies_selection_once_it_stopped_changing() {
+        let (mut state, _) = memory_state(vec![
+            CrowCommand::test("1", "echo 1"),
+            CrowCommand::test("2", "echo 2"),
+            CrowCommand::test("3", "echo 3"),
+        ]);
+        state._set_clipboard(Box::new(MemoryClipboard::default()));
+        state.set_config(Config {
+            copy_on_select: true,
+            ..Default::default()
+        });
+        let settled = || Instant::now() + Duration::from_secs(1);
+
+        // Nothing is copied before the selection changes
+        assert_eq!(state.copy_settled_selection(settled()), Ok(None));
+
+        state.select_command(1);
+        assert_eq!(state.copy_settled_selection(Instant::now()), Ok(None));
+        state.select_command(2);
+        assert_eq!(
+            state.copy_settled_selection(settled()),
+            Ok(Some("echo 3".to_string()))
+        );
+        assert_eq!(
+            state._clipboard_mut().get_contents(),
+            Ok("echo 3".to_string())
+        );
+        assert_eq!(state.copy_settled_selection(settled()), Ok(None));
+
+        // Quitting copies a pending selection right away
+        state.select_command(0);
+        assert_eq!(
+            state.copy_pending_selection(),
+            Ok(Some("echo 1".to_string()))
+        );
+        assert_eq!(state.copy_pending_selection(), Ok(None));
+    }
+
+    #[test]
+    fn copies_with_description_without_quitting() {
+        let (mut state, store) = memory_state(vec![
+            CrowCommand::test("1", "ls -la").with_description("list all\nfiles"),
+            CrowCommand::test("2", "git status"),
+        ]);
+        state._set_clipboard(Box::new(MemoryClipboard::default()));
+        state.set_input("ls".to_string());
+        state.search_and_select();
+
+        state.copy_selected_command_with_description();
+        assert_eq!(state.flash(), Some("Copied: # list all␊# files␊ls -la"));
+        assert_eq!(
+            state._clipboard_mut().get_contents(),
+            Ok("# list all\n# files\nls -la".to_string())
+        );
+        assert_eq!(stored_commands(&store)[0].use_count, 1);
+
+        state.clear_input();
+        assert_eq!(state.input(), "");
+        assert_eq!(state.visible_fuzz_result().len(), 2);
+        assert_eq!(state._selected_command_id(), Some(&"1".to_string()));
+    }
+
+    #[test]
+    fn verifies_clipboard_contents_if_enabled() {
+        let file_path = FilePath::new(Some("./testdata"), Some("crow.json"));
+
+        let mut state = State::new(file_path);
+        state._set_clipboard(Box::new(MemoryClipboard::default()));
+
+        let copied = state
+            .copy_selected_command(CopyFormat::Command)
+            .unwrap()
+            .unwrap();
+
+        // A clipboard manager transforms the content
+        state
+            ._clipboard_mut()
+            .set_contents("something else".to_string())
+            .unwrap();
+
+        assert_eq!(state.verify_clipboard(&copied), None);
+
+        state.set_config(Config {
+            verify_clipboard: true,
+            ..Default::default()
+        });
+
+        assert!(state.verify_clipboard(&copied).is_some());
+        assert_eq!(state.verify_clipboard("something else"), None);
+    }
+
+    #[test]
+    fn returns_no_selected_command_after_removal() {
+        let file_path = FilePath::new(Some("./testdata"), Some("crow.json"));
+
+        let mut state = State::new(file_path);
+
+        assert!(state.selected_crow_command().is_some());
+
+        state
+            .crow_commands_mut()
+            .commands_mut()
+            .shift_remove(&"test_command_1".to_string());
+
+        assert_eq!(
+            state._selected_command_id(),
+            Some(&"test_command_1".to_string())
+        );
+        assert_eq!(state.selected_crow_command(), None);
+    }
+
+    #[test]
+    fn keeps_selection_in_range_when_results_shrink() {
+        let file_path = FilePath::new(Some("./testdata"), Some("crow.json"));
+
+        let mut state = State::new(file_path);
+        state.select_command(1);
+
+        state.set_input("hi from db".to_string());
+        state.search();
+
+        assert_eq!(state.fuzz_result_or_all().len(), 1);
+        assert_eq!(state.command_list_state().selected(), Some(0));
+        assert_eq!(
+            state._selected_command_id(),
+            Some(&"test_command_1".to_string())
+        );
+
+        state.set_input("does not match anything".to_string());
+        state.search();
+
+        assert_eq!(state.command_list_state().selected(), None);
+        assert_eq!(state.selected_crow_command(), None);
+    }
+
+    #[test]
+    fn selects_nothing_for_empty_db() {
+        let fn_path = &format!("./testdata/tmp/{}", nanoid!());
+        let file_path = FilePath::new(Some(fn_path), Some("crow.json"));
+
+        let mut state = State::new(file_path);
+
+        assert!(!state.has_crow_commands());
+        assert_eq!(state.command_list_state().selected(), None);
+        assert_eq!(state._selected_command_id(), None);
+
+        state.select_command(0);
+
+        assert_eq!(state.command_list_state().selected(), None);
+        assert_eq!(state.selected_crow_command(), None);
+
+        std::fs::remove_dir_all(Path::new(fn_path)).unwrap();
+    }
+
+    #[test]
+    fn selects_and_copies_added_command_after_typing_query() {
+        let fn_path = &format!("./testdata/tmp/{}", nanoid!());
+        let file_path = FilePath::new(Some(fn_path), Some("crow.json"));
+
+        let mut connection = CrowDBConnection::new(file_path.clone());
+        connection
+            .add_command(CrowCommand::test("1", "ls -la").with_description("list all files"))
+            .add_command(
+                CrowCommand::test("2", "docker ps -a").with_description("list all containers"),
+            )
+            .add_command(CrowCommand::test("3", "git status").with_description("show repo status"))
+            .write();
+
+        let mut state = State::new(file_path);
+        state._set_clipboard(Box::new(MemoryClipboard::default()));
+
+        // Typing the query char by char the way the input handler does
+        for c in "dockr".chars() {
+            state.mut_input().push(c);
+            state.search();
+            state.select_command(0);
+        }
+
+        assert_eq!(state.visible_fuzz_result().len(), 1);
+        assert_eq!(
+            state.selected_crow_command(),
+            Some(&CrowCommand::test("2", "docker ps -a").with_description("list all containers"))
+        );
+        assert_eq!(
+            state.copy_selected_command(CopyFormat::Command),
+            Ok(Some("docker ps -a".to_string()))
+        );
+        assert_eq!(
+            state._clipboard_mut().get_contents(),
+            Ok("docker ps -a".to_string())
+        );
+
+        // Removing the query selects the first command of the full list again
+        while state.mut_input().pop().is_some() {
+            state.search();
+            state.select_command(0);
+        }
+
+        assert_eq!(state.visible_fuzz_result().len(), 3);
+        assert_eq!(
+            state.selected_crow_command().map(|c| c.id.as_str()),
+            Some("1")
+        );
+
+        std::fs::remove_dir_all(Path::new(fn_path)).unwrap();
+    }
+
+    #[test]
+    fn searches_again_when_returning_to_find() {
+        let file_path = FilePath::new(Some("./testdata"), Some("crow.json"));
+
+        let mut state = State::new(file_path);
+        state.set_input("podman".to_string());
+        state.search();
+        state.select_command(0);
+
+        assert_eq!(state.visible_fuzz_result().len(), 0);
+
+        // Editing the command in edit mode makes it match the preserved input
+        state.set_active_menu_item(MenuItem::Edit);
+        state
+            .crow_commands_mut()
+            .commands_mut()
+            .update_command("test_command_1".to_string(), "podman ps");
+
+        state.return_to_find();
+
+        assert!(matches!(state.active_menu_item(), MenuItem::Find));
+        assert_eq!(state.input(), "podman");
+        assert_eq!(state.visible_fuzz_result().len(), 1);
+        assert_eq!(
+            state._selected_command_id(),
+            Some(&"test_command_1".to_string())
+        );
+
+        // Cancelling the delete mode keeps the input and the result
+        state.set_active_menu_item(MenuItem::Delete);
+        state.return_to_find();
+
+        assert_eq!(state.visible_fuzz_result().len(), 1);
+        assert_eq!(
+            state._selected_command_id(),
+            Some(&"test_command_1".to_string())
+        );
+    }
+
+    #[test]
+    fn reselects_remembered_command_when_query_is_entered_again() {
+        let fn_path = &format!("./testdata/tmp/{}", nanoid!());
+        let file_path = FilePath::new(Some(fn_path), Some("crow.json"));
+
+        let mut connection = CrowDBConnection::new(file_path.clone());
+        connection
+            .add_command(CrowCommand::test("1", "git status"))
+            .add_command(CrowCommand::test("2", "git log"))
+            .add_command(CrowCommand::test("3", "ls -la"))
+            .write();
+
+        let mut state = State::new(file_path.clone());
+        state.set_config(Config {
+            remember_selection: true,
+            ..Default::default()
+        });
+
+        let type_query = |state: &mut State, query: &str| {
+            state.set_input(String::new());
+            for c in query.chars() {
+                state.mut_input().push(c);
+                state.search_and_select();
+            }
+        };
+
+        type_query(&mut state, "git");
+        assert_eq!(state.visible_fuzz_result().len(), 2);
+        let first_id = state._selected_command_id().cloned();
+
+        state.select_command(1);
+        state.remember_selection();
+        let remembered_id = state._selected_command_id().cloned();
+        assert_ne!(remembered_id, first_id);
+
+        type_query(&mut state, "ls");
+        assert_eq!(state._selected_command_id(), Some(&"3".to_string()));
+
+        type_query(&mut state, "git");
+        assert_eq!(state._selected_command_id().cloned(), remembered_id);
+
+        // Without the config option the first command is selected again
+        let mut state = State::new(file_path);
+        type_query(&mut state, "git");
+        state.select_command(1);
+        state.remember_selection();
+        type_query(&mut state, "git");
+        assert_eq!(state._selected_command_id().cloned(), first_id);
+
+        std::fs::remove_dir_all(Path::new(fn_path)).unwrap();
+    }
+
+    #[test]
+    fn reloads_commands_changed_by_another_program() {
+        let fn_path = &format!("./testdata/tmp/{}", nanoid!());
+        let file_path = FilePath::new(Some(fn_path), Some("crow.json"));
+
+        let mut connection = CrowDBConnection::new(file_path.clone());
+        connection
+            .add_command(CrowCommand::test("1", "ls -la"))
+            .write();
+
+        let mut state = State::new(file_path.clone());
+        state.set_input("git".to_string());
+        state.search();
+
+        assert_eq!(state.reload_if_changed(), Ok(false));
+        assert_eq!(state.visible_fuzz_result().len(), 0);
+
+        let mut connection = CrowDBConnection::new(file_path.clone());
+        connection
+            .add_command(CrowCommand::test("2", "git status"))
+            .write();
+
+        assert_eq!(state.reload_if_changed(), Ok(true));
+        assert_eq!(state.visible_fuzz_result().len(), 1);
+        assert_eq!(state._selected_command_id(), Some(&"2".to_string()));
+
+        // Writing the state afterwards keeps the external change
+        state.mark_selected_command_used();
+        assert_eq!(state.reload_if_changed(), Ok(false));
+        assert_eq!(CrowDBConnection::new(file_path.clone()).commands().len(), 2);
+
+        // Invalid changes (e.g. by editing the file by hand) are reported once and not loaded
+        std::fs::write(file_path.as_path(), "{\"commands\": [").unwrap();
+
+        assert!(state.reload_if_changed().is_err());
+        assert_eq!(state.reload_if_changed(), Ok(false));
+        assert_eq!(state.crow_commands().commands().len(), 2);
+
+        // The invalid file is not overwritten by the next change
+        state.mark_selected_command_used();
+        assert!(state.error().is_some());
+        assert_eq!(
+            std::fs::read_to_string(file_path.as_path()).unwrap(),
+            "{\"commands\": ["
+        );
+
+        // Once the file has been fixed, it is reloaded and written again
+        std::fs::write(
+            file_path.as_path(),
+            r#"{"commands": [{"id": "3", "command": "git log", "description": ""}]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(state.reload_if_changed(), Ok(true));
+        state.mark_selected_command_used();
+        assert_eq!(CrowDBConnection::new(file_path.clone()).commands().len(), 1);
+
+        std::fs::remove_dir_all(Path::new(fn_path)).unwrap();
+    }
+
+    #[test]
+    fn keeps_selection_while_typing_if_configured() {
+        let fn_path = &format!("./testdata/tmp/{}", nanoid!());
+        let file_path = FilePath::new(Some(fn_path), Some("crow.json"));
+
+        let mut connection = CrowDBConnection::new(file_path.clone());
+        connection
+            .add_command(CrowCommand::test("1", "git status"))
+            .add_command(CrowCommand::test("2", "git log"))
+            .add_command(CrowCommand::test("3", "ls -la"))
+            .write();
+
+        let mut state = State::new(file_path);
+        state.set_config(Config {
+            reset_selection_on_type: false,
+            ..Default::default()
+        });
+
+        state.set_input("gi".to_string());
+        state.search_and_select();
+        let index = state
+            .visible_fuzz_result()
+            .iter()
+            .position(|c| c.command_id() == "2")
+            .unwrap();
+        state.select_command(index);
+
+        state.mut_input().push('t');
+        state.search_and_select();
+        assert_eq!(state.visible_fuzz_result().len(), 2);
+        assert_eq!(state._selected_command_id(), Some(&"2".to_string()));
+
+        // The first command is selected if the selected command does not match anymore
+        state.set_input("ls".to_string());
+        state.search_and_select();
+        assert_eq!(state._selected_command_id(), Some(&"3".to_string()));
+
+        // By default the first command is selected after every change
+        state.set_config(Config::default());
+        state.set_input("git".to_string());
+        state.search_and_select();
+        state.select_command(1);
+        let second_id = state._selected_command_id().cloned();
+        state.mut_input().push(' ');
+        state.search_and_select();
+        assert_eq!(state.command_list_state().selected(), Some(0));
+        assert_ne!(state._selected_command_id().cloned(), second_id);
+
+        std::fs::remove_dir_all(Path::new(fn_path)).unwrap();
+    }
+
+    #[test]
+    fn clears_flash_after_it_expired() {
+        let file_path = FilePath::new(Some("./testdata"), Some("crow.json"));
+
+        let mut state = State::new(file_path);
+        assert_eq!(state.flash(), None);
+
+        state.set_flash("Duplicated command".to_string());
+        state.clear_expired_flash(std::time::Instant::now());
+        assert_eq!(state.flash(), Some("Duplicated command"));
+
+        state.clear_expired_flash(std::time::Instant::now() + super::FLASH_DURATION);
+        assert_eq!(state.flash(), None);
+    }
 }